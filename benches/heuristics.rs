@@ -0,0 +1,71 @@
+//! Compares how many candidates the search expands with each heuristic, since
+//! a tighter bound should expand fewer of them to reach the same distance:
+//!
+//! ```
+//! cargo bench --bench heuristics
+//! cargo bench --bench heuristics -- 5
+//! ```
+
+use std::env;
+use std::time::Instant;
+use supermutation::heuristic_fn::{TailAware, Zero};
+use supermutation::{AnalyticBound, Candidate, ClosedSet, Heuristic, HeuristicFn, Incremental, OpenSet, PatternDatabase, Problem, Search};
+
+// The number of symbols and the number of perms to search to, one subgoal at
+// a time.
+const CASES: &[(usize, usize)] = &[(4, 12), (4, 24), (5, 120), (6, 100)];
+
+// A breadth-first search grows too quickly to go further than this.
+const ZERO_LIMIT: usize = 12;
+
+type NewEstimate = fn(&Problem) -> Box<dyn HeuristicFn>;
+
+const ESTIMATES: &[(&str, NewEstimate)] = &[
+    ("learned", |problem| Box::new(Heuristic::seed(problem))),
+    ("tail", |problem| Box::new(TailAware::new(Heuristic::seed(problem)))),
+    ("analytic", |problem| Box::new(Heuristic::seed(problem).max(AnalyticBound::new(problem)))),
+    ("pattern", |problem| Box::new(Heuristic::seed(problem).max(PatternDatabase::build(problem)))),
+    ("zero", |_| Box::new(Zero)),
+];
+
+fn main() {
+    let symbols = env::args().skip(1).filter_map(|a| a.parse().ok()).collect::<Vec<usize>>();
+
+    for &(n, subgoal) in CASES {
+        if symbols.is_empty() || symbols.contains(&n) {
+            bench(n, subgoal);
+        }
+    }
+}
+
+fn bench(n: usize, subgoal: usize) {
+    let problem = Problem::new(n);
+    let mut baseline = None;
+
+    println!("N={} to {} perms", n, subgoal);
+
+    for (name, new_estimate) in ESTIMATES {
+        if *name == "zero" && subgoal > ZERO_LIMIT {
+            continue;
+        }
+
+        let search = Search::new(&problem, OpenSet::new(), ClosedSet::new());
+        let mut incremental = Incremental::new(Heuristic::seed(&problem), search);
+
+        incremental.stop_at(subgoal);
+        incremental.use_heuristic_fn(new_estimate(&problem));
+
+        let start = Instant::now();
+        let distance = incremental.shortest_path(Candidate::seed(&problem), |_, _, _, _, _, _| {});
+        let seconds = start.elapsed().as_secs_f64();
+        let expansions = incremental.search().expansions();
+        let baseline = *baseline.get_or_insert(expansions);
+
+        println!(
+            "  {:<12}{:>12} expansions {:>10.2}x learned {:>10.3}s  distance {:?}",
+            name, expansions, expansions as f64 / baseline as f64, seconds, distance,
+        );
+    }
+
+    println!();
+}
@@ -0,0 +1,171 @@
+//! Times the operations that each expansion of the search is made of, and a
+//! whole search to a fixed subgoal, with each representation of candidates so
+//! that regressions are visible:
+//!
+//! ```
+//! cargo bench
+//! cargo bench -- 6
+//! cargo bench -- 6 roaring
+//! ```
+
+use std::env;
+use std::hint::black_box;
+use std::time::Instant;
+use supermutation::{Candidate, ClosedSet, Heuristic, Incremental, OpenSet, Problem, Representation, Search};
+
+// The number of symbols, the depth to expand candidates to and the number of
+// perms that the whole search is timed to, one subgoal at a time.
+const CASES: &[(usize, usize, usize)] = &[(4, 7, 24), (5, 5, 110), (6, 4, 60)];
+const REPRESENTATIONS: &[Representation] = &[Representation::Roaring, Representation::Words];
+const REPEATS: usize = 5;
+
+fn main() {
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    let symbols = args.iter().filter_map(|a| a.parse().ok()).collect::<Vec<usize>>();
+    let representations = args.iter().filter_map(|a| a.parse().ok()).collect::<Vec<Representation>>();
+
+    for &(n, depth, subgoal) in CASES {
+        for &representation in REPRESENTATIONS {
+            if (symbols.is_empty() || symbols.contains(&n)) && (representations.is_empty() || representations.contains(&representation)) {
+                bench(n, representation, depth, subgoal);
+            }
+        }
+    }
+}
+
+fn bench(n: usize, representation: Representation, depth: usize, subgoal: usize) {
+    let problem = Problem::with_representation(n, representation);
+    let candidates = candidates(&problem, depth);
+    let count = candidates.len();
+
+    println!("N={} ({} candidates, {:?} representation)", n, count, problem.representation());
+
+    let per_op = time(count * problem.expansions(), || (), |_| {
+        for candidate in &candidates {
+            for symbol in 0..problem.expansions() {
+                black_box(candidate.expand(&problem, symbol));
+            }
+        }
+    });
+
+    report("Candidate::expand", per_op);
+
+    let per_op = time(count * problem.expansions(), || (), |_| {
+        for candidate in &candidates {
+            for symbol in 0..problem.expansions() {
+                black_box(problem.symmetry().mapping(symbol, candidate));
+            }
+        }
+    });
+
+    report("Symmetry::mapping", per_op);
+
+    let costs = |i: usize| (depth + i % 3, i % 5);
+
+    let add = time(count, || (OpenSet::new(), candidates.clone()), |(open_set, candidates)| {
+        for (i, candidate) in candidates.drain(..).enumerate() {
+            let (f_cost, g_cost) = costs(i);
+            open_set.add(candidate, f_cost, g_cost).unwrap();
+        }
+    });
+
+    let pop = time(count, || open_set(&candidates, costs), |open_set| {
+        while let Some(item) = open_set.pop().unwrap() {
+            black_box(item);
+        }
+    });
+
+    report("OpenSet::add", add);
+    report("OpenSet::pop", pop);
+
+    let add = time(count, || (ClosedSet::new(), candidates.clone()), |(closed_set, candidates)| {
+        for candidate in candidates.drain(..) {
+            closed_set.add(candidate, depth).unwrap();
+        }
+    });
+
+    let contains = time(count, || closed_set(&candidates, depth), |closed_set| {
+        for candidate in &candidates {
+            black_box(closed_set.contains(candidate, depth));
+        }
+    });
+
+    report("ClosedSet::add", add);
+    report("ClosedSet::contains", contains);
+
+    let search = Search::new(&problem, OpenSet::new(), ClosedSet::new());
+    let mut incremental = Incremental::new(Heuristic::seed(&problem), search);
+
+    incremental.stop_at(subgoal);
+
+    let start = Instant::now();
+    let distance = incremental.shortest_path(Candidate::seed(&problem), |_, _, _, _, _, _| {});
+    let seconds = start.elapsed().as_secs_f64();
+    let expansions = incremental.search().expansions();
+
+    println!(
+        "  {:<24}{:>12.3}s to {} perms ({:?}) with {} expansions ({:.0} per second)",
+        "Search::shortest_path", seconds, subgoal, distance, expansions, expansions as f64 / seconds,
+    );
+
+    println!();
+}
+
+// Every distinct candidate up to the depth, which is a mix of the candidates
+// that the search sees near the start and some that it wouldn't.
+fn candidates(problem: &Problem, depth: usize) -> Vec<Candidate> {
+    let mut frontier = vec![Candidate::seed(problem)];
+    let mut all = frontier.clone();
+
+    for _ in 0..depth {
+        frontier = frontier.iter().flat_map(|c| (0..problem.expansions()).map(move |s| c.expand(problem, s))).collect();
+        frontier.sort();
+        frontier.dedup();
+
+        all.extend(frontier.iter().cloned());
+    }
+
+    all.sort();
+    all.dedup();
+    all
+}
+
+fn open_set<F: Fn(usize) -> (usize, usize)>(candidates: &[Candidate], costs: F) -> OpenSet {
+    let mut open_set = OpenSet::new();
+
+    for (i, candidate) in candidates.iter().enumerate() {
+        let (f_cost, g_cost) = costs(i);
+        open_set.add(candidate.clone(), f_cost, g_cost).unwrap();
+    }
+
+    open_set
+}
+
+fn closed_set(candidates: &[Candidate], g_cost: usize) -> ClosedSet {
+    let mut closed_set = ClosedSet::new();
+
+    for candidate in candidates {
+        closed_set.add(candidate.clone(), g_cost).unwrap();
+    }
+
+    closed_set
+}
+
+// The best of a few runs in nanoseconds per operation. The setup isn't timed.
+fn time<S, T: FnMut() -> S, F: FnMut(&mut S)>(operations: usize, mut setup: T, mut f: F) -> f64 {
+    let mut best = f64::INFINITY;
+
+    for _ in 0..REPEATS {
+        let mut state = setup();
+
+        let start = Instant::now();
+        f(&mut state);
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+
+    best * 1e9 / operations as f64
+}
+
+fn report(name: &str, nanoseconds: f64) {
+    println!("  {:<24}{:>12.1}ns per op ({:.0} per second)", name, nanoseconds, 1e9 / nanoseconds);
+}
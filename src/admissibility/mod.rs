@@ -0,0 +1,317 @@
+use crate::candidate::Candidate;
+use crate::heuristic::Heuristic;
+use crate::heuristic_fn::HeuristicFn;
+use crate::problem::Problem;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fmt;
+
+// Indexes are u32 to keep the millions of candidates for four symbols in memory.
+const UNKNOWN: u32 = u32::MAX;
+
+/// Explores every canonical candidate up to the shortest distance to a target
+/// number of perms, then checks a heuristic against the exact distances from
+/// each candidate to each subgoal. No shortest path to a subgoal goes deeper,
+/// so this covers every candidate an A* search needs to expand. It's only
+/// feasible for three or four symbols.
+pub struct Admissibility<'a> {
+    problem: &'a Problem,
+    candidates: Vec<Candidate>,
+    perms: Vec<usize>,
+    depths: Vec<u32>,
+    neighbors: Vec<u32>,
+    reverse_offsets: Vec<u32>,
+    reverse: Vec<u32>,
+    distances: Vec<usize>,
+}
+
+/// Where a heuristic overestimated the exact distance to the goal, or where
+/// its cost dropped by more than one across an expansion.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Violation {
+    kind: Kind,
+    goal: usize,
+    perms: usize,
+    depth: usize,
+    cost: usize,
+    bound: usize,
+    candidates: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Kind {
+    Overestimate,
+    Inconsistent,
+}
+
+impl Kind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Kind::Overestimate => "overestimate",
+            Kind::Inconsistent => "inconsistent",
+        }
+    }
+}
+
+impl<'a> Admissibility<'a> {
+    /// Searches breadth-first from the seed. Candidates are told apart in
+    /// full rather than by their fingerprints so that a collision can't merge
+    /// two of them.
+    pub fn new(problem: &'a Problem, target: usize) -> Self {
+        let seed = Candidate::seed(problem);
+        let start = seed.number_of_permutations(problem);
+        let target = target.max(start).min(Candidate::maximum_permutations(problem));
+
+        let mut index = HashMap::new();
+        index.insert(seed.clone(), 0);
+
+        let mut candidates = vec![seed];
+        let mut perms = vec![start];
+        let mut depths = vec![0];
+        let mut neighbors = vec![];
+        let mut distances = vec![0];
+
+        let mut expanded = 0;
+
+        while expanded < candidates.len() {
+            let depth = depths[expanded];
+
+            // The candidates at the target's distance are kept but not expanded.
+            if distances.len() > target - start && depth as usize >= distances[target - start] {
+                break;
+            }
+
+            for symbol in 0..problem.expansions() {
+                let neighbor = candidates[expanded].expand(problem, symbol);
+                let neighbor_perms = neighbor.number_of_permutations(problem);
+
+                // Perms go up by at most one so the first to reach them is the closest.
+                if neighbor_perms == start + distances.len() {
+                    distances.push(depth as usize + 1);
+                }
+
+                let id = match index.get(&neighbor) {
+                    Some(id) => *id,
+                    None => {
+                        index.insert(neighbor.clone(), candidates.len() as u32);
+
+                        candidates.push(neighbor);
+                        perms.push(neighbor_perms);
+                        depths.push(depth + 1);
+
+                        candidates.len() as u32 - 1
+                    },
+                };
+
+                neighbors.push(id);
+            }
+
+            expanded += 1;
+        }
+
+        let (reverse_offsets, reverse) = Self::reverse(&neighbors, problem.expansions(), candidates.len());
+
+        Self { problem, candidates, perms, depths, neighbors, reverse_offsets, reverse, distances }
+    }
+
+    /// The number of candidates explored.
+    pub fn len(&self) -> usize {
+        self.candidates.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.candidates.is_empty()
+    }
+
+    /// The exact shortest distances from the seed to each number of perms,
+    /// starting with zero for the seed's perms.
+    pub fn distances(&self) -> &[usize] {
+        &self.distances
+    }
+
+    /// Checks the heuristic for each subgoal in turn, after it has learned
+    /// the exact distances to the earlier subgoals. Violations are grouped by
+    /// their perms and depth, keeping the one that's furthest out of bounds.
+    pub fn check<H: HeuristicFn>(&self, heuristic: &mut H) -> Vec<Violation> {
+        let problem = self.problem;
+        let start = self.perms[0];
+        let mut violations = vec![];
+
+        for goal in (start + 1)..(start + self.distances.len()) {
+            heuristic.learn(&Heuristic::from_distances(problem, &self.distances[..(goal - start)]));
+
+            let exact = self.exact_costs(goal);
+            let mut worst: BTreeMap<(Kind, usize, usize), Violation> = BTreeMap::new();
+
+            for (id, candidate) in self.candidates.iter().enumerate() {
+                let perms = self.perms[id];
+                let depth = self.depths[id] as usize;
+
+                if perms > goal {
+                    continue;
+                }
+
+                let cost = heuristic.cost(problem, candidate, depth);
+
+                if exact[id] != UNKNOWN && cost > exact[id] as usize {
+                    let violation = Violation::new(Kind::Overestimate, goal, perms, depth, cost, exact[id] as usize);
+                    Self::keep_worst(&mut worst, violation);
+                }
+
+                if perms == goal {
+                    continue;
+                }
+
+                for &neighbor in self.neighbors_of(id) {
+                    let neighbor = neighbor as usize;
+
+                    if self.perms[neighbor] > goal {
+                        continue;
+                    }
+
+                    let neighbor_cost = heuristic.cost(problem, &self.candidates[neighbor], depth + 1);
+
+                    if cost > neighbor_cost + 1 {
+                        let violation = Violation::new(Kind::Inconsistent, goal, perms, depth, cost, neighbor_cost + 1);
+                        Self::keep_worst(&mut worst, violation);
+                    }
+                }
+            }
+
+            violations.extend(worst.into_values());
+        }
+
+        violations
+    }
+
+    // The candidates at the bound's depth weren't expanded so have no neighbors.
+    fn neighbors_of(&self, id: usize) -> &[u32] {
+        let expansions = self.problem.expansions();
+        let from = (id * expansions).min(self.neighbors.len());
+        let to = ((id + 1) * expansions).min(self.neighbors.len());
+
+        &self.neighbors[from..to]
+    }
+
+    // Searches backwards from the candidates with the goal's perms. Candidates
+    // past the goal can't lead to it since the search would have stopped.
+    fn exact_costs(&self, goal: usize) -> Vec<u32> {
+        let mut costs = vec![UNKNOWN; self.len()];
+        let mut queue = VecDeque::new();
+
+        for (id, perms) in self.perms.iter().enumerate() {
+            if *perms == goal {
+                costs[id] = 0;
+                queue.push_back(id);
+            }
+        }
+
+        while let Some(id) = queue.pop_front() {
+            let from = self.reverse_offsets[id] as usize;
+            let to = self.reverse_offsets[id + 1] as usize;
+
+            for &parent in &self.reverse[from..to] {
+                let parent = parent as usize;
+
+                if costs[parent] == UNKNOWN && self.perms[parent] < goal {
+                    costs[parent] = costs[id] + 1;
+                    queue.push_back(parent);
+                }
+            }
+        }
+
+        costs
+    }
+
+    // Groups the parents of each candidate together, with the offsets of
+    // where each group starts.
+    fn reverse(neighbors: &[u32], expansions: usize, len: usize) -> (Vec<u32>, Vec<u32>) {
+        let mut offsets = vec![0; len + 1];
+
+        for &neighbor in neighbors {
+            offsets[neighbor as usize + 1] += 1;
+        }
+
+        for id in 0..len {
+            offsets[id + 1] += offsets[id];
+        }
+
+        let mut next = offsets.clone();
+        let mut reverse = vec![0; neighbors.len()];
+
+        for (edge, &neighbor) in neighbors.iter().enumerate() {
+            let parent = (edge / expansions) as u32;
+
+            reverse[next[neighbor as usize] as usize] = parent;
+            next[neighbor as usize] += 1;
+        }
+
+        (offsets, reverse)
+    }
+
+    fn keep_worst(worst: &mut BTreeMap<(Kind, usize, usize), Violation>, violation: Violation) {
+        let key = (violation.kind, violation.perms, violation.depth);
+
+        let entry = worst.entry(key).or_insert(Violation { candidates: 0, ..violation });
+        let candidates = entry.candidates + 1;
+
+        if violation.excess() > entry.excess() {
+            *entry = violation;
+        }
+
+        entry.candidates = candidates;
+    }
+}
+
+impl Violation {
+    fn new(kind: Kind, goal: usize, perms: usize, depth: usize, cost: usize, bound: usize) -> Self {
+        Self { kind, goal, perms, depth, cost, bound, candidates: 1 }
+    }
+
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// The number of perms the heuristic was estimating the distance to.
+    pub fn goal(&self) -> usize {
+        self.goal
+    }
+
+    pub fn perms(&self) -> usize {
+        self.perms
+    }
+
+    /// The shortest distance from the seed to the candidate.
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    pub fn cost(&self) -> usize {
+        self.cost
+    }
+
+    /// The exact distance to the goal for an overestimate, or one more than
+    /// the neighbor's cost when it's inconsistent.
+    pub fn bound(&self) -> usize {
+        self.bound
+    }
+
+    /// The number of candidates with these perms and depth that were out of
+    /// bounds.
+    pub fn candidates(&self) -> usize {
+        self.candidates
+    }
+
+    fn excess(&self) -> usize {
+        self.cost - self.bound
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} for goal {} at {} perms and depth {}: cost {} > {} ({} candidates)",
+            self.kind.name(), self.goal, self.perms, self.depth, self.cost, self.bound, self.candidates)
+    }
+}
+
+#[cfg(test)]
+mod test;
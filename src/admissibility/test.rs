@@ -0,0 +1,161 @@
+use super::*;
+use crate::heuristic_fn::Zero;
+use crate::published::Published;
+
+type Subject<'a> = Admissibility<'a>;
+
+lazy_static! {
+    static ref THREE: Problem = Problem::new(3);
+    static ref FOUR: Problem = Problem::new(4);
+}
+
+fn published(symbols: usize, len: usize) -> Vec<usize> {
+    let table = Published::max_perms_per_waste(symbols).unwrap();
+    Published::distances(table)[..len].to_vec()
+}
+
+// Overestimates by two at the seed, which is at least two away from the later
+// goals, but estimates nothing for its neighbors.
+struct DropsAtTheSeed;
+
+impl HeuristicFn for DropsAtTheSeed {
+    fn cost(&self, _problem: &Problem, _candidate: &Candidate, search_depth: usize) -> usize {
+        if search_depth == 0 { 2 } else { 0 }
+    }
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn it_explores_up_to_the_shortest_distance_to_the_target() {
+        let subject = Subject::new(&THREE, 6);
+        let deepest = subject.depths.iter().max().cloned();
+
+        assert_eq!(subject.len(), 26);
+        assert_eq!(deepest, Some(6));
+    }
+
+    #[test]
+    fn it_explores_less_for_a_nearer_target() {
+        let nearer = Subject::new(&THREE, 4);
+        let further = Subject::new(&THREE, 6);
+
+        assert!(nearer.len() < further.len());
+    }
+
+    #[test]
+    fn it_only_expands_candidates_short_of_the_target_distance() {
+        let subject = Subject::new(&THREE, 6);
+        let expanded = subject.depths.iter().filter(|d| **d < 6).count();
+
+        assert_eq!(subject.neighbors.len(), expanded * THREE.expansions());
+    }
+
+    #[test]
+    fn it_keeps_each_candidate_once_and_links_neighbors_to_equal_candidates() {
+        let subject = Subject::new(&THREE, 6);
+        let unique = subject.candidates.iter().collect::<std::collections::HashSet<_>>();
+
+        assert_eq!(unique.len(), subject.len());
+
+        for (edge, &neighbor) in subject.neighbors.iter().enumerate() {
+            let (id, symbol) = (edge / THREE.expansions(), edge % THREE.expansions());
+            let expected = subject.candidates[id].expand(&THREE, symbol);
+
+            assert_eq!(subject.candidates[neighbor as usize], expected);
+        }
+    }
+
+    #[test]
+    fn it_groups_the_parents_of_each_candidate() {
+        let subject = Subject::new(&THREE, 6);
+        let expansions = THREE.expansions();
+
+        assert_eq!(subject.reverse.len(), subject.neighbors.len());
+
+        for (edge, neighbor) in subject.neighbors.iter().enumerate() {
+            let from = subject.reverse_offsets[*neighbor as usize] as usize;
+            let to = subject.reverse_offsets[*neighbor as usize + 1] as usize;
+
+            assert!(subject.reverse[from..to].contains(&((edge / expansions) as u32)));
+        }
+    }
+}
+
+mod distances {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_published_distances_for_three_symbols() {
+        let subject = Subject::new(&THREE, 6);
+        assert_eq!(subject.distances(), &published(3, 6)[..]);
+    }
+
+    #[test]
+    fn it_finds_the_published_distances_for_four_symbols_up_to_the_target() {
+        let subject = Subject::new(&FOUR, 10);
+        assert_eq!(subject.distances(), &published(4, 10)[..]);
+    }
+}
+
+mod check {
+    use super::*;
+
+    #[test]
+    fn it_finds_no_violations_for_the_zero_heuristic() {
+        let subject = Subject::new(&FOUR, 10);
+        assert_eq!(subject.check(&mut Zero), vec![]);
+    }
+
+    #[test]
+    fn it_finds_no_overestimates_for_the_learned_heuristic() {
+        for subject in [Subject::new(&THREE, 6), Subject::new(&FOUR, 10)] {
+            let violations = subject.check(&mut Heuristic::seed(subject.problem));
+            assert!(violations.iter().all(|v| v.kind() == Kind::Inconsistent));
+        }
+    }
+
+    #[test]
+    fn it_finds_where_the_learned_heuristic_is_inconsistent_for_three_symbols() {
+        let subject = Subject::new(&THREE, 6);
+        let violations = subject.check(&mut Heuristic::seed(&THREE));
+
+        // Adding four perms takes two more symbols than adding three, so a
+        // candidate with one perm costs two more than its neighbor with two.
+        assert_eq!(violations[0], Violation {
+            kind: Kind::Inconsistent, goal: 5, perms: 1, depth: 1, cost: 5, bound: 4, candidates: 1,
+        });
+
+        assert_eq!(violations.len(), 2);
+    }
+
+    #[test]
+    fn it_finds_inconsistencies_where_the_cost_drops_by_more_than_one() {
+        let subject = Subject::new(&THREE, 6);
+        let violations = subject.check(&mut DropsAtTheSeed);
+
+        let inconsistent = violations.iter().filter(|v| v.kind() == Kind::Inconsistent);
+        let overestimates = violations.iter().filter(|v| v.kind() == Kind::Overestimate);
+
+        assert_eq!(inconsistent.count(), 5);
+        assert!(violations.iter().all(|v| v.perms() == 1 && v.depth() == 0 && v.cost() == 2));
+
+        // Only the first goal is one away from the seed.
+        assert_eq!(overestimates.map(|v| v.goal()).collect::<Vec<_>>(), vec![2]);
+    }
+
+    #[test]
+    fn it_keeps_the_violation_that_is_furthest_out_of_bounds() {
+        let subject = Subject::new(&FOUR, 8);
+        let violations = subject.check(&mut Heuristic::seed(&FOUR).scaled(3.0));
+
+        for violation in &violations {
+            assert!(violation.cost() > violation.bound());
+            assert!(violation.candidates() >= 1);
+        }
+
+        let first_goal = violations.iter().find(|v| v.goal() == 2 && v.kind() == Kind::Overestimate);
+        assert_eq!(first_goal.map(|v| (v.perms(), v.depth(), v.cost(), v.bound())), Some((1, 0, 3, 1)));
+    }
+}
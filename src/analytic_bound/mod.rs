@@ -0,0 +1,79 @@
+use crate::candidate::Candidate;
+use crate::heuristic::Heuristic;
+use crate::heuristic_fn::HeuristicFn;
+use crate::problem::Problem;
+
+/// A lower bound on the distance to the next goal from the closed-form bounds
+/// on superpermutation length. Each perm takes at least one symbol and each
+/// 1-cycle holds n of them, so entering another 1-cycle takes at least one
+/// more. Once every perm is needed, the 2-cycles add another (n-2)! - 1,
+/// which gives the n! + (n-1)! + (n-2)! + n - 3 bound on the whole string.
+///
+/// The bound on the distance from the seed to the goal, less the search depth,
+/// is compared with the bound on adding the perms still needed from the
+/// candidate itself once it's ready, and the greater is used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AnalyticBound {
+    symbols: usize,
+    factorial: usize,
+    starting_perms: usize,
+    goal: usize,
+}
+
+impl AnalyticBound {
+    pub fn new(problem: &Problem) -> Self {
+        let starting_perms = Candidate::seed(problem).number_of_permutations(problem);
+        let goal = starting_perms + 1;
+
+        Self { symbols: problem.symbols(), factorial: problem.factorial(), starting_perms, goal }
+    }
+
+    /// The least number of symbols to add to the seed for it to have this
+    /// many perms. The 2-cycles only count once every perm is needed, since
+    /// fewer can be spread across them more cheaply than that, e.g. 13 perms
+    /// for four symbols are 15 symbols away rather than 16.
+    pub fn shortest_distance(&self, perms: usize) -> usize {
+        let perms = perms.max(self.starting_perms);
+        let n = self.symbols;
+
+        let mut distance = perms - self.starting_perms + perms.div_ceil(n) - 1;
+
+        if perms == self.factorial && n > 1 {
+            distance += perms.div_ceil(n * (n - 1)) - 1;
+        }
+
+        distance
+    }
+
+    /// The least number of symbols to add this many perms to a candidate that
+    /// isn't ready for this many symbols. It might be midway through its
+    /// 1-cycle so that one doesn't count.
+    pub fn shortest_distance_to_add(&self, until_ready: usize, perms_needed: usize) -> usize {
+        match perms_needed {
+            0 => 0,
+            _ => until_ready + perms_needed + perms_needed.div_ceil(self.symbols) - 1,
+        }
+    }
+}
+
+impl HeuristicFn for AnalyticBound {
+    fn cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize) -> usize {
+        let perms = candidate.number_of_permutations(problem);
+
+        if perms >= self.goal {
+            return 0;
+        }
+
+        let from_seed = self.shortest_distance(self.goal).saturating_sub(search_depth);
+        let from_here = self.shortest_distance_to_add(candidate.symbols_until_ready(problem), self.goal - perms);
+
+        from_seed.max(from_here)
+    }
+
+    fn learn(&mut self, heuristic: &Heuristic) {
+        self.goal = heuristic.next_goal();
+    }
+}
+
+#[cfg(test)]
+mod test;
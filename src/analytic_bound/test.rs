@@ -0,0 +1,148 @@
+use super::*;
+use crate::admissibility::{Admissibility, Kind};
+use crate::closed_set::ClosedSet;
+use crate::incremental::Incremental;
+use crate::open_set::OpenSet;
+use crate::published::Published;
+use crate::search::Search;
+
+type Subject = AnalyticBound;
+
+lazy_static! {
+    static ref THREE: Problem = Problem::new(3);
+    static ref FOUR: Problem = Problem::new(4);
+    static ref FIVE: Problem = Problem::new(5);
+}
+
+fn published(problem: &Problem) -> Vec<usize> {
+    let table = Published::max_perms_per_waste(problem.symbols()).unwrap();
+    Published::distances(table)
+}
+
+mod shortest_distance {
+    use super::*;
+
+    #[test]
+    fn it_adds_one_symbol_per_perm_and_one_per_extra_one_cycle() {
+        let subject = Subject::new(&FIVE);
+
+        assert_eq!(subject.shortest_distance(1), 0);
+        assert_eq!(subject.shortest_distance(5), 4);
+        assert_eq!(subject.shortest_distance(6), 6);
+        assert_eq!(subject.shortest_distance(11), 12);
+    }
+
+    #[test]
+    fn it_is_the_closed_form_bound_less_the_seed_for_every_perm() {
+        for problem in [&*THREE, &*FOUR, &*FIVE] {
+            let n = problem.symbols();
+            let factorial = |n: usize| (1..=n).product::<usize>();
+
+            let bound = factorial(n) + factorial(n - 1) + factorial(n - 2) + n - 3;
+            let subject = Subject::new(problem);
+
+            assert_eq!(subject.shortest_distance(problem.factorial()), bound - n);
+        }
+    }
+
+    #[test]
+    fn it_never_exceeds_the_published_distances() {
+        for problem in [&*THREE, &*FOUR, &*FIVE] {
+            let subject = Subject::new(problem);
+
+            for (i, distance) in published(problem).into_iter().enumerate() {
+                assert!(subject.shortest_distance(i + 1) <= distance);
+            }
+        }
+    }
+
+    #[test]
+    fn it_is_the_published_distance_for_every_perm_with_four_symbols() {
+        let subject = Subject::new(&FOUR);
+        assert_eq!(subject.shortest_distance(24), published(&FOUR)[23]);
+    }
+}
+
+mod shortest_distance_to_add {
+    use super::*;
+
+    #[test]
+    fn it_adds_the_symbols_until_ready() {
+        let subject = Subject::new(&FIVE);
+
+        assert_eq!(subject.shortest_distance_to_add(0, 1), 1);
+        assert_eq!(subject.shortest_distance_to_add(2, 1), 3);
+        assert_eq!(subject.shortest_distance_to_add(2, 0), 0);
+    }
+
+    #[test]
+    fn it_adds_one_for_each_one_cycle_after_the_current_one() {
+        let subject = Subject::new(&FIVE);
+
+        assert_eq!(subject.shortest_distance_to_add(0, 5), 5);
+        assert_eq!(subject.shortest_distance_to_add(0, 6), 7);
+        assert_eq!(subject.shortest_distance_to_add(1, 11), 1 + 11 + 2);
+    }
+}
+
+mod cost {
+    use super::*;
+
+    #[test]
+    fn it_subtracts_the_search_depth_from_the_distance_from_the_seed() {
+        let mut subject = Subject::new(&FIVE);
+        let seed = Candidate::seed(&FIVE);
+
+        subject.learn(&Heuristic::from_distances(&FIVE, &published(&FIVE)[..5]));
+
+        assert_eq!(subject.cost(&FIVE, &seed, 0), 6);
+        assert_eq!(subject.cost(&FIVE, &seed, 3), 5);
+    }
+
+    #[test]
+    fn it_is_at_least_the_distance_to_add_the_perms_from_the_candidate() {
+        let mut subject = Subject::new(&FIVE);
+        let not_ready = Candidate::seed(&FIVE).expand(&FIVE, 3);
+
+        subject.learn(&Heuristic::from_distances(&FIVE, &published(&FIVE)[..5]));
+
+        assert_eq!(not_ready.symbols_until_ready(&FIVE), 2);
+        assert_eq!(subject.shortest_distance(6) - 1, 5);
+        assert_eq!(subject.cost(&FIVE, &not_ready, 1), 2 + 5);
+    }
+
+    #[test]
+    fn it_is_zero_once_the_candidate_has_the_goals_perms() {
+        let subject = Subject::new(&FIVE);
+        let neighbor = Candidate::seed(&FIVE).expand(&FIVE, 0);
+
+        assert_eq!(subject.cost(&FIVE, &neighbor, 1), 0);
+    }
+
+    #[test]
+    fn it_never_overestimates_for_three_symbols() {
+        let admissibility = Admissibility::new(&THREE, 6);
+        let violations = admissibility.check(&mut Subject::new(&THREE));
+
+        assert!(violations.iter().all(|v| v.kind() != Kind::Overestimate));
+    }
+
+    #[test]
+    fn it_never_overestimates_for_four_symbols_up_to_the_target() {
+        let admissibility = Admissibility::new(&FOUR, 10);
+        let violations = admissibility.check(&mut Subject::new(&FOUR));
+
+        assert!(violations.iter().all(|v| v.kind() != Kind::Overestimate));
+    }
+
+    #[test]
+    fn it_finds_the_published_distance_to_every_perm_when_combined_with_the_learned_heuristic() {
+        let search = Search::new(&FOUR, OpenSet::new(), ClosedSet::new());
+        let mut incremental = Incremental::new(Heuristic::seed(&FOUR), search);
+
+        incremental.use_heuristic_fn(Box::new(Heuristic::seed(&FOUR).max(Subject::new(&FOUR))));
+
+        let distance = incremental.shortest_path(Candidate::seed(&FOUR), |_, _, _, _, _, _| {});
+        assert_eq!(distance, Some(published(&FOUR)[23]));
+    }
+}
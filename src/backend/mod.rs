@@ -0,0 +1,72 @@
+use crate::candidate::Candidate;
+use crate::codec::{Encoder, Decoder};
+use crate::events::Events;
+use crate::heuristic::Heuristic;
+use crate::heuristic_fn::HeuristicFn;
+use crate::limits::Limits;
+use crate::path::Path;
+use crate::problem::Problem;
+use crate::stats::SearchStats;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+/// A search that an incremental search can use to find the shortest path to
+/// each subgoal in turn.
+pub trait Backend<'a>: Sized {
+    /// Identifies the backend in checkpoints.
+    const NAME: &'static str;
+
+    fn problem(&self) -> &'a Problem;
+    fn seed(&mut self, candidate: Candidate);
+    fn seed_from_string(&mut self, string: &str) -> Result<(), String>;
+
+    /// Returns the shortest distance to a candidate with the goal number of
+    /// perms, or None if it was interrupted.
+    fn shortest_path(&mut self, goal: usize) -> Option<usize>;
+
+    /// The path to the goal that was reached most recently.
+    fn path(&self) -> Option<Path<'a>>;
+
+    /// A lower bound on the shortest distance to the goal that was reached most
+    /// recently. It's the distance itself unless the search was weighted, so
+    /// the path is at most the distance over this many times the shortest.
+    fn lower_bound(&self) -> Option<usize>;
+
+    fn update_heuristic(&mut self, heuristic: &Heuristic);
+
+    /// Estimates costs with this rather than the learned heuristic alone.
+    fn use_heuristic_fn(&mut self, estimate: Box<dyn HeuristicFn>);
+    fn limit_to(&mut self, limits: Limits);
+
+    /// Multiplies the heuristic's costs by the weight so that paths are found
+    /// sooner but may be up to that many times longer than the shortest.
+    fn weight_by(&mut self, weight: f64);
+    fn weight(&self) -> f64;
+
+    fn expansions(&self) -> usize;
+    fn stats(&self) -> &SearchStats;
+
+    /// The sizes of the open and closed sets, which are zero if there are none.
+    fn open_set_len(&self) -> usize;
+    fn closed_set_len(&self) -> usize;
+
+    /// Writes the search's progress towards each subgoal to the stream.
+    fn emit_events_to(&mut self, events: Events);
+    fn interrupt_flag(&self) -> Arc<AtomicBool>;
+    fn interrupted(&self) -> bool;
+
+    /// The error that interrupted the search, if one did, e.g. when a set it
+    /// spilled to disk couldn't be read back.
+    fn error(&self) -> Option<&io::Error>;
+
+    /// Stops the search once it's past the deadline and at a point where it
+    /// can be checkpointed, e.g. so that one is saved on time. Searching for
+    /// the same goal again continues from where it paused.
+    fn pause_at(&mut self, deadline: Option<Instant>);
+    fn paused(&self) -> bool;
+
+    fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()>;
+    fn read_from<R: Read>(decoder: &mut Decoder<R>, problem: &'a Problem) -> io::Result<Self>;
+}
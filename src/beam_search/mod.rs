@@ -0,0 +1,129 @@
+use crate::candidate::Candidate;
+use crate::limits::Limits;
+use crate::path::Path;
+use crate::problem::Problem;
+use crate::utility::Utility;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+
+/// Keeps the best candidates at each depth rather than searching exhaustively.
+/// Candidates are ranked by their number of perms and then by the score, which
+/// defaults to the number of unique tail symbols so that candidates that are
+/// ready to add a permutation are preferred.
+pub struct BeamSearch<'a> {
+    problem: &'a Problem,
+    width: usize,
+    score: Box<dyn Fn(&Candidate) -> usize + 'a>,
+    target: usize,
+    max_depth: usize,
+    limits: Limits,
+    expansions: usize,
+    curve: Vec<usize>,
+}
+
+// The index of the parent in the previous layer and the symbol expanded.
+type Link = (usize, usize);
+
+impl<'a> BeamSearch<'a> {
+    pub fn new(problem: &'a Problem, width: usize) -> Self {
+        let score = Box::new(move |candidate: &Candidate| candidate.unique_tail_symbols(problem));
+        let target = Candidate::maximum_permutations(problem);
+
+        // The length of the recursive construction, 1! + 2! + ... + n!, which
+        // the beam gives up at if it hasn't found a shorter superpermutation.
+        let n = problem.symbols();
+        let max_depth = (1..=n).map(Utility::factorial).sum::<usize>() - n;
+
+        Self { problem, width, score, target, max_depth, limits: Limits::none(), expansions: 0, curve: vec![] }
+    }
+
+    /// Ranks candidates with the same number of perms by this score, highest first.
+    pub fn score_by<F: Fn(&Candidate) -> usize + 'a>(&mut self, score: F) {
+        self.score = Box::new(score);
+    }
+
+    /// Stops once this many perms are reached rather than every permutation.
+    pub fn stop_at(&mut self, perms: usize) {
+        self.target = perms;
+    }
+
+    pub fn limit_to(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Returns the shortest path to the most perms that the beam found. The
+    /// parents are kept as indexes into each layer so paths aren't copied.
+    pub fn search(&mut self, candidate: Candidate) -> Path<'a> {
+        let problem = self.problem;
+        let perms = candidate.number_of_permutations(problem);
+
+        let mut beam = vec![candidate.clone()];
+        let mut layers: Vec<Vec<Link>> = vec![];
+        let mut best = (perms, 0, 0);
+
+        self.curve = vec![perms];
+
+        while best.0 < self.target && layers.len() < self.max_depth && !self.limits.exceeded(self.expansions) {
+            let mut seen = HashSet::new();
+            let mut children = vec![];
+
+            for (parent, candidate) in beam.iter().enumerate() {
+                for symbol in 0..problem.expansions() {
+                    let child = candidate.expand(problem, symbol);
+
+                    if seen.insert(child.clone()) {
+                        let perms = child.number_of_permutations(problem);
+                        let score = (self.score)(&child);
+
+                        children.push((perms, score, child, (parent, symbol)));
+                    }
+                }
+
+                self.expansions += 1;
+            }
+
+            children.sort_by_key(|&(perms, score, _, _)| Reverse((perms, score)));
+            children.truncate(self.width);
+
+            let perms = match children.first() {
+                Some((perms, _, _, _)) => *perms,
+                None => break,
+            };
+
+            if perms > best.0 {
+                best = (perms, layers.len() + 1, 0);
+            }
+
+            self.curve.push(perms);
+
+            layers.push(children.iter().map(|(_, _, _, link)| *link).collect());
+            beam = children.into_iter().map(|(_, _, child, _)| child).collect();
+        }
+
+        let (_, depth, mut index) = best;
+        let mut symbols = vec![];
+
+        for layer in layers[..depth].iter().rev() {
+            let (parent, symbol) = layer[index];
+
+            symbols.push(symbol);
+            index = parent;
+        }
+
+        symbols.reverse();
+
+        Path::new(problem, candidate, symbols)
+    }
+
+    /// The most perms in the beam at each depth of the last search.
+    pub fn curve(&self) -> &[usize] {
+        &self.curve
+    }
+
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,108 @@
+use super::*;
+use crate::verification::Verification;
+
+type Subject<'a> = BeamSearch<'a>;
+
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(4);
+}
+
+fn verify(path: &Path) -> Verification {
+    Verification::new(&PROBLEM, &path.to_string()).unwrap()
+}
+
+mod search {
+    use super::*;
+
+    #[test]
+    fn it_finds_a_superpermutation_with_a_wide_enough_beam() {
+        let mut subject = Subject::new(&PROBLEM, 100);
+        let path = subject.search(Candidate::seed(&PROBLEM));
+
+        assert_eq!(path.len(), 29);
+        assert!(verify(&path).is_superpermutation());
+    }
+
+    #[test]
+    fn it_returns_the_shortest_path_to_the_most_perms_when_the_beam_is_too_narrow() {
+        let mut subject = Subject::new(&PROBLEM, 1);
+        let path = subject.search(Candidate::seed(&PROBLEM));
+        let most = subject.curve().iter().max().unwrap();
+
+        assert_eq!(verify(&path).number_of_permutations(), *most);
+        assert_eq!(path.len(), subject.curve().iter().position(|p| p == most).unwrap());
+    }
+
+    #[test]
+    fn it_gives_up_at_the_length_of_the_recursive_construction() {
+        let mut subject = Subject::new(&PROBLEM, 1);
+        subject.search(Candidate::seed(&PROBLEM));
+
+        // 1! + 2! + 3! + 4! = 33 symbols, which is 29 after the first four.
+        assert_eq!(subject.curve().len(), 29 + 1);
+    }
+}
+
+mod curve {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_most_perms_in_the_beam_at_each_depth() {
+        let mut subject = Subject::new(&PROBLEM, 100);
+        subject.search(Candidate::seed(&PROBLEM));
+
+        let curve = subject.curve();
+
+        assert_eq!(&curve[0..5], &[1, 2, 3, 4, 4]);
+        assert_eq!(curve.last(), Some(&24));
+        assert!(curve.windows(2).all(|w| w[0] <= w[1]));
+    }
+}
+
+mod score_by {
+    use super::*;
+
+    #[test]
+    fn it_ranks_candidates_with_the_same_number_of_perms_by_the_score() {
+        let mut default = Subject::new(&PROBLEM, 2);
+        let mut reversed = Subject::new(&PROBLEM, 2);
+
+        reversed.score_by(|candidate| PROBLEM.symbols() - candidate.unique_tail_symbols(&PROBLEM));
+
+        let default = verify(&default.search(Candidate::seed(&PROBLEM))).number_of_permutations();
+        let reversed = verify(&reversed.search(Candidate::seed(&PROBLEM))).number_of_permutations();
+
+        assert_eq!(default, 21);
+        assert_eq!(reversed, 4);
+    }
+}
+
+mod stop_at {
+    use super::*;
+
+    #[test]
+    fn it_stops_once_the_number_of_perms_is_reached() {
+        let mut subject = Subject::new(&PROBLEM, 100);
+        subject.stop_at(10);
+
+        let path = subject.search(Candidate::seed(&PROBLEM));
+
+        assert_eq!(verify(&path).number_of_permutations(), 10);
+        assert_eq!(subject.curve().last(), Some(&10));
+    }
+}
+
+mod limit_to {
+    use super::*;
+
+    #[test]
+    fn it_stops_once_the_limits_are_exceeded() {
+        let mut subject = Subject::new(&PROBLEM, 100);
+        subject.limit_to(Limits::new(Some(5), None));
+
+        subject.search(Candidate::seed(&PROBLEM));
+
+        assert!(subject.expansions() >= 5);
+        assert!(subject.curve().last() < Some(&24));
+    }
+}
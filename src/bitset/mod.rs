@@ -0,0 +1,130 @@
+use croaring::Bitmap;
+use std::cmp::Ordering::{self, Equal, Less, Greater};
+
+/// Something that bits can be looked up in, so that the symmetry can choose a
+/// mapping for either representation of a candidate.
+pub trait Bits {
+    fn contains(&self, bit: u32) -> bool;
+}
+
+impl Bits for Bitmap {
+    fn contains(&self, bit: u32) -> bool {
+        Bitmap::contains(self, bit)
+    }
+}
+
+/// A fixed number of 64-bit words that is copied rather than allocated. Six
+/// symbols need 724 bits so fit in twelve words.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Bitset<const K: usize> {
+    words: [u64; K],
+}
+
+impl<const K: usize> Bitset<K> {
+    pub fn new() -> Self {
+        Self { words: [0; K] }
+    }
+
+    pub fn add(&mut self, bit: u32) {
+        self.words[bit as usize / 64] |= 1 << (bit % 64);
+    }
+
+    pub fn cardinality(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn maximum(&self) -> Option<u32> {
+        let (i, word) = self.words.iter().enumerate().rev().find(|(_, w)| **w != 0)?;
+        Some(i as u32 * 64 + 63 - word.leading_zeros())
+    }
+
+    pub fn words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// The set bits in ascending order.
+    pub fn iter(&self) -> Ones<'_> {
+        Ones::new(&self.words)
+    }
+
+    pub fn from_words(words: &[u64]) -> Option<Self> {
+        let mut bitset = Self::new();
+
+        if words.len() != K {
+            return None;
+        }
+
+        bitset.words.copy_from_slice(words);
+        Some(bitset)
+    }
+}
+
+impl<const K: usize> Default for Bitset<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const K: usize> Bits for Bitset<K> {
+    fn contains(&self, bit: u32) -> bool {
+        self.words[bit as usize / 64] & (1 << (bit % 64)) != 0
+    }
+}
+
+// The same order as a bitmap's: fewer bits first, then by the lowest bit that
+// only one of them has, which comes first if it's set in that one.
+impl<const K: usize> Ord for Bitset<K> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match self.cardinality().cmp(&other.cardinality()) {
+            Equal => {},
+            ordering => return ordering,
+        }
+
+        for (a, b) in self.words.iter().zip(other.words.iter()) {
+            if a != b {
+                let lowest = (a ^ b).trailing_zeros();
+                return if a >> lowest & 1 == 1 { Less } else { Greater };
+            }
+        }
+
+        Equal
+    }
+}
+
+impl<const K: usize> PartialOrd for Bitset<K> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// Iterates the set bits of some words without allocating.
+pub struct Ones<'a> {
+    words: &'a [u64],
+    index: usize,
+    word: u64,
+}
+
+impl<'a> Ones<'a> {
+    pub fn new(words: &'a [u64]) -> Self {
+        Self { words, index: 0, word: words.first().cloned().unwrap_or(0) }
+    }
+}
+
+impl<'a> Iterator for Ones<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        while self.word == 0 {
+            self.index += 1;
+            self.word = *self.words.get(self.index)?;
+        }
+
+        let bit = self.word.trailing_zeros();
+        self.word &= self.word - 1;
+
+        Some(self.index as u32 * 64 + bit)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,113 @@
+use super::*;
+
+type Subject = Bitset<2>;
+
+mod add {
+    use super::*;
+
+    #[test]
+    fn it_sets_bits_across_words() {
+        let mut subject = Subject::new();
+
+        subject.add(3);
+        subject.add(64);
+        subject.add(127);
+
+        assert!(subject.contains(3));
+        assert!(subject.contains(64));
+        assert!(subject.contains(127));
+        assert!(!subject.contains(4));
+
+        assert_eq!(subject.cardinality(), 3);
+    }
+}
+
+mod maximum {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_highest_set_bit() {
+        let mut subject = Subject::new();
+        assert_eq!(subject.maximum(), None);
+
+        subject.add(5);
+        assert_eq!(subject.maximum(), Some(5));
+
+        subject.add(70);
+        assert_eq!(subject.maximum(), Some(70));
+    }
+}
+
+mod iter {
+    use super::*;
+
+    #[test]
+    fn it_yields_the_set_bits_in_ascending_order() {
+        let mut subject = Subject::new();
+
+        for bit in &[100, 0, 63, 64, 7] {
+            subject.add(*bit);
+        }
+
+        assert_eq!(subject.iter().collect::<Vec<_>>(), &[0, 7, 63, 64, 100]);
+    }
+
+    #[test]
+    fn it_skips_empty_words() {
+        let mut subject = Bitset::<3>::new();
+        subject.add(130);
+
+        assert_eq!(subject.iter().collect::<Vec<_>>(), &[130]);
+    }
+}
+
+mod from_words {
+    use super::*;
+
+    #[test]
+    fn it_builds_a_bitset_with_the_same_words() {
+        let subject = Subject::from_words(&[1, 2]).unwrap();
+
+        assert_eq!(subject.words(), &[1, 2]);
+        assert_eq!(subject.iter().collect::<Vec<_>>(), &[0, 65]);
+    }
+
+    #[test]
+    fn it_returns_none_if_the_number_of_words_is_wrong() {
+        assert_eq!(Subject::from_words(&[1]), None);
+        assert_eq!(Subject::from_words(&[1, 2, 3]), None);
+    }
+}
+
+mod cmp {
+    use super::*;
+
+    fn bitset(bits: &[u32]) -> Subject {
+        let mut bitset = Subject::new();
+        for bit in bits { bitset.add(*bit); }
+        bitset
+    }
+
+    fn bitmap(bits: &[u32]) -> Bitmap {
+        Bitmap::of(bits)
+    }
+
+    #[test]
+    fn it_orders_by_the_number_of_bits_first() {
+        assert!(bitset(&[100]) < bitset(&[0, 1]));
+    }
+
+    #[test]
+    fn it_orders_the_same_as_a_bitmap() {
+        let sets: &[&[u32]] = &[&[0, 1], &[0, 2], &[1, 2], &[0, 64], &[64, 65], &[3, 70], &[5], &[80]];
+
+        for a in sets {
+            for b in sets {
+                let expected = bitmap(a).iter().cmp(bitmap(b).iter());
+                let by_length = a.len().cmp(&b.len());
+
+                assert_eq!(bitset(a).cmp(&bitset(b)), by_length.then(expected), "{:?} {:?}", a, b);
+            }
+        }
+    }
+}
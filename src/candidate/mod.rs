@@ -1,67 +1,440 @@
 use croaring::Bitmap;
+use lehmer::Lehmer;
 use std::cmp::Ordering::{self, Equal, Less, Greater};
-use super::{FACTORIAL, CAPACITY, SYMMETRY};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::mem;
+use std::io::{self, ErrorKind, Read, Write};
+use crate::bitset::{Bits, Bitset, Ones};
+use crate::codec::{Encoder, Decoder};
+use crate::problem::{Problem, Representation, MAXIMUM_WORDS};
+use crate::utility::Utility;
 
-#[derive(Clone, Debug, PartialEq)]
+// The parameters of the 128-bit FNV-1a hash.
+const FNV_OFFSET_BASIS: u128 = 0x6c62_272e_07bb_0142_62b8_2175_6295_c58d;
+const FNV_PRIME: u128 = 0x0000_0000_0100_0000_0000_0000_0000_013b;
+
+/// A set of permutations and the length of the unique tail, stored in a bitmap
+/// and relabelled into a canonical form so that symmetric strings are equal.
+#[derive(Clone, Debug)]
 pub struct Candidate {
-    bitmap: Bitmap,
+    storage: Storage,
+}
+
+// The problem picks the representation. Fixed width words are chosen by the
+// number of symbols so that smaller problems copy fewer of them. The widest is
+// boxed so that it doesn't make every candidate as big as itself.
+#[derive(Clone, Debug, PartialEq)]
+enum Storage {
+    Roaring(Bitmap),
+    One(Bitset<1>),
+    Two(Bitset<2>),
+    Twelve(Box<Bitset<MAXIMUM_WORDS>>),
 }
 
 impl Candidate {
-    pub fn seed() -> Self {
-        let mut bitmap = Bitmap::create_with_capacity(*CAPACITY);
+    /// The candidate for the first permutation, 0123...
+    pub fn seed(problem: &Problem) -> Self {
+        let capacity = problem.capacity();
+        let mut storage = Storage::empty(problem);
+
+        storage.add(0);
+
+        for i in problem.factorial()..(capacity as usize) {
+            storage.add(i as u32);
+        }
+
+        storage.optimize();
+
+        Self { storage }
+    }
+
+    /// The candidate for a string that starts with a permutation.
+    pub fn from_string(problem: &Problem, string: &str) -> Result<Self, String> {
+        let symbols = Self::canonical_symbols(problem, string)?;
+
+        Ok(symbols.iter().fold(Self::seed(problem), |candidate, symbol| {
+            candidate.expand(problem, *symbol)
+        }))
+    }
+
+    /// Replays the string through the same canonicalising expansions as the
+    /// search and returns the symbol it expanded at each step. The string must
+    /// start with a permutation and never repeat the previous symbol.
+    pub fn canonical_symbols(problem: &Problem, string: &str) -> Result<Vec<usize>, String> {
+        let n = problem.symbols();
+
+        // labels[canonical] = actual
+        let mut labels = Self::first_permutation(problem, string)?;
+        let string = Utility::parse_symbols(string, problem.symbols())?;
+        let mut candidate = Self::seed(problem);
+        let mut symbols = vec![];
 
-        bitmap.add(0);
+        for actual in &string[n..] {
+            let symbol = match labels.iter().position(|l| l == actual) {
+                Some(symbol) if symbol < problem.expansions() => symbol,
+                Some(_) => return Err("the string repeats a symbol".to_string()),
+                None => return Err(format!("'{}' is not one of the {} symbols", actual, n)),
+            };
+
+            let relabelling = candidate.relabelling(problem, symbol);
+            labels = relabelling.iter().map(|l| labels[*l as usize]).collect();
+
+            candidate = candidate.expand(problem, symbol);
+            symbols.push(symbol);
+        }
 
-        for i in *FACTORIAL..(*CAPACITY as usize) {
-            bitmap.add(i as u32);
+        Ok(symbols)
+    }
+
+    /// The permutation the string starts with, which is what the seed's labels
+    /// refer to when the rest of the string is replayed.
+    pub fn first_permutation(problem: &Problem, string: &str) -> Result<Vec<u8>, String> {
+        let n = problem.symbols();
+        let string = Utility::parse_symbols(string, n)?;
+        let head = &string[0..n.min(string.len())];
+
+        if head.len() < n || !Utility::is_permutation(head) {
+            return Err(format!("the string must start with a permutation of {} symbols", n));
         }
 
-        bitmap.run_optimize();
+        Ok(head.to_vec())
+    }
+
+    /// Appends a symbol, numbered by its canonical label, and relabels the tail.
+    pub fn expand(&self, problem: &Problem, symbol: usize) -> Self {
+        let storage = match &self.storage {
+            Storage::Roaring(bitmap) => Storage::Roaring(Self::expand_bitmap(problem, symbol, bitmap)),
+            Storage::One(bitset) => Storage::One(Self::expand_bitset(problem, symbol, bitset)),
+            Storage::Two(bitset) => Storage::Two(Self::expand_bitset(problem, symbol, bitset)),
+            Storage::Twelve(bitset) => Storage::Twelve(Box::new(Self::expand_bitset(problem, symbol, &**bitset))),
+        };
 
-        Self { bitmap }
+        Self { storage }
     }
 
-    pub fn expand(&self, symbol: usize) -> Self {
-        let mut bitmap = Bitmap::create_with_capacity(*CAPACITY);
-        let mapping = SYMMETRY.mapping(symbol, &self.bitmap);
+    fn expand_bitmap(problem: &Problem, symbol: usize, bitmap: &Bitmap) -> Bitmap {
+        let mut expanded = Bitmap::create_with_capacity(problem.capacity());
+        let mapping = problem.symmetry().mapping(symbol, bitmap);
 
-        for bit in self.bitmap.iter() {
-            bitmap.add(mapping[bit as usize]);
+        for bit in bitmap.iter() {
+            expanded.add(mapping[bit as usize]);
         }
 
         // Set the bit for the ground truth:
-        bitmap.add(*FACTORIAL as u32);
+        expanded.add(problem.factorial() as u32);
+
+        expanded.run_optimize();
+        expanded
+    }
+
+    // The same as above but without allocating.
+    fn expand_bitset<const K: usize>(problem: &Problem, symbol: usize, bitset: &Bitset<K>) -> Bitset<K> {
+        let mut expanded = Bitset::new();
+        let mapping = problem.symmetry().mapping(symbol, bitset);
 
-        bitmap.run_optimize();
+        for bit in bitset.iter() {
+            expanded.add(mapping[bit as usize]);
+        }
+
+        expanded.add(problem.factorial() as u32);
+        expanded
+    }
 
-        Self { bitmap }
+    /// How the expansion relabels the tail: index is the new label and value is
+    /// the old label.
+    pub fn relabelling<'a>(&self, problem: &'a Problem, symbol: usize) -> &'a Vec<u8> {
+        problem.symmetry().transposition(symbol, &self.storage)
     }
 
-    pub fn number_of_permutations(&self) -> usize {
-        self.number_of_bits() - self.number_of_counter_bits()
+    pub fn number_of_permutations(&self, problem: &Problem) -> usize {
+        self.number_of_bits() - self.number_of_counter_bits(problem)
+    }
+
+    /// The permutations in their canonical labelling, in Lehmer order.
+    pub fn permutations(&self, problem: &Problem) -> Vec<Vec<u8>> {
+        self.permutations_of(problem.symbols())
+    }
+
+    fn permutations_of(&self, symbols: usize) -> Vec<Vec<u8>> {
+        let factorial = Utility::factorial(symbols) as u32;
+        let bits = self.storage.iter().take_while(|b| *b < factorial);
+
+        bits.map(|b| Lehmer::from_decimal(b as usize, symbols).to_permutation()).collect()
+    }
+
+    /// The Lehmer index of each permutation in ascending order, which is
+    /// cheaper than decoding them.
+    pub fn permutation_indexes<'a>(&'a self, problem: &Problem) -> impl Iterator<Item = usize> + 'a {
+        let factorial = problem.factorial() as u32;
+        self.storage.iter().take_while(move |b| *b < factorial).map(|b| b as usize)
+    }
+
+    /// The length of the longest tail with unique symbols, decoded from the
+    /// unary counter bits. It is capped at one less than the number of symbols.
+    pub fn unique_tail_symbols(&self, problem: &Problem) -> usize {
+        self.number_of_counter_bits(problem) + 1
+    }
+
+    /// Whether the next symbol can complete a new permutation.
+    pub fn is_ready(&self, problem: &Problem) -> bool {
+        self.unique_tail_symbols(problem) == problem.symbols() - 1
+    }
+
+    /// The number of symbols that must be added before the candidate is ready,
+    /// none of which can complete a new permutation.
+    pub fn symbols_until_ready(&self, problem: &Problem) -> usize {
+        problem.symbols() - 1 - self.unique_tail_symbols(problem)
     }
 
     pub fn number_of_bits(&self) -> usize {
-        self.bitmap.cardinality() as usize
+        self.storage.cardinality()
     }
 
-    fn number_of_counter_bits(&self) -> usize {
-        let range = (*FACTORIAL as u32)..*CAPACITY;
-        range.filter(|b| self.bitmap.contains(*b)).count()
+    fn number_of_counter_bits(&self, problem: &Problem) -> usize {
+        let range = (problem.factorial() as u32)..problem.capacity();
+        range.filter(|b| self.storage.contains(*b)).count()
     }
 
-    pub fn maximum_permutations() -> usize {
-        *FACTORIAL as usize
+    pub fn maximum_permutations(problem: &Problem) -> usize {
+        problem.factorial()
+    }
+
+    /// The 128-bit FNV-1a hash of the little-endian bytes of each bit that's
+    /// set, in order. It's the same for either representation, build and
+    /// platform because it's written to checkpoints and picks shards.
+    pub fn fingerprint(&self) -> u128 {
+        let bytes = self.storage.iter().flat_map(|bit| bit.to_le_bytes());
+
+        bytes.fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ byte as u128).wrapping_mul(FNV_PRIME))
+    }
+
+    /// An estimate of the memory used by the candidate and whatever it points
+    /// to, i.e. its bitmap or boxed words.
+    pub fn size_in_bytes(&self) -> usize {
+        match &self.storage {
+            Storage::Roaring(bitmap) => mem::size_of::<Self>() + bitmap.get_serialized_size_in_bytes(),
+            Storage::Twelve(bitset) => mem::size_of::<Self>() + mem::size_of_val(&**bitset),
+            _ => mem::size_of::<Self>(),
+        }
+    }
+
+    // The number of words is written first, or zero for a bitmap. Bitmaps are
+    // written as runs of bits rather than in roaring's own format because
+    // deserializing that doesn't check the bytes it's given.
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        match &self.storage {
+            Storage::Roaring(bitmap) => {
+                let runs = Self::runs(bitmap);
+
+                encoder.write_usize(0)?;
+                encoder.write_usize(runs.len())?;
+
+                for (first, last) in runs {
+                    encoder.write_usize(first as usize)?;
+                    encoder.write_usize(last as usize)?;
+                }
+
+                Ok(())
+            },
+            storage => {
+                let words = storage.words();
+                encoder.write_usize(words.len())?;
+
+                for word in words {
+                    encoder.write_usize(*word as usize)?;
+                }
+
+                Ok(())
+            },
+        }
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self> {
+        let len = decoder.read_usize()?;
+
+        if len == 0 {
+            let mut bitmap = Bitmap::create();
+
+            for _ in 0..decoder.read_usize()? {
+                let first = decoder.read_usize()?;
+                let last = decoder.read_usize()?;
+
+                if first > last || last > u32::MAX as usize {
+                    return Err(io::Error::new(ErrorKind::InvalidData, "invalid run of bits for a candidate"));
+                }
+
+                bitmap.add_range(first as u64..last as u64 + 1);
+            }
+
+            bitmap.run_optimize();
+
+            return Ok(Self { storage: Storage::Roaring(bitmap) });
+        }
+
+        if len > MAXIMUM_WORDS {
+            return Err(io::Error::new(ErrorKind::InvalidData, "too many words for a candidate"));
+        }
+
+        let mut words = [0; MAXIMUM_WORDS];
+
+        for word in &mut words[..len] {
+            *word = decoder.read_usize()? as u64;
+        }
+
+        let storage = match len {
+            1 => Bitset::from_words(&words[..1]).map(Storage::One),
+            2 => Bitset::from_words(&words[..2]).map(Storage::Two),
+            _ => Bitset::from_words(&words[..len]).map(|bitset| Storage::Twelve(Box::new(bitset))),
+        };
+
+        match storage {
+            Some(storage) => Ok(Self { storage }),
+            None => Err(io::Error::new(ErrorKind::InvalidData, "unexpected number of words for a candidate")),
+        }
+    }
+
+    // The first and last bit of each run of consecutive bits.
+    fn runs(bitmap: &Bitmap) -> Vec<(u32, u32)> {
+        let mut runs: Vec<(u32, u32)> = vec![];
+
+        for bit in bitmap.iter() {
+            match runs.last_mut() {
+                Some((_, last)) if *last + 1 == bit => *last = bit,
+                _ => runs.push((bit, bit)),
+            }
+        }
+
+        runs
+    }
+}
+
+impl Storage {
+    fn empty(problem: &Problem) -> Self {
+        match (problem.representation(), problem.words()) {
+            (Representation::Roaring, _) => Storage::Roaring(Bitmap::create_with_capacity(problem.capacity())),
+            (Representation::Words, 1) => Storage::One(Bitset::new()),
+            (Representation::Words, 2) => Storage::Two(Bitset::new()),
+            (Representation::Words, _) => Storage::Twelve(Box::new(Bitset::new())),
+        }
+    }
+
+    fn add(&mut self, bit: u32) {
+        match self {
+            Storage::Roaring(bitmap) => bitmap.add(bit),
+            Storage::One(bitset) => bitset.add(bit),
+            Storage::Two(bitset) => bitset.add(bit),
+            Storage::Twelve(bitset) => bitset.add(bit),
+        }
+    }
+
+    fn optimize(&mut self) {
+        if let Storage::Roaring(bitmap) = self {
+            bitmap.run_optimize();
+        }
+    }
+
+    fn cardinality(&self) -> usize {
+        match self {
+            Storage::Roaring(bitmap) => bitmap.cardinality() as usize,
+            Storage::One(bitset) => bitset.cardinality(),
+            Storage::Two(bitset) => bitset.cardinality(),
+            Storage::Twelve(bitset) => bitset.cardinality(),
+        }
+    }
+
+    fn maximum(&self) -> u32 {
+        match self {
+            Storage::Roaring(bitmap) => bitmap.maximum(),
+            Storage::One(bitset) => bitset.maximum().unwrap_or(0),
+            Storage::Two(bitset) => bitset.maximum().unwrap_or(0),
+            Storage::Twelve(bitset) => bitset.maximum().unwrap_or(0),
+        }
+    }
+
+    // Empty for a bitmap.
+    fn words(&self) -> &[u64] {
+        match self {
+            Storage::Roaring(_) => &[],
+            Storage::One(bitset) => bitset.words(),
+            Storage::Two(bitset) => bitset.words(),
+            Storage::Twelve(bitset) => bitset.words(),
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = u32> + '_ {
+        let bitmap = match self {
+            Storage::Roaring(bitmap) => Some(bitmap),
+            _ => None,
+        };
+
+        bitmap.into_iter().flat_map(|b| b.iter()).chain(Ones::new(self.words()))
+    }
+}
+
+// So that the symmetry's mapping can be looked up for a candidate directly.
+impl Bits for Candidate {
+    fn contains(&self, bit: u32) -> bool {
+        self.storage.contains(bit)
+    }
+}
+
+impl Bits for Storage {
+    fn contains(&self, bit: u32) -> bool {
+        match self {
+            Storage::Roaring(bitmap) => bitmap.contains(bit),
+            Storage::One(bitset) => bitset.contains(bit),
+            Storage::Two(bitset) => bitset.contains(bit),
+            Storage::Twelve(bitset) => bitset.contains(bit),
+        }
+    }
+}
+
+// Display doesn't have access to the problem so work out the number of symbols
+// from the highest bit. The first counter bit is always set so the highest bit
+// lies between n! and the capacity, which don't overlap for different n.
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let highest_bit = self.storage.maximum() as usize;
+        let symbols = (1..).take_while(|n| Utility::factorial(*n) <= highest_bit).last().unwrap_or(1);
+
+        let permutations = self.permutations_of(symbols).iter().map(|p| {
+            p.iter().map(|s| s.to_string()).collect::<String>()
+        }).collect::<Vec<_>>();
+
+        let unique_tail_symbols = self.number_of_bits() - permutations.len() + 1;
+        let ready = unique_tail_symbols == symbols - 1;
+
+        writeln!(f, "Permutations ({}): {}", permutations.len(), permutations.join(" "))?;
+        writeln!(f, "Unique tail symbols: {}", unique_tail_symbols)?;
+        write!(f, "Ready: {}", if ready { "yes" } else { "no" })
+    }
+}
+
+impl PartialEq for Candidate {
+    fn eq(&self, other: &Self) -> bool {
+        match (&self.storage, &other.storage) {
+            (Storage::Roaring(a), Storage::Roaring(b)) => a == b,
+            (a, b) if a.words().len() == b.words().len() => a.words() == b.words(),
+            _ => self.cmp(other) == Equal,
+        }
     }
 }
 
 impl Eq for Candidate { }
 
+// Words are compared directly but in the same order as bitmaps.
 impl Ord for Candidate {
     fn cmp(&self, other: &Self) -> Ordering {
-        let left = &self.bitmap;
-        let right = &other.bitmap;
+        let left = &self.storage;
+        let right = &other.storage;
+
+        match (left, right) {
+            (Storage::One(a), Storage::One(b)) => return a.cmp(b),
+            (Storage::Two(a), Storage::Two(b)) => return a.cmp(b),
+            (Storage::Twelve(a), Storage::Twelve(b)) => return a.cmp(b),
+            _ => {},
+        }
 
         let left_len = left.cardinality();
         let right_len = right.cardinality();
@@ -77,7 +450,7 @@ impl Ord for Candidate {
         for (a, b) in left.iter().zip(right.iter()) {
             match a.cmp(&b) {
                 Equal => continue,
-                o @ _ => return o,
+                o => return o,
             };
         }
 
@@ -91,5 +464,13 @@ impl PartialOrd for Candidate {
     }
 }
 
+impl Hash for Candidate {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        for bit in self.storage.iter() {
+            bit.hash(state);
+        }
+    }
+}
+
 #[cfg(test)]
 mod test;
@@ -1,10 +1,13 @@
-#![cfg(not(feature = "four_symbols"))]
-
 use lehmer::Lehmer;
 use super::*;
+use crate::path::Path;
 
 type Subject = Candidate;
 
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
 const T: bool = true;
 const F: bool = false;
 
@@ -12,12 +15,12 @@ fn seen_permutation(subject: &Subject, slice: &[u8]) -> bool {
     let lehmer = Lehmer::from_permutation(slice);
     let decimal = lehmer.to_decimal() as u32;
 
-    subject.bitmap.contains(decimal)
+    subject.storage.contains(decimal)
 }
 
 fn counter_bits(subject: &Subject) -> Vec<bool> {
-    let range = (*FACTORIAL as u32)..*CAPACITY;
-    range.map(|i| subject.bitmap.contains(i)).collect()
+    let range = (PROBLEM.factorial() as u32)..PROBLEM.capacity();
+    range.map(|i| subject.storage.contains(i)).collect()
 }
 
 mod seed {
@@ -25,18 +28,89 @@ mod seed {
 
     #[test]
     fn it_has_seen_the_first_permutation() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
 
-        assert_eq!(seen_permutation(&subject, &[0, 1, 2, 3, 4]), true);
-        assert_eq!(seen_permutation(&subject, &[0, 1, 2, 4, 3]), false);
+        assert!(seen_permutation(&subject, &[0, 1, 2, 3, 4]));
+        assert!(!seen_permutation(&subject, &[0, 1, 2, 4, 3]));
     }
 
     #[test]
     fn it_sets_all_the_counter_bits() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
 
         assert_eq!(counter_bits(&subject), &[T, T, T]);
     }
+
+    #[test]
+    fn it_seeds_candidates_for_different_numbers_of_symbols() {
+        for n in 3..=6 {
+            let problem = Problem::new(n);
+            let subject = Subject::seed(&problem);
+
+            assert_eq!(subject.number_of_permutations(&problem), 1);
+            assert_eq!(subject.number_of_bits(), 1 + (n - 2));
+        }
+    }
+}
+
+mod from_string {
+    use super::*;
+
+    #[test]
+    fn it_replays_the_string_through_the_canonicalising_expansions() {
+        let expected = Subject::seed(&PROBLEM).expand(&PROBLEM, 0).expand(&PROBLEM, 2);
+        let string = Path::new(&PROBLEM, Subject::seed(&PROBLEM), vec![0, 2]).to_string();
+
+        assert_eq!(Subject::from_string(&PROBLEM, &string), Ok(expected));
+    }
+
+    #[test]
+    fn it_relabels_strings_that_start_with_a_different_permutation() {
+        let subject = Subject::from_string(&PROBLEM, "432104").unwrap();
+        let expected = Subject::from_string(&PROBLEM, "012340").unwrap();
+
+        assert_eq!(subject, expected);
+    }
+
+    #[test]
+    fn it_returns_the_seed_for_the_first_permutation() {
+        assert_eq!(Subject::from_string(&PROBLEM, "01234"), Ok(Subject::seed(&PROBLEM)));
+    }
+}
+
+mod canonical_symbols {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_symbols_that_were_expanded_to_produce_the_string() {
+        let symbols = vec![0, 0, 1, 3, 2, 0, 1, 0, 3];
+        let string = Path::new(&PROBLEM, Subject::seed(&PROBLEM), symbols.clone()).to_string();
+
+        assert_eq!(Subject::canonical_symbols(&PROBLEM, &string), Ok(symbols));
+    }
+
+    #[test]
+    fn it_returns_an_error_if_the_string_does_not_start_with_a_permutation() {
+        assert!(Subject::canonical_symbols(&PROBLEM, "01230").is_err());
+        assert!(Subject::canonical_symbols(&PROBLEM, "0123").is_err());
+        assert!(Subject::canonical_symbols(&PROBLEM, "012356").is_err());
+    }
+
+    #[test]
+    fn it_returns_an_error_if_the_string_repeats_a_symbol_or_has_too_many() {
+        assert!(Subject::canonical_symbols(&PROBLEM, "0123444").is_err());
+        assert!(Subject::canonical_symbols(&PROBLEM, "0123456").is_err());
+    }
+}
+
+mod first_permutation {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_permutation_the_string_starts_with() {
+        assert_eq!(Subject::first_permutation(&PROBLEM, "31204312"), Ok(vec![3, 1, 2, 0, 4]));
+        assert!(Subject::first_permutation(&PROBLEM, "31304").is_err());
+    }
 }
 
 mod expand {
@@ -44,109 +118,125 @@ mod expand {
 
     #[test]
     fn it_maps_the_permutation_to_its_canonical_form() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
 
         // For the 12340 transposition: 01234 -> 40123
-        assert_eq!(seen_permutation(&subject.expand(0), &[4, 0, 1, 2, 3]), true);
+        assert!(seen_permutation(&subject.expand(&PROBLEM, 0), &[4, 0, 1, 2, 3]));
 
         // For the *2341 transposition: 01234 -> 04123
-        assert_eq!(seen_permutation(&subject.expand(1), &[0, 4, 1, 2, 3]), true);
+        assert!(seen_permutation(&subject.expand(&PROBLEM, 1), &[0, 4, 1, 2, 3]));
 
         // For the **342 transposition: 01234 -> { 01423, 10423 }
         // 01423 is chosen because it's before 10423 in the bitmap
-        assert_eq!(seen_permutation(&subject.expand(2), &[0, 1, 4, 2, 3]), true);
-        assert_eq!(seen_permutation(&subject.expand(2), &[1, 0, 4, 2, 3]), false);
+        assert!(seen_permutation(&subject.expand(&PROBLEM, 2), &[0, 1, 4, 2, 3]));
+        assert!(!seen_permutation(&subject.expand(&PROBLEM, 2), &[1, 0, 4, 2, 3]));
 
         // For the ***43 transposition: 01234 -> { 01243, 02143, 10243, 20143, 12043, 21043 }
         // 01243 is chosen because it's before the others in the bitmap
-        assert_eq!(seen_permutation(&subject.expand(3), &[0, 1, 2, 4, 3]), true);
-        assert_eq!(seen_permutation(&subject.expand(3), &[0, 2, 1, 4, 3]), false);
-        assert_eq!(seen_permutation(&subject.expand(3), &[1, 0, 2, 4, 3]), false);
-        assert_eq!(seen_permutation(&subject.expand(3), &[2, 0, 1, 4, 3]), false);
-        assert_eq!(seen_permutation(&subject.expand(3), &[1, 2, 0, 4, 3]), false);
-        assert_eq!(seen_permutation(&subject.expand(3), &[2, 1, 0, 4, 3]), false);
+        assert!(seen_permutation(&subject.expand(&PROBLEM, 3), &[0, 1, 2, 4, 3]));
+        assert!(!seen_permutation(&subject.expand(&PROBLEM, 3), &[0, 2, 1, 4, 3]));
+        assert!(!seen_permutation(&subject.expand(&PROBLEM, 3), &[1, 0, 2, 4, 3]));
+        assert!(!seen_permutation(&subject.expand(&PROBLEM, 3), &[2, 0, 1, 4, 3]));
+        assert!(!seen_permutation(&subject.expand(&PROBLEM, 3), &[1, 2, 0, 4, 3]));
+        assert!(!seen_permutation(&subject.expand(&PROBLEM, 3), &[2, 1, 0, 4, 3]));
     }
 
     #[test]
     fn it_adds_a_new_permutation_when_the_zero_symbol_is_expanded() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
 
-        assert_eq!(subject.expand(0).number_of_permutations(), 2);
-        assert_eq!(subject.expand(1).number_of_permutations(), 1);
-        assert_eq!(subject.expand(2).number_of_permutations(), 1);
-        assert_eq!(subject.expand(3).number_of_permutations(), 1);
+        assert_eq!(subject.expand(&PROBLEM, 0).number_of_permutations(&PROBLEM), 2);
+        assert_eq!(subject.expand(&PROBLEM, 1).number_of_permutations(&PROBLEM), 1);
+        assert_eq!(subject.expand(&PROBLEM, 2).number_of_permutations(&PROBLEM), 1);
+        assert_eq!(subject.expand(&PROBLEM, 3).number_of_permutations(&PROBLEM), 1);
 
-        assert_eq!(seen_permutation(&subject.expand(0), &[0, 1, 2, 3, 4]), true);
+        assert!(seen_permutation(&subject.expand(&PROBLEM, 0), &[0, 1, 2, 3, 4]));
     }
 
     #[test]
     fn it_incrementally_adds_new_permutations() {
-        let subject = Subject::seed(); // 01234
+        let subject = Subject::seed(&PROBLEM); // 01234
 
         // The comments use the un-canonicalised form for simplicity.
 
-        let candidate = subject.expand(0); // 012340
-        assert_eq!(candidate.number_of_permutations(), 2);
+        let candidate = subject.expand(&PROBLEM, 0); // 012340
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 2);
 
-        let candidate = candidate.expand(0); // 0123401
-        assert_eq!(candidate.number_of_permutations(), 3);
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123401
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 3);
 
-        let candidate = candidate.expand(0); // 01234012
-        assert_eq!(candidate.number_of_permutations(), 4);
+        let candidate = candidate.expand(&PROBLEM, 0); // 01234012
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 4);
 
-        let candidate = candidate.expand(0); // 012340123
-        assert_eq!(candidate.number_of_permutations(), 5);
+        let candidate = candidate.expand(&PROBLEM, 0); // 012340123
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 5);
 
-        let candidate = candidate.expand(0); // 0123401234
-        assert_eq!(candidate.number_of_permutations(), 5); // <-- the same
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123401234
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 5); // <-- the same
 
-        let candidate = candidate.expand(1); // 01234012341
-        assert_eq!(candidate.number_of_permutations(), 5);
+        let candidate = candidate.expand(&PROBLEM, 1); // 01234012341
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 5);
 
-        let candidate = candidate.expand(0); // 012340123410
-        assert_eq!(candidate.number_of_permutations(), 6);
+        let candidate = candidate.expand(&PROBLEM, 0); // 012340123410
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 6);
 
-        let candidate = candidate.expand(1); // 0123401234103
-        assert_eq!(candidate.number_of_permutations(), 6);
+        let candidate = candidate.expand(&PROBLEM, 1); // 0123401234103
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 6);
 
-        let candidate = candidate.expand(0); // 01234012341032
-        assert_eq!(candidate.number_of_permutations(), 7);
+        let candidate = candidate.expand(&PROBLEM, 0); // 01234012341032
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 7);
 
-        let candidate = candidate.expand(2); // 012340123410320
-        assert_eq!(candidate.number_of_permutations(), 7);
+        let candidate = candidate.expand(&PROBLEM, 2); // 012340123410320
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 7);
 
-        let candidate = candidate.expand(1); // 0123401234103204
-        assert_eq!(candidate.number_of_permutations(), 7);
+        let candidate = candidate.expand(&PROBLEM, 1); // 0123401234103204
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 7);
 
-        let candidate = candidate.expand(0); // 01234012341032041
-        assert_eq!(candidate.number_of_permutations(), 8);
+        let candidate = candidate.expand(&PROBLEM, 0); // 01234012341032041
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 8);
     }
 
     #[test]
     fn it_sets_counter_bits_based_on_the_expanded_symbol() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
 
-        assert_eq!(counter_bits(&subject.expand(0)), &[T, T, T]);
-        assert_eq!(counter_bits(&subject.expand(1)), &[T, T, T]);
-        assert_eq!(counter_bits(&subject.expand(2)), &[T, T, F]);
-        assert_eq!(counter_bits(&subject.expand(3)), &[T, F, F]);
+        assert_eq!(counter_bits(&subject.expand(&PROBLEM, 0)), &[T, T, T]);
+        assert_eq!(counter_bits(&subject.expand(&PROBLEM, 1)), &[T, T, T]);
+        assert_eq!(counter_bits(&subject.expand(&PROBLEM, 2)), &[T, T, F]);
+        assert_eq!(counter_bits(&subject.expand(&PROBLEM, 3)), &[T, F, F]);
     }
 
     #[test]
     fn it_adds_removed_counter_bits_based_on_the_expanded_symbol() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
+
+        let candidate = subject.expand(&PROBLEM, 2); // one bit removed
+        assert_eq!(counter_bits(&candidate.expand(&PROBLEM, 0)), &[T, T, T]);
+        assert_eq!(counter_bits(&candidate.expand(&PROBLEM, 1)), &[T, T, T]);
+        assert_eq!(counter_bits(&candidate.expand(&PROBLEM, 2)), &[T, T, F]);
+        assert_eq!(counter_bits(&candidate.expand(&PROBLEM, 3)), &[T, F, F]);
+
+        let candidate = subject.expand(&PROBLEM, 3); // two bits removed
+        assert_eq!(counter_bits(&candidate.expand(&PROBLEM, 0)), &[T, T, F]);
+        assert_eq!(counter_bits(&candidate.expand(&PROBLEM, 1)), &[T, T, F]);
+        assert_eq!(counter_bits(&candidate.expand(&PROBLEM, 2)), &[T, T, F]);
+        assert_eq!(counter_bits(&candidate.expand(&PROBLEM, 3)), &[T, F, F]);
+    }
+}
 
-        let candidate = subject.expand(2); // one bit removed
-        assert_eq!(counter_bits(&candidate.expand(0)), &[T, T, T]);
-        assert_eq!(counter_bits(&candidate.expand(1)), &[T, T, T]);
-        assert_eq!(counter_bits(&candidate.expand(2)), &[T, T, F]);
-        assert_eq!(counter_bits(&candidate.expand(3)), &[T, F, F]);
+mod relabelling {
+    use super::*;
 
-        let candidate = subject.expand(3); // two bits removed
-        assert_eq!(counter_bits(&candidate.expand(0)), &[T, T, F]);
-        assert_eq!(counter_bits(&candidate.expand(1)), &[T, T, F]);
-        assert_eq!(counter_bits(&candidate.expand(2)), &[T, T, F]);
-        assert_eq!(counter_bits(&candidate.expand(3)), &[T, F, F]);
+    #[test]
+    fn it_returns_how_the_tail_symbols_are_relabelled_by_the_expansion() {
+        let subject = Subject::seed(&PROBLEM);
+
+        // Index is the new label and value is the old label, e.g. for 01234
+        // expanded by 0 the tail becomes 12340 which is relabelled to 01234.
+        assert_eq!(subject.relabelling(&PROBLEM, 0), &[1, 2, 3, 4, 0]);
+        assert_eq!(subject.relabelling(&PROBLEM, 1), &[0, 2, 3, 4, 1]);
+        assert_eq!(subject.relabelling(&PROBLEM, 2), &[0, 1, 3, 4, 2]);
+        assert_eq!(subject.relabelling(&PROBLEM, 3), &[0, 1, 2, 4, 3]);
     }
 }
 
@@ -155,17 +245,112 @@ mod number_of_permutations {
 
     #[test]
     fn it_returns_the_number_of_permutations_that_have_been_seen() {
-        let subject = Subject::seed();
-        let first = subject.expand(0);
-        let second = subject.expand(1);
-        let third = subject.expand(2);
-        let fourth = subject.expand(3);
+        let subject = Subject::seed(&PROBLEM);
+        let first = subject.expand(&PROBLEM, 0);
+        let second = subject.expand(&PROBLEM, 1);
+        let third = subject.expand(&PROBLEM, 2);
+        let fourth = subject.expand(&PROBLEM, 3);
+
+        assert_eq!(subject.number_of_permutations(&PROBLEM), 1);
+        assert_eq!(first.number_of_permutations(&PROBLEM), 2);
+        assert_eq!(second.number_of_permutations(&PROBLEM), 1);
+        assert_eq!(third.number_of_permutations(&PROBLEM), 1);
+        assert_eq!(fourth.number_of_permutations(&PROBLEM), 1);
+    }
+}
+
+mod permutations {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_permutations_as_symbols_in_lehmer_order() {
+        let subject = Subject::seed(&PROBLEM);
+        assert_eq!(subject.permutations(&PROBLEM), &[vec![0, 1, 2, 3, 4]]);
+
+        let subject = subject.expand(&PROBLEM, 0);
+        assert_eq!(subject.permutations(&PROBLEM), &[vec![0, 1, 2, 3, 4], vec![4, 0, 1, 2, 3]]);
+    }
+}
+
+mod permutation_indexes {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_lehmer_index_of_each_permutation() {
+        let subject = Subject::seed(&PROBLEM).expand(&PROBLEM, 0).expand(&PROBLEM, 0);
+
+        let expected = subject.permutations(&PROBLEM).iter()
+            .map(|p| Lehmer::from_permutation(p).to_decimal())
+            .collect::<Vec<_>>();
 
-        assert_eq!(subject.number_of_permutations(), 1);
-        assert_eq!(first.number_of_permutations(), 2);
-        assert_eq!(second.number_of_permutations(), 1);
-        assert_eq!(third.number_of_permutations(), 1);
-        assert_eq!(fourth.number_of_permutations(), 1);
+        assert_eq!(subject.permutation_indexes(&PROBLEM).collect::<Vec<_>>(), expected);
+        assert_eq!(expected.len(), 3);
+    }
+}
+
+mod unique_tail_symbols {
+    use super::*;
+
+    #[test]
+    fn it_decodes_the_counter_bits() {
+        let subject = Subject::seed(&PROBLEM);
+
+        assert_eq!(subject.unique_tail_symbols(&PROBLEM), 4);                       // 01234
+        assert_eq!(subject.expand(&PROBLEM, 0).unique_tail_symbols(&PROBLEM), 4);   // 012340
+        assert_eq!(subject.expand(&PROBLEM, 2).unique_tail_symbols(&PROBLEM), 3);   // 012342
+        assert_eq!(subject.expand(&PROBLEM, 3).unique_tail_symbols(&PROBLEM), 2);   // 012343
+    }
+}
+
+mod is_ready {
+    use super::*;
+
+    #[test]
+    fn it_returns_true_if_the_next_symbol_can_complete_a_permutation() {
+        let subject = Subject::seed(&PROBLEM);
+
+        assert!(subject.is_ready(&PROBLEM));
+        assert!(subject.expand(&PROBLEM, 1).is_ready(&PROBLEM));
+        assert!(!subject.expand(&PROBLEM, 2).is_ready(&PROBLEM));
+    }
+}
+
+mod symbols_until_ready {
+    use super::*;
+
+    #[test]
+    fn it_returns_how_many_symbols_are_needed_before_the_tail_is_unique_enough() {
+        let subject = Subject::seed(&PROBLEM);
+
+        assert_eq!(subject.symbols_until_ready(&PROBLEM), 0);                       // 01234
+        assert_eq!(subject.expand(&PROBLEM, 2).symbols_until_ready(&PROBLEM), 1);   // 012342
+        assert_eq!(subject.expand(&PROBLEM, 3).symbols_until_ready(&PROBLEM), 2);   // 012343
+        assert_eq!(subject.expand(&PROBLEM, 3).expand(&PROBLEM, 0).symbols_until_ready(&PROBLEM), 1);
+    }
+}
+
+mod fmt {
+    use super::*;
+
+    #[test]
+    fn it_prints_the_permutations_and_tail_state() {
+        let subject = Subject::seed(&PROBLEM).expand(&PROBLEM, 3);
+
+        assert_eq!(subject.to_string(), "\
+Permutations (1): 01243
+Unique tail symbols: 2
+Ready: no");
+    }
+
+    #[test]
+    fn it_works_out_the_number_of_symbols_from_the_bitmap() {
+        let problem = Problem::new(3);
+        let subject = Subject::seed(&problem).expand(&problem, 0);
+
+        assert_eq!(subject.to_string(), "\
+Permutations (2): 012 201
+Unique tail symbols: 2
+Ready: yes");
     }
 }
 
@@ -174,11 +359,11 @@ mod number_of_bits {
 
     #[test]
     fn it_returns_the_number_of_bits_that_are_set_in_the_bitmap() {
-        let subject = Subject::seed();
-        let first = subject.expand(0);
-        let second = subject.expand(1);
-        let third = subject.expand(2);
-        let fourth = subject.expand(3);
+        let subject = Subject::seed(&PROBLEM);
+        let first = subject.expand(&PROBLEM, 0);
+        let second = subject.expand(&PROBLEM, 1);
+        let third = subject.expand(&PROBLEM, 2);
+        let fourth = subject.expand(&PROBLEM, 3);
 
         assert_eq!(subject.number_of_bits(), 4); // 1 perm, 3 counters
         assert_eq!(first.number_of_bits(), 5);   // 2 perms, 3 counters
@@ -193,7 +378,10 @@ mod maximum_permutations {
 
     #[test]
     fn it_returns_the_factorial_of_the_number_of_symbols() {
-        assert_eq!(Subject::maximum_permutations(), *FACTORIAL as usize);
+        assert_eq!(Subject::maximum_permutations(&PROBLEM), 120);
+
+        let problem = Problem::new(4);
+        assert_eq!(Subject::maximum_permutations(&problem), 24);
     }
 }
 
@@ -202,17 +390,17 @@ mod ord {
 
     #[test]
     fn it_consistently_orders_candidates() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
 
-        let a = subject.expand(0);
-        let b = subject.expand(1);
-        let c = subject.expand(2);
-        let d = subject.expand(0);
+        let a = subject.expand(&PROBLEM, 0);
+        let b = subject.expand(&PROBLEM, 1);
+        let c = subject.expand(&PROBLEM, 2);
+        let d = subject.expand(&PROBLEM, 0);
 
         // Order is total and antisymmetric:
         assert!(a > b);
-        assert!(!(a == b));
-        assert!(!(a < b));
+        assert!(a != b);
+        assert!(a >= b);
 
         // Order is transistive:
         assert!(a > b);
@@ -221,8 +409,8 @@ mod ord {
 
         // Order is equal for the same bitmap:
         assert!(a == d);
-        assert!(!(a < d));
-        assert!(!(a > d));
+        assert!(a >= d);
+        assert!(a <= d);
     }
 
     #[test]
@@ -230,9 +418,172 @@ mod ord {
         let shorter = Bitmap::of(&[0, 1, 2]);
         let longer = Bitmap::of(&[0, 1, 2, 3]);
 
-        let a = Candidate { bitmap: shorter };
-        let b = Candidate { bitmap: longer };
+        let a = Candidate { storage: Storage::Roaring(shorter) };
+        let b = Candidate { storage: Storage::Roaring(longer) };
 
         assert!(a < b);
     }
 }
+
+mod hash {
+    use super::*;
+    use std::collections::hash_map::DefaultHasher;
+
+    fn hash_of(subject: &Subject) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        subject.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn it_hashes_equal_candidates_the_same() {
+        let subject = Subject::seed(&PROBLEM);
+
+        let a = subject.expand(&PROBLEM, 0);
+        let b = subject.expand(&PROBLEM, 0);
+        let c = subject.expand(&PROBLEM, 1);
+
+        assert_eq!(hash_of(&a), hash_of(&b));
+        assert_ne!(hash_of(&a), hash_of(&c));
+    }
+}
+
+mod fingerprint {
+    use super::*;
+
+    #[test]
+    fn it_is_the_same_for_equal_candidates_and_uses_all_128_bits() {
+        let subject = Subject::seed(&PROBLEM);
+
+        let a = subject.expand(&PROBLEM, 0);
+        let b = subject.expand(&PROBLEM, 0);
+        let c = subject.expand(&PROBLEM, 1);
+
+        assert_eq!(a.fingerprint(), b.fingerprint());
+        assert_ne!(a.fingerprint(), c.fingerprint());
+
+        assert_ne!(a.fingerprint() as u64, (a.fingerprint() >> 64) as u64);
+    }
+
+    #[test]
+    fn it_is_a_fixed_hash_that_does_not_depend_on_the_build_or_representation() {
+        for &representation in &[Representation::Words, Representation::Roaring] {
+            let problem = Problem::with_representation(5, representation);
+
+            assert_eq!(Subject::seed(&problem).fingerprint(), 0x70a1_9c18_1578_11d8_c25e_75f8_a2ca_51d6);
+        }
+    }
+}
+
+mod write_to {
+    use super::*;
+    use crate::codec::{Encoder, Decoder};
+
+    #[test]
+    fn it_can_be_read_back_with_read_from() {
+        let subject = Subject::seed(&PROBLEM).expand(&PROBLEM, 0).expand(&PROBLEM, 2);
+        let mut bytes = vec![];
+
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+        let result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+        assert_eq!(result, subject);
+    }
+
+    #[test]
+    fn it_rejects_an_invalid_run_of_bits_in_a_bitmap() {
+        let mut bytes = vec![];
+        let mut encoder = Encoder::new(&mut bytes);
+
+        encoder.write_usize(0).unwrap();
+        encoder.write_usize(1).unwrap();
+        encoder.write_usize(5).unwrap();
+        encoder.write_usize(4).unwrap();
+
+        assert!(Subject::read_from(&mut Decoder::new(&bytes[..])).is_err());
+    }
+}
+
+mod representation {
+    use super::*;
+
+    // Expands every candidate to this depth breadth first.
+    fn expansions(problem: &Problem, depth: usize) -> Vec<Subject> {
+        let mut frontier = vec![Subject::seed(problem)];
+        let mut all = frontier.clone();
+
+        for _ in 0..depth {
+            frontier = frontier.iter().flat_map(|c| (0..problem.expansions()).map(move |s| c.expand(problem, s))).collect();
+            all.extend(frontier.iter().cloned());
+        }
+
+        all
+    }
+
+    #[test]
+    fn it_expands_and_orders_the_same_in_words_as_in_a_bitmap() {
+        for &(symbols, depth) in &[(3, 6), (4, 5), (5, 4), (6, 3)] {
+            let words = Problem::with_representation(symbols, Representation::Words);
+            let roaring = Problem::with_representation(symbols, Representation::Roaring);
+
+            let mut a = expansions(&words, depth);
+            let mut b = expansions(&roaring, depth);
+
+            for (x, y) in a.iter().zip(&b) {
+                assert_eq!(x.to_string(), y.to_string());
+                assert_eq!(x.fingerprint(), y.fingerprint());
+                assert_eq!(x, y);
+            }
+
+            a.sort();
+            b.sort();
+
+            let a = a.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            let b = b.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+
+            assert_eq!(a, b);
+        }
+    }
+
+    #[test]
+    fn it_uses_less_memory_in_words() {
+        let words = Problem::with_representation(5, Representation::Words);
+        let roaring = Problem::with_representation(5, Representation::Roaring);
+
+        let a = Subject::seed(&words).expand(&words, 0);
+        let b = Subject::seed(&roaring).expand(&roaring, 0);
+
+        assert!(a.size_in_bytes() < b.size_in_bytes());
+    }
+
+    #[test]
+    fn it_boxes_the_widest_words_and_counts_them_in_its_size() {
+        let narrow = Problem::with_representation(5, Representation::Words);
+        let wide = Problem::with_representation(6, Representation::Words);
+
+        let a = Subject::seed(&narrow).expand(&narrow, 0);
+        let b = Subject::seed(&wide).expand(&wide, 0);
+
+        assert!(mem::size_of::<Subject>() < mem::size_of::<Bitset<MAXIMUM_WORDS>>());
+
+        assert_eq!(a.size_in_bytes(), mem::size_of::<Subject>());
+        assert_eq!(b.size_in_bytes(), mem::size_of::<Subject>() + mem::size_of::<Bitset<MAXIMUM_WORDS>>());
+    }
+
+    #[test]
+    fn it_can_be_written_and_read_back_in_either_representation() {
+        use crate::codec::{Encoder, Decoder};
+
+        for &representation in &[Representation::Words, Representation::Roaring] {
+            let problem = Problem::with_representation(6, representation);
+            let subject = Subject::seed(&problem).expand(&problem, 0).expand(&problem, 3);
+            let mut bytes = vec![];
+
+            subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+            let result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+            assert_eq!(result, subject);
+            assert_eq!(result.storage, subject.storage);
+        }
+    }
+}
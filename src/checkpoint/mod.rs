@@ -0,0 +1,109 @@
+use crate::backend::Backend;
+use crate::codec::{Encoder, Decoder};
+use crate::incremental::Incremental;
+use crate::problem::{Problem, SUPPORTED_SYMBOLS};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, ErrorKind};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const MAGIC: &[u8] = b"supermutation";
+const VERSION: usize = 15;
+
+/// Periodically saves an incremental search so that it can be resumed.
+pub struct Checkpoint {
+    path: PathBuf,
+    interval: Duration,
+    last_saved: Instant,
+}
+
+impl Checkpoint {
+    pub fn new<P: Into<PathBuf>>(path: P, interval: Duration) -> Self {
+        Self { path: path.into(), interval, last_saved: Instant::now() }
+    }
+
+    pub fn is_due(&self) -> bool {
+        self.last_saved.elapsed() >= self.interval
+    }
+
+    pub fn due_at(&self) -> Instant {
+        self.last_saved + self.interval
+    }
+
+    /// The next one is due after another interval even if this one failed so
+    /// that a full disk isn't retried after every expansion.
+    pub fn save<'a, B: Backend<'a>>(&mut self, incremental: &Incremental<'a, B>) -> io::Result<()> {
+        let result = Self::write(&self.path, incremental);
+        self.last_saved = Instant::now();
+
+        result
+    }
+
+    /// Write to a temporary file first so that an interrupted write never
+    /// clobbers the previous checkpoint.
+    pub fn write<'a, P: AsRef<Path>, B: Backend<'a>>(path: P, incremental: &Incremental<'a, B>) -> io::Result<()> {
+        let path = path.as_ref();
+        let temporary = path.with_extension("tmp");
+
+        let file = File::create(&temporary)?;
+        let mut encoder = Encoder::new(BufWriter::new(file));
+
+        encoder.write_bytes(MAGIC)?;
+        encoder.write_usize(VERSION)?;
+        encoder.write_usize(incremental.problem().symbols())?;
+        encoder.write_bytes(B::NAME.as_bytes())?;
+
+        incremental.write_to(&mut encoder)?;
+        encoder.flush()?;
+
+        fs::rename(&temporary, path)
+    }
+
+    /// Reads a checkpoint that was written by the same kind of backend.
+    pub fn read<'a, P: AsRef<Path>, B: Backend<'a>>(path: P, problem: &'a Problem) -> io::Result<Incremental<'a, B>> {
+        let mut decoder = Self::open(path)?;
+
+        if decoder.read_usize()? != problem.symbols() {
+            return Err(invalid("checkpoint is for a different number of symbols"));
+        }
+
+        if decoder.read_bytes()? != B::NAME.as_bytes() {
+            return Err(invalid("checkpoint is for a different algorithm"));
+        }
+
+        Incremental::read_from(&mut decoder, problem)
+    }
+
+    /// Reads the number of symbols from the header so the problem can be built.
+    pub fn read_symbols<P: AsRef<Path>>(path: P) -> io::Result<usize> {
+        let symbols = Self::open(path)?.read_usize()?;
+
+        if !SUPPORTED_SYMBOLS.contains(&symbols) {
+            return Err(invalid("checkpoint is for an unsupported number of symbols"));
+        }
+
+        Ok(symbols)
+    }
+
+    fn open<P: AsRef<Path>>(path: P) -> io::Result<Decoder<BufReader<File>>> {
+        let file = File::open(path)?;
+        let mut decoder = Decoder::new(BufReader::new(file));
+
+        if decoder.read_bytes()? != MAGIC {
+            return Err(invalid("not a checkpoint file"));
+        }
+
+        if decoder.read_usize()? != VERSION {
+            return Err(invalid("unsupported checkpoint version"));
+        }
+
+        Ok(decoder)
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test;
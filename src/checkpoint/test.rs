@@ -0,0 +1,167 @@
+use super::*;
+use crate::candidate::Candidate;
+use crate::closed_set::ClosedSet;
+use crate::heuristic::Heuristic;
+use crate::iterative_deepening::IterativeDeepening;
+use crate::open_set::OpenSet;
+use crate::search::Search;
+use std::env;
+use std::sync::atomic::Ordering::Relaxed;
+
+type Subject = Checkpoint;
+
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(4);
+}
+
+fn path(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("supermutation-{}-{}.checkpoint", name, std::process::id()))
+}
+
+fn incremental() -> Incremental<'static> {
+    let search = Search::new(&PROBLEM, OpenSet::new(), ClosedSet::new());
+    let heuristic = Heuristic::seed(&PROBLEM);
+
+    Incremental::new(heuristic, search)
+}
+
+fn interrupted_at(subgoal: usize) -> Incremental<'static> {
+    let mut incremental = incremental();
+    let interrupt = incremental.interrupt_flag();
+
+    incremental.shortest_path(Candidate::seed(&PROBLEM), |_, s, _, _, _, _| {
+        if s == subgoal {
+            interrupt.store(true, Relaxed);
+        }
+    });
+
+    incremental
+}
+
+mod write {
+    use super::*;
+
+    #[test]
+    fn it_writes_a_file_that_can_be_read_back() {
+        let path = path("write");
+        let incremental = interrupted_at(5);
+
+        Subject::write(&path, &incremental).unwrap();
+        let result = Subject::read::<_, Search>(&path, &PROBLEM).unwrap();
+
+        assert_eq!(result.subgoal(), incremental.subgoal());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_does_not_leave_the_temporary_file_behind() {
+        let path = path("temporary");
+
+        Subject::write(&path, &incremental()).unwrap();
+
+        assert!(!path.with_extension("tmp").exists());
+        fs::remove_file(path).unwrap();
+    }
+}
+
+mod read {
+    use super::*;
+
+    #[test]
+    fn it_rejects_files_that_are_not_checkpoints() {
+        let path = path("garbage");
+        fs::write(&path, b"garbage").unwrap();
+
+        assert!(Subject::read::<_, Search>(&path, &PROBLEM).is_err());
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_rejects_checkpoints_for_a_different_number_of_symbols() {
+        let path = path("symbols");
+        let other = Problem::new(3);
+
+        Subject::write(&path, &incremental()).unwrap();
+
+        let error = Subject::read::<_, Search>(&path, &other).err().unwrap();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_rejects_checkpoints_for_a_different_algorithm() {
+        let path = path("algorithm");
+
+        Subject::write(&path, &incremental()).unwrap();
+        assert!(Subject::read::<_, IterativeDeepening>(&path, &PROBLEM).is_err());
+
+        let search = IterativeDeepening::new(&PROBLEM);
+        let incremental = Incremental::new(Heuristic::seed(&PROBLEM), search);
+
+        Subject::write(&path, &incremental).unwrap();
+        assert!(Subject::read::<_, IterativeDeepening>(&path, &PROBLEM).is_ok());
+
+        fs::remove_file(path).unwrap();
+    }
+}
+
+mod read_symbols {
+    use super::*;
+
+    #[test]
+    fn it_reads_the_number_of_symbols_from_the_header() {
+        let path = path("read_symbols");
+
+        Subject::write(&path, &incremental()).unwrap();
+
+        assert_eq!(Subject::read_symbols(&path).unwrap(), 4);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_rejects_an_unsupported_number_of_symbols() {
+        let path = path("unsupported_symbols");
+        let mut bytes = vec![];
+        let mut encoder = Encoder::new(&mut bytes);
+
+        encoder.write_bytes(MAGIC).unwrap();
+        encoder.write_usize(VERSION).unwrap();
+        encoder.write_usize(1000).unwrap();
+        fs::write(&path, bytes).unwrap();
+
+        assert!(Subject::read_symbols(&path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
+
+mod save {
+    use super::*;
+
+    #[test]
+    fn it_writes_the_checkpoint_and_resets_the_interval() {
+        let path = path("save");
+        let mut subject = Subject::new(&path, Duration::from_secs(0));
+
+        assert!(subject.is_due());
+        subject.save(&interrupted_at(7)).unwrap();
+
+        let result = Subject::read::<_, Search>(&path, &PROBLEM).unwrap();
+        assert_eq!(result.subgoal(), 8);
+
+        fs::remove_file(path).unwrap();
+    }
+}
+
+mod is_due {
+    use super::*;
+
+    #[test]
+    fn it_returns_whether_the_interval_has_elapsed_since_the_last_save() {
+        let subject = Subject::new("unused", Duration::from_secs(3600));
+        assert!(!subject.is_due());
+
+        let subject = Subject::new("unused", Duration::from_secs(0));
+        assert!(subject.is_due());
+    }
+}
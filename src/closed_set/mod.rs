@@ -1,28 +1,411 @@
 use crate::candidate::Candidate;
-use std::collections::BTreeMap;
+use crate::codec::{Encoder, Decoder};
+use crate::spill::{Record, Run, Spill};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, ErrorKind, Read, Write};
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
 
+/// Candidates that have been expanded and the g-cost they were expanded at.
+/// They can be stored in full or by their 128-bit fingerprints, which use less
+/// memory and are faster to compare but could collide. On disk, candidates are
+/// written in sorted runs once those in memory exceed the budget, and the runs
+/// are merged as more are written. Only the candidates in memory are looked up
+/// one at a time. Those in runs are checked in batches by remove_closed.
 pub struct ClosedSet {
-    candidates: BTreeMap<Candidate, usize>,
+    candidates: Storage,
+    collisions: usize,
+    bytes: usize,
+}
+
+enum Storage {
+    Exact(BTreeMap<Candidate, usize>),
+    Hashed(HashMap<u128, usize>),
+    Verified(HashMap<u128, Vec<(Candidate, usize)>>),
+    OnDisk(Runs),
+}
+
+struct Runs {
+    spill: Arc<Spill>,
+    budget: usize,
+    memory: BTreeMap<Candidate, usize>,
+    runs: Vec<Run<Record>>,
+    written: usize,
 }
 
 impl ClosedSet {
     pub fn new() -> Self {
-        Self { candidates: BTreeMap::new() }
+        Self::from_storage(Storage::Exact(BTreeMap::new()))
+    }
+
+    /// Stores fingerprints rather than candidates. A collision would make a
+    /// candidate appear closed when it isn't.
+    pub fn hashed() -> Self {
+        Self::from_storage(Storage::Hashed(HashMap::new()))
+    }
+
+    /// Looks candidates up by their fingerprints but also keeps them to check
+    /// for collisions, which are counted rather than mistaken for each other.
+    pub fn verified() -> Self {
+        Self::from_storage(Storage::Verified(HashMap::new()))
+    }
+
+    /// Writes runs to a new directory inside this one once the candidates in
+    /// memory use more than the budget in bytes.
+    pub fn on_disk<P: AsRef<Path>>(directory: P, budget: usize) -> io::Result<Self> {
+        Ok(Self::spilling_to(Spill::new(directory, budget)?, budget))
+    }
+
+    fn spilling_to(spill: Arc<Spill>, budget: usize) -> Self {
+        Self::from_storage(Storage::OnDisk(Runs { spill, budget, memory: BTreeMap::new(), runs: vec![], written: 0 }))
     }
 
-    pub fn add(&mut self, candidate: Candidate, g_cost: usize) {
-        self.candidates.insert(candidate, g_cost);
+    fn from_storage(candidates: Storage) -> Self {
+        Self { candidates, collisions: 0, bytes: 0 }
     }
 
+    /// Writes a run if the closed set is on disk and over its budget.
+    pub fn add(&mut self, candidate: Candidate, g_cost: usize) -> io::Result<()> {
+        match &mut self.candidates {
+            Storage::Exact(map) => {
+                let bytes = Self::entry_size_in_bytes(&candidate);
+
+                if map.insert(candidate, g_cost).is_none() {
+                    self.bytes += bytes;
+                }
+            },
+            Storage::Hashed(map) => { map.insert(candidate.fingerprint(), g_cost); },
+            Storage::Verified(map) => {
+                let entries = map.entry(candidate.fingerprint()).or_insert_with(Vec::new);
+
+                match entries.iter_mut().find(|(c, _)| *c == candidate) {
+                    Some(entry) => entry.1 = g_cost,
+                    None => {
+                        if !entries.is_empty() {
+                            self.collisions += 1;
+                        }
+
+                        self.bytes += Self::entry_size_in_bytes(&candidate);
+                        entries.push((candidate, g_cost));
+                    },
+                }
+            },
+            Storage::OnDisk(runs) => {
+                let bytes = Self::entry_size_in_bytes(&candidate);
+
+                if runs.memory.insert(candidate, g_cost).is_none() {
+                    self.bytes += bytes;
+                }
+
+                if self.bytes > runs.budget {
+                    runs.write_run()?;
+                    self.bytes = 0;
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// Whether the candidate was added with a lower or equal g-cost. On disk,
+    /// only the candidates in memory are checked.
     pub fn contains(&self, candidate: &Candidate, g_cost: usize) -> bool {
-        match self.candidates.get(candidate) {
-            Some(previous) if g_cost >= *previous => true,
-            _ => false,
+        matches!(self.g_cost(candidate), Some(previous) if g_cost >= previous)
+    }
+
+    /// The lowest g-cost the candidate was added with, if it was. On disk, only
+    /// the candidates in memory are checked.
+    pub fn g_cost(&self, candidate: &Candidate) -> Option<usize> {
+        match &self.candidates {
+            Storage::Exact(map) => map.get(candidate).cloned(),
+            Storage::Hashed(map) => map.get(&candidate.fingerprint()).cloned(),
+            Storage::Verified(map) => map.get(&candidate.fingerprint()).and_then(|entries| {
+                entries.iter().find(|(c, _)| c == candidate).map(|(_, g)| *g)
+            }),
+            Storage::OnDisk(runs) => runs.memory.get(candidate).cloned(),
+        }
+    }
+
+    /// Removes the candidates that were written to runs with a lower or equal
+    /// g-cost than this one and returns how many were removed and how many are
+    /// in the runs with a higher g-cost, i.e. are being reopened. The
+    /// candidates are looked up in sorted order so that each block of each run
+    /// is read at most once for the whole batch. Other kinds of closed set
+    /// have no runs, so nothing is removed.
+    pub fn remove_closed(&self, candidates: &mut Vec<Candidate>, g_cost: usize) -> io::Result<(usize, usize)> {
+        let runs = match &self.candidates {
+            Storage::OnDisk(runs) => &runs.runs,
+            _ => return Ok((0, 0)),
+        };
+
+        let mut order = (0..candidates.len()).collect::<Vec<_>>();
+        order.sort_by(|&a, &b| candidates[a].cmp(&candidates[b]));
+
+        let mut lowest: Vec<Option<usize>> = vec![None; candidates.len()];
+
+        for run in runs {
+            let records = run.get_sorted(order.iter().map(|&i| &candidates[i]))?;
+
+            for (&i, (_, previous)) in order.iter().zip(records).filter_map(|(i, r)| Some((i, r?))) {
+                lowest[i] = Some(lowest[i].map_or(previous, |l| l.min(previous)));
+            }
+        }
+
+        let (mut removed, mut reopened) = (0, 0);
+        let mut lowest = lowest.into_iter();
+
+        candidates.retain(|_| match lowest.next().unwrap() {
+            Some(previous) if g_cost >= previous => { removed += 1; false },
+            Some(_) => { reopened += 1; true },
+            None => true,
+        });
+
+        Ok((removed, reopened))
+    }
+
+    /// The number of runs that have been written from memory, so that a batch
+    /// that was checked before can be checked again once there are more.
+    pub fn runs_written(&self) -> usize {
+        match &self.candidates {
+            Storage::OnDisk(runs) => runs.written,
+            _ => 0,
         }
     }
 
     pub fn len(&self) -> usize {
-        self.candidates.len()
+        match &self.candidates {
+            Storage::Exact(map) => map.len(),
+            Storage::Hashed(map) => map.len(),
+            Storage::Verified(map) => map.values().map(|entries| entries.len()).sum(),
+            Storage::OnDisk(runs) => runs.memory.len() + runs.runs.iter().map(|r| r.len()).sum::<usize>(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of candidates whose fingerprints were the same as another's.
+    /// Only the verified closed set can detect these.
+    pub fn collisions(&self) -> usize {
+        self.collisions
+    }
+
+    /// An estimate of the memory used. It counts the candidates' bitmaps and
+    /// the capacity of hash maps but not the nodes of the exact set's tree. On
+    /// disk, it counts the runs' indexes and filters but not their files. The
+    /// candidates kept in full are counted as they're added.
+    pub fn size_in_bytes(&self) -> usize {
+        match &self.candidates {
+            Storage::Exact(_) => {
+                self.bytes
+            },
+            Storage::Hashed(map) => {
+                map.capacity() * (mem::size_of::<u128>() + mem::size_of::<usize>() + 1)
+            },
+            Storage::Verified(map) => {
+                let buckets = map.capacity() * (mem::size_of::<(u128, Vec<(Candidate, usize)>)>() + 1);
+
+                self.bytes + buckets
+            },
+            Storage::OnDisk(runs) => {
+                self.bytes + runs.runs.iter().map(|r| r.size_in_bytes()).sum::<usize>()
+            },
+        }
+    }
+
+    pub fn bytes_per_candidate(&self) -> f64 {
+        self.size_in_bytes() as f64 / self.len().max(1) as f64
+    }
+
+    /// Splits into closed sets of the same kind by fingerprint, e.g. so that
+    /// each thread of a parallel search can own a part. Parts on disk split
+    /// each run and the budget.
+    pub fn partition(self, parts: usize) -> io::Result<Vec<Self>> {
+        let mut result = (0..parts).map(|_| match &self.candidates {
+            Storage::OnDisk(runs) => Self::spilling_to(runs.spill.clone(), runs.spill.budget() / parts),
+            _ => self.empty(),
+        }).collect::<Vec<_>>();
+
+        let part = |fingerprint: u128| (fingerprint % parts as u128) as usize;
+
+        if let Storage::OnDisk(runs) = &self.candidates {
+            for run in &runs.runs {
+                for (closed_set, run) in result.iter_mut().zip(run.partition(parts, |c| part(c.fingerprint()))?) {
+                    match &mut closed_set.candidates {
+                        Storage::OnDisk(runs) if run.len() > 0 => runs.runs.push(run),
+                        _ => {},
+                    }
+                }
+            }
+        }
+
+        match self.candidates {
+            Storage::Exact(map) => for (candidate, g_cost) in map {
+                result[part(candidate.fingerprint())].add(candidate, g_cost)?;
+            },
+            Storage::Hashed(map) => for (fingerprint, g_cost) in map {
+                result[part(fingerprint)].insert_fingerprint(fingerprint, g_cost);
+            },
+            Storage::Verified(map) => for (fingerprint, entries) in map {
+                for (candidate, g_cost) in entries {
+                    result[part(fingerprint)].add(candidate, g_cost)?;
+                }
+            },
+            Storage::OnDisk(runs) => for (candidate, g_cost) in runs.memory {
+                result[part(candidate.fingerprint())].add(candidate, g_cost)?;
+            },
+        }
+
+        Ok(result)
+    }
+
+    /// Adds the candidates of another closed set of the same kind.
+    pub fn extend(&mut self, other: Self) -> io::Result<()> {
+        self.collisions += other.collisions;
+
+        match other.candidates {
+            Storage::Exact(map) => for (candidate, g_cost) in map {
+                self.add(candidate, g_cost)?;
+            },
+            Storage::Hashed(map) => for (fingerprint, g_cost) in map {
+                self.insert_fingerprint(fingerprint, g_cost);
+            },
+            Storage::Verified(map) => for (candidate, g_cost) in map.into_values().flatten() {
+                self.add(candidate, g_cost)?;
+            },
+            Storage::OnDisk(other) => {
+                if let Storage::OnDisk(runs) = &mut self.candidates {
+                    runs.runs.extend(other.runs);
+                    Run::merge_newest(&runs.spill, &mut runs.runs)?;
+                }
+
+                for (candidate, g_cost) in other.memory {
+                    self.add(candidate, g_cost)?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    /// An empty closed set of the same kind.
+    pub fn empty(&self) -> Self {
+        match &self.candidates {
+            Storage::Exact(_) => Self::new(),
+            Storage::Hashed(_) => Self::hashed(),
+            Storage::Verified(_) => Self::verified(),
+            Storage::OnDisk(runs) => Self::spilling_to(runs.spill.clone(), runs.spill.budget()),
+        }
+    }
+
+    fn entry_size_in_bytes(candidate: &Candidate) -> usize {
+        candidate.size_in_bytes() + mem::size_of::<usize>()
+    }
+
+    fn insert_fingerprint(&mut self, fingerprint: u128, g_cost: usize) {
+        if let Storage::Hashed(map) = &mut self.candidates {
+            map.insert(fingerprint, g_cost);
+        }
+    }
+
+    // The kind of closed set is written first so it's the same when read. One
+    // on disk is written as an exact closed set and read back into memory.
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        match &self.candidates {
+            Storage::Exact(map) => {
+                encoder.write_usize(0)?;
+                encoder.write_usize(map.len())?;
+
+                for (candidate, g_cost) in map {
+                    encoder.write_usize(*g_cost)?;
+                    candidate.write_to(encoder)?;
+                }
+            },
+            Storage::Hashed(map) => {
+                encoder.write_usize(1)?;
+                encoder.write_usize(map.len())?;
+
+                for (fingerprint, g_cost) in map {
+                    encoder.write_usize(*g_cost)?;
+                    encoder.write_usize(*fingerprint as u64 as usize)?;
+                    encoder.write_usize((*fingerprint >> 64) as u64 as usize)?;
+                }
+            },
+            Storage::Verified(map) => {
+                encoder.write_usize(2)?;
+                encoder.write_usize(self.len())?;
+
+                for (candidate, g_cost) in map.values().flatten() {
+                    encoder.write_usize(*g_cost)?;
+                    candidate.write_to(encoder)?;
+                }
+            },
+            Storage::OnDisk(runs) => {
+                encoder.write_usize(0)?;
+                encoder.write_usize(self.len())?;
+
+                // Later runs have lower g-costs for the candidates they share.
+                for run in &runs.runs {
+                    for record in run.entries()? {
+                        let (candidate, g_cost) = record?;
+
+                        encoder.write_usize(g_cost)?;
+                        candidate.write_to(encoder)?;
+                    }
+                }
+
+                for (candidate, g_cost) in &runs.memory {
+                    encoder.write_usize(*g_cost)?;
+                    candidate.write_to(encoder)?;
+                }
+            },
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self> {
+        let mut closed_set = match decoder.read_usize()? {
+            0 => Self::new(),
+            1 => Self::hashed(),
+            2 => Self::verified(),
+            _ => return Err(io::Error::new(ErrorKind::InvalidData, "unknown kind of closed set")),
+        };
+
+        for _ in 0..decoder.read_usize()? {
+            let g_cost = decoder.read_usize()?;
+
+            match closed_set.candidates {
+                Storage::Hashed(_) => {
+                    let low = decoder.read_usize()? as u64 as u128;
+                    let high = decoder.read_usize()? as u64 as u128;
+
+                    closed_set.insert_fingerprint(high << 64 | low, g_cost);
+                },
+                _ => closed_set.add(Candidate::read_from(decoder)?, g_cost)?,
+            }
+        }
+
+        Ok(closed_set)
+    }
+}
+
+impl Default for ClosedSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Runs {
+    fn write_run(&mut self) -> io::Result<()> {
+        let records = mem::take(&mut self.memory);
+
+        self.runs.push(Run::write(&self.spill, records)?);
+        self.written += 1;
+
+        Run::merge_newest(&self.spill, &mut self.runs)
     }
 }
 
@@ -1,20 +1,47 @@
-#![cfg(not(feature = "four_symbols"))]
-
 use super::*;
+use crate::problem::Problem;
 
 type Subject = ClosedSet;
 
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
 mod add {
     use super::*;
 
     #[test]
     fn it_adds_a_candidate_to_the_closed_set_with_its_g_cost() {
         let mut subject = Subject::new();
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        subject.add(candidate.clone(), 0).unwrap();
+
+        assert_eq!(subject.len(), 1);
+    }
+
+    #[test]
+    fn it_adds_a_fingerprint_to_a_hashed_closed_set() {
+        let mut subject = Subject::hashed();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        subject.add(candidate.clone(), 0).unwrap();
+        subject.add(candidate.clone(), 1).unwrap();
+
+        assert_eq!(subject.len(), 1);
+        assert!(subject.contains(&candidate, 1));
+    }
+
+    #[test]
+    fn it_keeps_the_candidate_in_a_verified_closed_set() {
+        let mut subject = Subject::verified();
+        let candidate = Candidate::seed(&PROBLEM);
 
-        subject.add(candidate.clone(), 0);
+        subject.add(candidate.clone(), 2).unwrap();
 
-        assert_eq!(subject.candidates.len(), 1);
+        assert_eq!(subject.len(), 1);
+        assert!(subject.contains(&candidate, 2));
+        assert!(!subject.contains(&candidate.expand(&PROBLEM, 0), 2));
     }
 }
 
@@ -24,27 +51,92 @@ mod contains {
     #[test]
     fn it_returns_true_if_the_closed_set_contains_the_candidate() {
         let mut subject = Subject::new();
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
 
-        let first = candidate.expand(0);
-        let second = candidate.expand(1);
+        let first = candidate.expand(&PROBLEM, 0);
+        let second = candidate.expand(&PROBLEM, 1);
 
-        subject.add(first.clone(), 0);
+        subject.add(first.clone(), 0).unwrap();
 
-        assert_eq!(subject.contains(&first, 0), true);
-        assert_eq!(subject.contains(&second, 0), false);
+        assert!(subject.contains(&first, 0));
+        assert!(!subject.contains(&second, 0));
     }
 
     #[test]
     fn it_returns_false_if_it_contains_the_candidate_with_a_higher_g_cost() {
         let mut subject = Subject::new();
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        subject.add(candidate.clone(), 5).unwrap();
+
+        assert!(!subject.contains(&candidate, 4));
+        assert!(subject.contains(&candidate, 5));
+        assert!(subject.contains(&candidate, 6));
+    }
+}
+
+mod g_cost {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_g_cost_the_candidate_was_added_with() {
+        let candidate = Candidate::seed(&PROBLEM);
+        let other = candidate.expand(&PROBLEM, 0);
+
+        for mut subject in [Subject::new(), Subject::hashed(), Subject::verified()] {
+            subject.add(candidate.clone(), 3).unwrap();
+
+            assert_eq!(subject.g_cost(&candidate), Some(3));
+            assert_eq!(subject.g_cost(&other), None);
+        }
+    }
+
+    #[test]
+    fn it_only_looks_up_candidates_in_memory_when_on_disk() {
+        let mut subject = Subject::on_disk(std::env::temp_dir(), 1000).unwrap();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        subject.add(candidate.clone(), 2).unwrap();
+        assert_eq!(subject.g_cost(&candidate), Some(2));
+
+        let mut subject = Subject::on_disk(std::env::temp_dir(), 0).unwrap();
 
-        subject.add(candidate.clone(), 5);
+        subject.add(candidate.clone(), 2).unwrap();
+        assert_eq!(subject.g_cost(&candidate), None);
+    }
+}
+
+mod remove_closed {
+    use super::*;
+
+    #[test]
+    fn it_removes_candidates_in_runs_with_a_lower_or_equal_g_cost() {
+        let mut subject = Subject::on_disk(std::env::temp_dir(), 0).unwrap();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        subject.add(candidate.expand(&PROBLEM, 0), 3).unwrap();
+        subject.add(candidate.expand(&PROBLEM, 1), 5).unwrap();
+        subject.add(candidate.expand(&PROBLEM, 2), 4).unwrap();
+
+        let mut candidates = (0..4).map(|symbol| candidate.expand(&PROBLEM, symbol)).collect::<Vec<_>>();
+        let expected = vec![candidate.expand(&PROBLEM, 1), candidate.expand(&PROBLEM, 3)];
 
-        assert_eq!(subject.contains(&candidate, 4), false);
-        assert_eq!(subject.contains(&candidate, 5), true);
-        assert_eq!(subject.contains(&candidate, 6), true);
+        assert_eq!(subject.remove_closed(&mut candidates, 4).unwrap(), (2, 1));
+        assert_eq!(candidates, expected);
+    }
+
+    #[test]
+    fn it_leaves_the_candidates_of_a_closed_set_in_memory_to_be_looked_up_one_at_a_time() {
+        let candidate = Candidate::seed(&PROBLEM);
+
+        for mut subject in [Subject::new(), Subject::hashed(), Subject::verified()] {
+            let mut candidates = vec![candidate.clone()];
+            subject.add(candidate.clone(), 0).unwrap();
+
+            assert_eq!(subject.remove_closed(&mut candidates, 1).unwrap(), (0, 0));
+            assert_eq!(candidates.len(), 1);
+            assert_eq!(subject.runs_written(), 0);
+        }
     }
 }
 
@@ -53,12 +145,212 @@ mod len {
 
     #[test]
     fn it_returns_the_number_of_candidates_in_the_closed_set() {
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
 
         let mut subject = Subject::new();
         assert_eq!(subject.len(), 0);
 
-        subject.add(candidate, 0);
+        subject.add(candidate, 0).unwrap();
         assert_eq!(subject.len(), 1);
     }
 }
+
+mod on_disk {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn it_merges_the_runs_it_writes() {
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        for symbol in 0..4 {
+            subject.add(candidate.expand(&PROBLEM, symbol), 5).unwrap();
+        }
+
+        assert_eq!(subject.runs_written(), 4);
+        assert_eq!(subject.len(), 4);
+
+        if let Storage::OnDisk(runs) = &subject.candidates {
+            assert_eq!(runs.runs.len(), 1);
+        }
+    }
+
+    #[test]
+    fn it_splits_the_runs_between_parts_and_merges_them_back() {
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        for symbol in 0..PROBLEM.expansions() {
+            subject.add(candidate.expand(&PROBLEM, symbol), symbol).unwrap();
+        }
+
+        let parts = subject.partition(3).unwrap();
+        let neighbor = candidate.expand(&PROBLEM, 2);
+
+        for (part, closed_set) in parts.iter().enumerate() {
+            let mut candidates = vec![neighbor.clone()];
+            closed_set.remove_closed(&mut candidates, 2).unwrap();
+
+            assert_eq!(candidates.is_empty(), neighbor.fingerprint() % 3 == part as u128);
+        }
+
+        let mut merged = parts[0].empty();
+
+        for part in parts {
+            merged.extend(part).unwrap();
+        }
+
+        assert_eq!(merged.len(), PROBLEM.expansions());
+    }
+
+    #[test]
+    fn it_is_written_as_an_exact_closed_set_with_the_lowest_g_costs() {
+        use crate::codec::{Encoder, Decoder};
+
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        subject.add(candidate.clone(), 5).unwrap();
+        subject.add(candidate.clone(), 3).unwrap();
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+        assert_eq!(result.len(), 1);
+        assert!(result.contains(&candidate, 3));
+        assert!(!result.contains(&candidate, 2));
+    }
+}
+
+mod collisions {
+    use super::*;
+
+    #[test]
+    fn it_is_zero_when_no_fingerprints_are_the_same() {
+        let mut subject = Subject::verified();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        for symbol in 0..PROBLEM.expansions() {
+            subject.add(candidate.expand(&PROBLEM, symbol), 1).unwrap();
+        }
+
+        assert_eq!(subject.len(), PROBLEM.expansions());
+        assert_eq!(subject.collisions(), 0);
+    }
+}
+
+mod size_in_bytes {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn it_uses_less_memory_per_candidate_when_hashed() {
+        let mut exact = Subject::new();
+        let mut hashed = Subject::hashed();
+        let mut candidate = Candidate::seed(&PROBLEM);
+
+        for depth in 0..50 {
+            exact.add(candidate.clone(), depth).unwrap();
+            hashed.add(candidate.clone(), depth).unwrap();
+
+            candidate = candidate.expand(&PROBLEM, depth % 2);
+        }
+
+        assert!(exact.size_in_bytes() > 0);
+        assert!(hashed.bytes_per_candidate() < exact.bytes_per_candidate());
+    }
+
+    #[test]
+    fn it_counts_each_candidate_kept_in_full_once() {
+        let a = Candidate::seed(&PROBLEM);
+        let b = a.expand(&PROBLEM, 0);
+        let entry = |c: &Candidate| c.size_in_bytes() + mem::size_of::<usize>();
+
+        for mut subject in [Subject::new(), Subject::on_disk(env::temp_dir(), 1000).unwrap()] {
+            subject.add(a.clone(), 2).unwrap();
+            subject.add(b.clone(), 1).unwrap();
+            subject.add(a.clone(), 0).unwrap();
+
+            assert_eq!(subject.size_in_bytes(), entry(&a) + entry(&b));
+        }
+    }
+}
+
+mod partition {
+    use super::*;
+
+    fn closed_sets() -> Vec<Subject> {
+        vec![Subject::new(), Subject::hashed(), Subject::verified()]
+    }
+
+    #[test]
+    fn it_splits_the_candidates_into_closed_sets_of_the_same_kind() {
+        let candidate = Candidate::seed(&PROBLEM);
+
+        for mut subject in closed_sets() {
+            for symbol in 0..PROBLEM.expansions() {
+                subject.add(candidate.expand(&PROBLEM, symbol), symbol).unwrap();
+            }
+
+            let parts = subject.partition(3).unwrap();
+
+            assert_eq!(parts.len(), 3);
+            assert_eq!(parts.iter().map(|p| p.len()).sum::<usize>(), PROBLEM.expansions());
+
+            for symbol in 0..PROBLEM.expansions() {
+                let neighbor = candidate.expand(&PROBLEM, symbol);
+                let part = (neighbor.fingerprint() % 3) as usize;
+
+                assert!(parts[part].contains(&neighbor, symbol));
+            }
+
+            let mut merged = parts[0].empty();
+
+            for part in parts {
+                merged.extend(part).unwrap();
+            }
+
+            assert_eq!(merged.len(), PROBLEM.expansions());
+            assert!(merged.contains(&candidate.expand(&PROBLEM, 1), 1));
+        }
+    }
+}
+
+mod write_to {
+    use super::*;
+    use crate::codec::{Encoder, Decoder};
+
+    #[test]
+    fn it_can_be_read_back_with_read_from() {
+        let candidate = Candidate::seed(&PROBLEM);
+        let neighbor = candidate.expand(&PROBLEM, 0);
+
+        for mut subject in [Subject::new(), Subject::hashed(), Subject::verified()] {
+            subject.add(candidate.clone(), 3).unwrap();
+            subject.add(neighbor.clone(), 4).unwrap();
+
+            let mut bytes = vec![];
+            subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+            let result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+            assert_eq!(result.len(), 2);
+            assert!(result.contains(&candidate, 3));
+            assert!(!result.contains(&neighbor, 3));
+            assert!(result.contains(&neighbor, 4));
+        }
+    }
+
+    #[test]
+    fn it_returns_an_error_for_an_unknown_kind_of_closed_set() {
+        let mut bytes = vec![];
+        Encoder::new(&mut bytes).write_usize(7).unwrap();
+
+        let result = Subject::read_from(&mut Decoder::new(&bytes[..]));
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,98 @@
+use std::io::{self, ErrorKind, Read, Write};
+
+/// Writes the binary format used by checkpoints.
+pub struct Encoder<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> Encoder<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    pub fn write_usize(&mut self, value: usize) -> io::Result<()> {
+        self.writer.write_all(&(value as u64).to_le_bytes())
+    }
+
+    /// Writes the low eight bytes then the high eight, e.g. of a fingerprint.
+    pub fn write_u128(&mut self, value: u128) -> io::Result<()> {
+        self.writer.write_all(&value.to_le_bytes())
+    }
+
+    /// Writes the bits of the value so that it's read back exactly, e.g. a weight.
+    pub fn write_f64(&mut self, value: f64) -> io::Result<()> {
+        self.writer.write_all(&value.to_bits().to_le_bytes())
+    }
+
+    pub fn write_usizes(&mut self, values: &[usize]) -> io::Result<()> {
+        self.write_usize(values.len())?;
+
+        for value in values {
+            self.write_usize(*value)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.write_usize(bytes.len())?;
+        self.writer.write_all(bytes)
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// Reads the binary format used by checkpoints.
+pub struct Decoder<R: Read> {
+    reader: R,
+}
+
+impl<R: Read> Decoder<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader }
+    }
+
+    pub fn read_usize(&mut self) -> io::Result<usize> {
+        let mut buffer = [0; 8];
+        self.reader.read_exact(&mut buffer)?;
+
+        Ok(u64::from_le_bytes(buffer) as usize)
+    }
+
+    pub fn read_u128(&mut self) -> io::Result<u128> {
+        let mut buffer = [0; 16];
+        self.reader.read_exact(&mut buffer)?;
+
+        Ok(u128::from_le_bytes(buffer))
+    }
+
+    pub fn read_f64(&mut self) -> io::Result<f64> {
+        let mut buffer = [0; 8];
+        self.reader.read_exact(&mut buffer)?;
+
+        Ok(f64::from_bits(u64::from_le_bytes(buffer)))
+    }
+
+    pub fn read_usizes(&mut self) -> io::Result<Vec<usize>> {
+        let len = self.read_usize()?;
+        (0..len).map(|_| self.read_usize()).collect()
+    }
+
+    pub fn read_bytes(&mut self) -> io::Result<Vec<u8>> {
+        let len = self.read_usize()?;
+        let mut buffer = vec![];
+
+        self.reader.by_ref().take(len as u64).read_to_end(&mut buffer)?;
+
+        if buffer.len() != len {
+            return Err(ErrorKind::UnexpectedEof.into());
+        }
+
+        Ok(buffer)
+    }
+}
+
+#[cfg(test)]
+mod test;
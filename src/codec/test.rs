@@ -0,0 +1,88 @@
+use super::*;
+
+fn encode<F: FnOnce(&mut Encoder<&mut Vec<u8>>) -> io::Result<()>>(f: F) -> Vec<u8> {
+    let mut buffer = vec![];
+    f(&mut Encoder::new(&mut buffer)).unwrap();
+    buffer
+}
+
+mod write_usize {
+    use super::*;
+
+    #[test]
+    fn it_writes_the_value_as_eight_little_endian_bytes() {
+        let bytes = encode(|e| e.write_usize(258));
+        assert_eq!(bytes, &[2, 1, 0, 0, 0, 0, 0, 0]);
+    }
+}
+
+mod write_u128 {
+    use super::*;
+
+    #[test]
+    fn it_writes_the_low_and_then_the_high_eight_bytes() {
+        let bytes = encode(|e| e.write_u128(1 << 64 | 258));
+
+        assert_eq!(&bytes[0..8], &[2, 1, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[8..16], &[1, 0, 0, 0, 0, 0, 0, 0]);
+    }
+}
+
+mod write_usizes {
+    use super::*;
+
+    #[test]
+    fn it_writes_the_length_followed_by_the_values() {
+        let bytes = encode(|e| e.write_usizes(&[3, 4]));
+
+        assert_eq!(bytes.len(), 24);
+        assert_eq!(&bytes[0..8], &[2, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[8..16], &[3, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&bytes[16..24], &[4, 0, 0, 0, 0, 0, 0, 0]);
+    }
+}
+
+mod write_bytes {
+    use super::*;
+
+    #[test]
+    fn it_writes_the_length_followed_by_the_bytes() {
+        let bytes = encode(|e| e.write_bytes(&[7, 8, 9]));
+        assert_eq!(bytes, &[3, 0, 0, 0, 0, 0, 0, 0, 7, 8, 9]);
+    }
+}
+
+mod decoder {
+    use super::*;
+
+    #[test]
+    fn it_reads_back_what_the_encoder_wrote() {
+        let bytes = encode(|e| {
+            e.write_usize(123)?;
+            e.write_u128(u128::MAX - 1)?;
+            e.write_f64(1.5)?;
+            e.write_usizes(&[4, 5, 6])?;
+            e.write_bytes(&[7, 8])
+        });
+
+        let mut subject = Decoder::new(&bytes[..]);
+
+        assert_eq!(subject.read_usize().unwrap(), 123);
+        assert_eq!(subject.read_u128().unwrap(), u128::MAX - 1);
+        assert_eq!(subject.read_f64().unwrap(), 1.5);
+        assert_eq!(subject.read_usizes().unwrap(), &[4, 5, 6]);
+        assert_eq!(subject.read_bytes().unwrap(), &[7, 8]);
+    }
+
+    #[test]
+    fn it_returns_an_error_if_the_input_is_truncated() {
+        let bytes = encode(|e| e.write_bytes(&[7, 8, 9]));
+        let mut subject = Decoder::new(&bytes[..10]);
+
+        let error = subject.read_bytes().unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+
+        let mut subject = Decoder::new(&bytes[..4]);
+        assert!(subject.read_usize().is_err());
+    }
+}
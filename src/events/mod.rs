@@ -0,0 +1,40 @@
+use crate::format::{Format, Value};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Writes progress as newline-delimited JSON so that scripts can follow a long
+/// run. Each line is an object with the name of the event and the seconds
+/// since the stream was created. Clones write to the same stream.
+#[derive(Clone)]
+pub struct Events {
+    writer: Arc<Mutex<Box<dyn Write + Send>>>,
+    start: Instant,
+}
+
+impl Events {
+    pub fn new<W: Write + Send + 'static>(writer: W) -> Self {
+        Self { writer: Arc::new(Mutex::new(Box::new(writer))), start: Instant::now() }
+    }
+
+    pub fn to_file<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Ok(Self::new(BufWriter::new(File::create(path)?)))
+    }
+
+    /// Writes a line and flushes it. Errors are ignored so that a stream that
+    /// was closed early doesn't stop the search.
+    pub fn emit(&self, event: &str, fields: &[(&str, Value)]) {
+        let mut record = vec![("event", event.into()), ("elapsed", self.start.elapsed().as_secs_f64().into())];
+        record.extend(fields.iter().cloned());
+
+        let line = Format::Json.record(&record);
+        let mut writer = self.writer.lock().unwrap();
+
+        let _ = writeln!(writer, "{}", line).and_then(|_| writer.flush());
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,74 @@
+use super::*;
+
+type Subject = Events;
+
+// A writer whose contents can be read back while the events still hold it.
+#[derive(Clone, Default)]
+struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+impl Buffer {
+    fn lines(&self) -> Vec<String> {
+        let bytes = self.0.lock().unwrap();
+        String::from_utf8(bytes.clone()).unwrap().lines().map(String::from).collect()
+    }
+}
+
+impl Write for Buffer {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(bytes)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+mod emit {
+    use super::*;
+
+    #[test]
+    fn it_writes_a_line_of_json_with_the_event_and_elapsed_time() {
+        let buffer = Buffer::default();
+        let subject = Subject::new(buffer.clone());
+
+        subject.emit("subgoal", &[("distance", 3.into()), ("heuristic", vec![0, 1, 3].into())]);
+
+        let lines = buffer.lines();
+
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].starts_with("{\"event\":\"subgoal\",\"elapsed\":"));
+        assert!(lines[0].ends_with(",\"distance\":3,\"heuristic\":[0,1,3]}"));
+    }
+
+    #[test]
+    fn it_writes_to_the_same_stream_from_clones() {
+        let buffer = Buffer::default();
+        let subject = Subject::new(buffer.clone());
+
+        subject.emit("start", &[]);
+        subject.clone().emit("finished", &[]);
+
+        let lines = buffer.lines();
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("\"finished\""));
+    }
+}
+
+mod to_file {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn it_writes_the_events_to_the_file() {
+        let path = std::env::temp_dir().join(format!("supermutation-events-{}", std::process::id()));
+        let subject = Subject::to_file(&path).unwrap();
+
+        subject.emit("start", &[("symbols", 4.into())]);
+
+        let contents = fs::read_to_string(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert!(contents.ends_with("\"symbols\":4}\n"));
+    }
+}
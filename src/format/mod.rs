@@ -0,0 +1,134 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// How results are printed. Text and CSV are tables (text is space separated
+/// with a commented header) and JSON has one object per line.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+/// A value in a record.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Number(usize),
+    Decimal(f64),
+    Text(String),
+    Numbers(Vec<usize>),
+    Boolean(bool),
+}
+
+impl Format {
+    pub fn header(&self, names: &[&str]) -> Option<String> {
+        match self {
+            Format::Text => Some(format!("# {}", names.join(" "))),
+            Format::Json => None,
+            Format::Csv => Some(names.join(",")),
+        }
+    }
+
+    pub fn record(&self, fields: &[(&str, Value)]) -> String {
+        match self {
+            Format::Text => {
+                let values = fields.iter().map(|(_, v)| v.to_text());
+                values.collect::<Vec<_>>().join(" ")
+            },
+            Format::Json => {
+                let pairs = fields.iter().map(|(k, v)| format!("{}:{}", json_string(k), v.to_json()));
+                format!("{{{}}}", pairs.collect::<Vec<_>>().join(","))
+            },
+            Format::Csv => {
+                let values = fields.iter().map(|(_, v)| csv_field(&v.to_text()));
+                values.collect::<Vec<_>>().join(",")
+            },
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown format '{}'", name)),
+        }
+    }
+}
+
+impl Value {
+    fn to_text(&self) -> String {
+        match self {
+            Value::Number(n) => n.to_string(),
+            Value::Decimal(d) => d.to_string(),
+            Value::Text(s) => s.clone(),
+            Value::Numbers(ns) => ns.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(" "),
+            Value::Boolean(b) => b.to_string(),
+        }
+    }
+
+    fn to_json(&self) -> String {
+        match self {
+            Value::Decimal(d) if !d.is_finite() => "null".to_string(),
+            Value::Text(s) => json_string(s),
+            Value::Numbers(ns) => format!("[{}]", ns.iter().map(|n| n.to_string()).collect::<Vec<_>>().join(",")),
+            _ => self.to_text(),
+        }
+    }
+}
+
+impl From<usize> for Value {
+    fn from(n: usize) -> Self { Value::Number(n) }
+}
+
+impl From<f64> for Value {
+    fn from(d: f64) -> Self { Value::Decimal(d) }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self { Value::Text(s.to_string()) }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self { Value::Text(s) }
+}
+
+impl From<Vec<usize>> for Value {
+    fn from(ns: Vec<usize>) -> Self { Value::Numbers(ns) }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self { Value::Boolean(b) }
+}
+
+fn json_string(string: &str) -> String {
+    let mut json = String::from("\"");
+
+    for c in string.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+
+    json.push('"');
+    json
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod test;
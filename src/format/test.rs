@@ -0,0 +1,67 @@
+use super::*;
+
+type Subject = Format;
+
+fn fields() -> Vec<(&'static str, Value)> {
+    vec![("subgoal", 13.into()), ("distance", 16.into()), ("string", "01230".into())]
+}
+
+mod header {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_column_names_for_tables() {
+        let names = &["perms", "distance"];
+
+        assert_eq!(Subject::Text.header(names), Some("# perms distance".to_string()));
+        assert_eq!(Subject::Csv.header(names), Some("perms,distance".to_string()));
+        assert_eq!(Subject::Json.header(names), None);
+    }
+}
+
+mod record {
+    use super::*;
+
+    #[test]
+    fn it_formats_text_as_space_separated_values() {
+        assert_eq!(Subject::Text.record(&fields()), "13 16 01230");
+    }
+
+    #[test]
+    fn it_formats_json_as_an_object() {
+        assert_eq!(Subject::Json.record(&fields()), r#"{"subgoal":13,"distance":16,"string":"01230"}"#);
+    }
+
+    #[test]
+    fn it_formats_csv_as_comma_separated_values() {
+        assert_eq!(Subject::Csv.record(&fields()), "13,16,01230");
+    }
+
+    #[test]
+    fn it_formats_lists_of_numbers() {
+        let fields = &[("distances", vec![0, 1, 2].into())];
+
+        assert_eq!(Subject::Text.record(fields), "0 1 2");
+        assert_eq!(Subject::Json.record(fields), r#"{"distances":[0,1,2]}"#);
+    }
+
+    #[test]
+    fn it_escapes_strings() {
+        let fields = &[("message", "say \"hi\", ok".into())];
+
+        assert_eq!(Subject::Json.record(fields), r#"{"message":"say \"hi\", ok"}"#);
+        assert_eq!(Subject::Csv.record(fields), r#""say ""hi"", ok""#);
+    }
+}
+
+mod from_str {
+    use super::*;
+
+    #[test]
+    fn it_parses_the_name_of_the_format() {
+        assert_eq!("text".parse(), Ok(Subject::Text));
+        assert_eq!("json".parse(), Ok(Subject::Json));
+        assert_eq!("csv".parse(), Ok(Subject::Csv));
+        assert!("xml".parse::<Subject>().is_err());
+    }
+}
@@ -1,20 +1,35 @@
 use crate::candidate::Candidate;
+use crate::codec::{Encoder, Decoder};
+use crate::problem::Problem;
+use crate::published::Published;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
 
+/// A lower bound on the distance to the next goal that is learned from the
+/// shortest path distances to the previous goals.
+///
+/// The distances are measured from where the search starts. That's usually the
+/// seed, but a heuristic can start from a later candidate, e.g. one a prefix
+/// string reached. Its depths are then measured from that candidate and how
+/// quickly perms can be added comes from a table of distances from the seed.
 #[derive(Clone, Debug, PartialEq)]
 pub struct Heuristic {
     starting_perms: usize,
+    starting_depth: usize,
     distances: Vec<usize>,
+    seed_distances: Option<Vec<usize>>,
     lower_bounds: Vec<usize>,
     max_depths: Vec<usize>,
 }
 
 impl Heuristic {
     pub fn new(starting_perms: usize, distances: Vec<usize>, lower_bounds: Vec<usize>, max_depths: Vec<usize>) -> Self {
-        Self { starting_perms, distances, lower_bounds, max_depths }
+        Self { starting_perms, starting_depth: 0, distances, seed_distances: None, lower_bounds, max_depths }
     }
 
-    pub fn seed() -> Self {
-        let starting_perms = Candidate::seed().number_of_permutations();
+    pub fn seed(problem: &Problem) -> Self {
+        let starting_perms = Candidate::seed(problem).number_of_permutations(problem);
         let distances = vec![0];
         let lower_bounds = (0..=(starting_perms + 1)).rev().collect();
         let max_depths = (0..=(starting_perms + 1)).map(|_| 0).collect();
@@ -22,13 +37,131 @@ impl Heuristic {
         Self::new(starting_perms, distances, lower_bounds, max_depths)
     }
 
+    /// Starts from a candidate that has this many perms at this depth rather
+    /// than from the seed. The table's distances from the seed bound how
+    /// quickly perms can be added but nothing is known about the distances from
+    /// the candidate until they're learned.
+    pub fn starting_from(table: &Self, starting_perms: usize, starting_depth: usize) -> Self {
+        let distances = vec![0];
+        let seed_distances = Some(table.seed_distances().to_vec());
+        let lower_bounds = (0..=(starting_perms + 1)).rev().collect();
+        let max_depths = (0..=(starting_perms + 1)).map(|_| 0).collect();
+
+        Self { starting_perms, starting_depth, distances, seed_distances, lower_bounds, max_depths }
+    }
+
+    /// Builds the heuristic as if the search had learned these distances. The
+    /// first distance is for the starting perms so it should always be zero.
+    pub fn from_distances(problem: &Problem, distances: &[usize]) -> Self {
+        let mut heuristic = Self::seed(problem);
+
+        for distance in distances.iter().skip(1) {
+            heuristic.improve_based_on(*distance);
+        }
+
+        heuristic
+    }
+
+    /// Preloads the published distances for up to the given number of perms.
+    pub fn published(problem: &Problem, perms: usize) -> Option<Self> {
+        let table = Published::max_perms_per_waste(problem.symbols())?;
+        let distances = Published::distances(table);
+        let perms = perms.min(distances.len());
+
+        Some(Self::from_distances(problem, &distances[..perms]))
+    }
+
+    pub fn load_distances<P: AsRef<Path>>(problem: &Problem, path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Self::read_distances(problem, BufReader::new(file))
+    }
+
+    pub fn save_distances<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = File::create(path)?;
+        self.write_distances(BufWriter::new(file))
+    }
+
+    pub fn read_distances<R: BufRead>(problem: &Problem, reader: R) -> io::Result<Self> {
+        let starting_perms = Candidate::seed(problem).number_of_permutations(problem);
+        let mut distances: Vec<usize> = vec![];
+
+        for line in reader.lines() {
+            let line = line?;
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace().map(|f| f.parse::<usize>());
+
+            let (perms, distance) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(Ok(perms)), Some(Ok(distance)), None) => (perms, distance),
+                _ => return Err(invalid(&format!("expected '<perms> <distance>' but got '{}'", line))),
+            };
+
+            if perms != starting_perms + distances.len() || perms > problem.factorial() {
+                return Err(invalid(&format!("unexpected number of perms: {}", perms)));
+            }
+
+            if distances.last().map_or(distance != 0, |previous| distance <= *previous) {
+                return Err(invalid(&format!("distance does not increase: {}", distance)));
+            }
+
+            distances.push(distance);
+        }
+
+        Ok(Self::from_distances(problem, &distances))
+    }
+
+    pub fn write_distances<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writeln!(writer, "# perms distance")?;
+
+        for (perms_added, distance) in self.distances.iter().enumerate() {
+            writeln!(writer, "{} {}", self.starting_perms + perms_added, distance)?;
+        }
+
+        writer.flush()
+    }
+
+    pub fn distances(&self) -> &[usize] {
+        &self.distances
+    }
+
+    /// The shortest distances from the seed, which are the distances unless
+    /// the heuristic starts from a later candidate.
+    pub fn seed_distances(&self) -> &[usize] {
+        self.seed_distances.as_ref().unwrap_or(&self.distances)
+    }
+
+    /// The depth of the candidate the distances are measured from.
+    pub fn starting_depth(&self) -> usize {
+        self.starting_depth
+    }
+
+    /// The estimated distance to the next goal.
     pub fn cost(&self, number_of_perms: usize, search_depth: usize) -> usize {
         let lower_bound = self.lower_bounds[number_of_perms];
         let max_depth = self.max_depths[number_of_perms];
+        let depth = search_depth.saturating_sub(self.starting_depth);
 
-        lower_bound + max_depth.saturating_sub(search_depth)
+        lower_bound + max_depth.saturating_sub(depth)
     }
 
+    /// The cost multiplied by the weight and rounded up. A weight above one
+    /// finds paths sooner that are at most that many times longer.
+    pub fn weighted_cost(&self, number_of_perms: usize, search_depth: usize, weight: f64) -> usize {
+        let cost = self.cost(number_of_perms, search_depth);
+
+        (cost as f64 * weight).ceil() as usize
+    }
+
+    /// Learns from the shortest path distance to the next goal.
+    ///
+    /// A candidate can't reach the goal sooner than it takes to add the perms
+    /// it's missing, nor sooner than the lower bound on the goal's distance
+    /// minus its depth. The cost is whichever of these is greater so that it
+    /// never overestimates, however deep the candidate is.
     pub fn improve_based_on(&mut self, shortest_path_distance: usize) {
         self.distances.push(shortest_path_distance);
         self.lower_bounds.push(0);
@@ -37,20 +170,46 @@ impl Heuristic {
         let lower_bound = self.maximum_lower_bound_on_the_distance_to_next_goal();
 
         for number_of_perms in 0..self.next_goal() {
-            let next_min_depth = self.shortest_distance(number_of_perms + 1);
-            let max_depth = next_min_depth.unwrap_or(lower_bound).saturating_sub(1);
-
-            self.max_depths[number_of_perms] = max_depth;
-
             if number_of_perms < self.starting_perms {
+                let next_min_depth = self.shortest_distance(number_of_perms + 1);
                 let distance_to_start = self.starting_perms - number_of_perms;
+
+                self.max_depths[number_of_perms] = next_min_depth.unwrap_or(lower_bound).saturating_sub(1);
                 self.lower_bounds[number_of_perms] = lower_bound + distance_to_start;
             } else {
-                self.lower_bounds[number_of_perms] = lower_bound - max_depth;
+                let needed_perms = self.next_goal() - number_of_perms;
+                let distance_to_goal = self.minimum_distance_to_add(needed_perms).unwrap_or(lower_bound);
+
+                self.max_depths[number_of_perms] = lower_bound.saturating_sub(distance_to_goal);
+                self.lower_bounds[number_of_perms] = distance_to_goal;
             }
         }
     }
 
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_usize(self.starting_perms)?;
+        encoder.write_usize(self.starting_depth)?;
+        encoder.write_usizes(&self.distances)?;
+        encoder.write_usizes(self.seed_distances.as_deref().unwrap_or(&[]))?;
+        encoder.write_usizes(&self.lower_bounds)?;
+        encoder.write_usizes(&self.max_depths)
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self> {
+        let starting_perms = decoder.read_usize()?;
+        let starting_depth = decoder.read_usize()?;
+        let distances = decoder.read_usizes()?;
+        let seed_distances = Some(decoder.read_usizes()?).filter(|d| !d.is_empty());
+        let lower_bounds = decoder.read_usizes()?;
+        let max_depths = decoder.read_usizes()?;
+
+        if lower_bounds.len() != max_depths.len() {
+            return Err(invalid("lower_bounds and max_depths have different lengths"));
+        }
+
+        Ok(Self { starting_perms, starting_depth, distances, seed_distances, lower_bounds, max_depths })
+    }
+
     fn maximum_lower_bound_on_the_distance_to_next_goal(&self) -> usize {
         let mut greatest_distance = 0;
 
@@ -58,9 +217,9 @@ impl Heuristic {
             let needed_perms = self.next_goal() - number_of_perms;
 
             let distance_from_start = self.shortest_distance(number_of_perms).unwrap();
-            let distance_to_goal = self.shortest_distance_to_add(needed_perms).unwrap();
+            let distance_to_goal = self.minimum_distance_to_add(needed_perms).unwrap();
 
-            let total_distance = distance_from_start as usize + distance_to_goal;
+            let total_distance = distance_from_start + distance_to_goal;
 
             if total_distance > greatest_distance {
                 greatest_distance = total_distance;
@@ -74,7 +233,8 @@ impl Heuristic {
         self.starting_perms + 1
     }
 
-    fn next_goal(&self) -> usize {
+    /// The number of perms in the goal the heuristic estimates the distance to.
+    pub fn next_goal(&self) -> usize {
         self.lower_bounds.len() - 1
     }
 
@@ -86,6 +246,26 @@ impl Heuristic {
     fn shortest_distance_to_add(&self, perms_to_add: usize) -> Option<usize> {
         self.distances.get(perms_to_add).cloned()
     }
+
+    // A candidate might not end in a permutation, or might already have the
+    // ones that are usually added next, so adding perms to it can be quicker
+    // than from the start. The string that adds them starts with its last n-1
+    // symbols and, trimmed to its first permutation, has at least that many
+    // perms so it's at least one longer than the shortest distance to them.
+    // Past the end of a table from the seed, each perm needs another symbol.
+    fn minimum_distance_to_add(&self, perms_to_add: usize) -> Option<usize> {
+        match &self.seed_distances {
+            Some(distances) => {
+                let distance = distances.get(perms_to_add.saturating_sub(1)).copied();
+                Some(distance.unwrap_or(perms_to_add.saturating_sub(1)) + 1)
+            },
+            None => self.shortest_distance(perms_to_add).map(|distance| distance + 1),
+        }
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
 }
 
 #[cfg(test)]
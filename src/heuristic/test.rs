@@ -1,23 +1,25 @@
-#![cfg(not(feature = "four_symbols"))]
-
 use super::*;
 
 type Subject = Heuristic;
 
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
 mod seed {
     use super::*;
 
     #[test]
     fn it_sets_starting_perms_from_the_seed_candidate() {
-        let subject = Subject::seed();
-        let expected = Candidate::seed().number_of_permutations();
+        let subject = Subject::seed(&PROBLEM);
+        let expected = Candidate::seed(&PROBLEM).number_of_permutations(&PROBLEM);
 
         assert_eq!(subject.starting_perms, expected);
     }
 
     #[test]
     fn it_sets_the_length_of_max_depth_so_that_the_next_goal_can_be_used_as_an_index() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
         let expected = subject.next_goal() + 1;
 
         assert_eq!(subject.max_depths.len(), expected);
@@ -25,7 +27,7 @@ mod seed {
 
     #[test]
     fn it_sets_the_length_of_lower_bounds_so_that_the_next_goal_can_be_used_as_an_index() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
         let expected = subject.next_goal() + 1;
 
         assert_eq!(subject.lower_bounds.len(), expected);
@@ -33,13 +35,13 @@ mod seed {
 
     #[test]
     fn it_sets_max_depth_elements_to_zero() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
         assert_eq!(subject.max_depths, &[0, 0, 0]);
     }
 
     #[test]
     fn it_sets_lower_bounds_to_descending_distances() {
-        let subject = Subject::seed();
+        let subject = Subject::seed(&PROBLEM);
 
         // The first goal is two perms and the candidate starts with one so if
         // the search goes in the opposite direction we know that at a minimum
@@ -56,7 +58,7 @@ mod cost {
     //  The following tests use this graph of number of perms vs. distance:
     //    (see the #improve_based_on tests below for more explanation)
     //
-    //           |            o (5, 6)
+    //           |            o (5, 5)
     //           |           /
     //           |          x (4, 5)
     //           |          |
@@ -73,7 +75,7 @@ mod cost {
     //            number of perms
 
     fn setup() -> Subject {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
 
         subject.improve_based_on(1);
         subject.improve_based_on(3);
@@ -83,32 +85,33 @@ mod cost {
     }
 
     #[test]
-    fn it_returns_the_shortest_distance_for_candidates_on_the_optimal_path() {
+    fn it_returns_the_lower_bound_on_the_goal_less_the_depth_for_candidates_on_the_optimal_path() {
         let subject = setup();
 
-        assert_eq!(subject.cost(4, 5), 1);
-        assert_eq!(subject.cost(4, 4), 2);
-        assert_eq!(subject.cost(3, 3), 3);
-        assert_eq!(subject.cost(2, 2), 4);
-        assert_eq!(subject.cost(2, 1), 5);
-        assert_eq!(subject.cost(1, 0), 6);
+        assert_eq!(subject.cost(4, 4), 1);
+        assert_eq!(subject.cost(3, 3), 2);
+        assert_eq!(subject.cost(2, 1), 4);
+        assert_eq!(subject.cost(1, 0), 5);
     }
 
     #[test]
-    fn it_returns_the_shortest_distance_for_candidates_off_the_optimal_path() {
+    fn it_returns_the_shortest_distance_to_add_the_missing_perms_for_candidates_off_the_optimal_path() {
         let subject = setup();
 
+        // Adding 1 perm takes at least 1 symbol.
         assert_eq!(subject.cost(4, 6), 1);
         assert_eq!(subject.cost(4, 7), 1);
         assert_eq!(subject.cost(4, 8), 1);
 
+        // Adding 3 perms takes one more than the 3 symbols it took to reach 3
+        // perms, because the candidate might not end in a permutation.
         assert_eq!(subject.cost(2, 6), 4);
         assert_eq!(subject.cost(2, 7), 4);
         assert_eq!(subject.cost(2, 8), 4);
 
-        assert_eq!(subject.cost(1, 3), 6);
-        assert_eq!(subject.cost(1, 4), 6);
-        assert_eq!(subject.cost(1, 5), 6);
+        assert_eq!(subject.cost(1, 3), 5);
+        assert_eq!(subject.cost(1, 4), 5);
+        assert_eq!(subject.cost(1, 5), 5);
     }
 
     #[test]
@@ -125,7 +128,7 @@ mod cost {
     #[test]
     fn it_returns_the_cumulative_distance_for_candidates_with_less_than_starting_perms() {
         let subject = setup();
-        let distance_from_start = 6;
+        let distance_from_start = 5;
 
         assert_eq!(subject.cost(0, 0), 1 + distance_from_start);
         assert_eq!(subject.cost(0, 1), 1 + distance_from_start);
@@ -134,12 +137,30 @@ mod cost {
     }
 }
 
+mod weighted_cost {
+    use super::*;
+
+    #[test]
+    fn it_multiplies_the_cost_by_the_weight_and_rounds_up() {
+        let mut subject = Subject::seed(&PROBLEM);
+
+        subject.improve_based_on(1);
+        subject.improve_based_on(3);
+        subject.improve_based_on(4);
+
+        assert_eq!(subject.weighted_cost(1, 0, 1.0), 5);
+        assert_eq!(subject.weighted_cost(1, 0, 2.0), 10);
+        assert_eq!(subject.weighted_cost(1, 0, 1.5), 8);
+        assert_eq!(subject.weighted_cost(5, 5, 2.0), 0);
+    }
+}
+
 mod improve_based_on {
     use super::*;
 
     #[test]
     fn it_adds_the_new_distance_to_its_shortest_path_distances() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
 
         subject.improve_based_on(1);
         assert_eq!(subject.distances, &[0, 1]);
@@ -150,7 +171,7 @@ mod improve_based_on {
 
     #[test]
     fn it_adds_a_new_max_depth_for_the_next_goal() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
         let previous = subject.max_depths.len();
 
         subject.improve_based_on(1);
@@ -159,7 +180,7 @@ mod improve_based_on {
 
     #[test]
     fn it_sets_the_new_max_depth_to_zero_for_the_next_goal() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
 
         subject.improve_based_on(1);
 
@@ -170,7 +191,7 @@ mod improve_based_on {
 
     #[test]
     fn it_keeps_a_max_depth_of_zero_for_indexes_below_starting_perms() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
 
         subject.improve_based_on(1);
 
@@ -181,7 +202,7 @@ mod improve_based_on {
 
     #[test]
     fn it_sets_max_depth_to_the_furthest_distance_from_the_start_for_each_number_of_perms() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
 
         subject.improve_based_on(1);
         assert_eq!(&subject.max_depths[1..], &[0, 1, 0]);
@@ -219,7 +240,7 @@ mod improve_based_on {
 
     #[test]
     fn it_adds_a_new_lower_bound_for_the_next_goal() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
         let previous = subject.lower_bounds.len();
 
         subject.improve_based_on(1);
@@ -228,7 +249,7 @@ mod improve_based_on {
 
     #[test]
     fn it_sets_the_new_lower_bound_to_zero_for_the_next_goal() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
 
         subject.improve_based_on(1);
 
@@ -238,7 +259,7 @@ mod improve_based_on {
 
     #[test]
     fn it_adds_the_full_shortest_path_distance_for_indexes_below_starting_perms() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
         assert_eq!(&subject.lower_bounds[..1], &[2]);
 
         subject.improve_based_on(1);
@@ -253,7 +274,7 @@ mod improve_based_on {
 
     #[test]
     fn it_sets_lower_bound_to_the_nearest_distance_to_the_end_for_each_number_of_perms() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
 
         subject.improve_based_on(1);
         assert_eq!(&subject.lower_bounds[1..], &[2, 1, 0]);
@@ -291,40 +312,39 @@ mod improve_based_on {
 
     #[test]
     fn it_increases_the_lower_bound_of_the_next_goal_based_on_previous_knowledge() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
 
         subject.improve_based_on(1);
         subject.improve_based_on(3);
         subject.improve_based_on(4);
+        subject.improve_based_on(5);
 
         //   Graph of number of perms vs. distance:
         //
         //
-        //           |            o (5, 6)  <--    best lower bound for 5 perms is
-        //           |           /             \   higher, because we know that...
-        //           |          x (4, 5)        |
-        //           |          |               |- ...we're about to add another
-        //           |          x (4, 4)        |     2 perms from here and...
-        //           |         /               /
-        //           |        x (3, 3)      <--
-        //           |       /                  \
-        //  distance |      x (2, 2)             |
-        //           |      |                    |- ...here we added 2 perms
-        //           |      x (2, 1)             |     which required 3 distance
-        //           |     /                    /
-        //           |____x_(1, 0)__        <--
+        //           |              o (6, 7)  <--    best lower bound for 6 perms is
+        //           |             /             \   higher, because we know that...
+        //           |            x (5, 5)        |
+        //           |           /                |
+        //           |          x (4, 4)          |- ...we're about to add another
+        //           |         /                  |     3 perms from here and...
+        //           |        x (3, 3)         <--
+        //           |       /                    \
+        //  distance |      x (2, 2)               |
+        //           |      |                      |- ...here we reached 3 perms
+        //           |      x (2, 1)               |     which required 3 distance,
+        //           |     /                       /     so adding 3 from anywhere
+        //           |____x_(1, 0)__           <--       takes at least 3 + 1
         //
         //            number of perms
 
-        assert_eq!(subject.max_depths, &[0, 0, 2, 3, 5, 0]);
-                                                   //         ^
-                                                   // this is higher because
-                                                   // of the higher lower bound
+        assert_eq!(subject.max_depths, &[0, 1, 2, 3, 5, 6, 0]);
+        assert_eq!(subject.lower_bounds, &[8, 6, 5, 4, 2, 1, 0]);
 
-        assert_eq!(subject.lower_bounds, &[7, 6, 4, 3, 1, 0]);
-                                        // ^  ^  ^  ^
-                                        // these are further away because
-                                        // of the higher lower bound
+        // So a candidate on the shortest path is at least 7 less its depth
+        // away, e.g. 3 from (4, 4) rather than the 2 it takes to add 2 perms.
+        assert_eq!(subject.cost(4, 4), 3);
+        assert_eq!(subject.cost(4, 6), 2);
     }
 }
 
@@ -333,7 +353,7 @@ mod first_goal {
 
     #[test]
     fn it_returns_the_number_of_perms_in_the_first_subgoal() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
         assert_eq!(subject.first_goal(), 2);
 
         subject.improve_based_on(1);
@@ -349,7 +369,7 @@ mod next_goal {
 
     #[test]
     fn it_returns_the_number_of_perms_in_the_next_subgoal() {
-        let mut subject = Subject::seed();
+        let mut subject = Subject::seed(&PROBLEM);
         assert_eq!(subject.next_goal(), 2);
 
         subject.improve_based_on(1);
@@ -359,3 +379,173 @@ mod next_goal {
         assert_eq!(subject.next_goal(), 4);
     }
 }
+
+mod write_to {
+    use super::*;
+    use crate::codec::{Encoder, Decoder};
+
+    #[test]
+    fn it_can_be_read_back_with_read_from() {
+        let mut subject = Subject::seed(&PROBLEM);
+
+        subject.improve_based_on(1);
+        subject.improve_based_on(3);
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+        assert_eq!(result, subject);
+    }
+
+    #[test]
+    fn it_rejects_tables_of_different_lengths() {
+        let subject = Subject::new(1, vec![0], vec![2, 1, 0], vec![0, 0]);
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let result = Subject::read_from(&mut Decoder::new(&bytes[..]));
+
+        assert!(result.is_err());
+    }
+}
+
+mod starting_from {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_table_of_distances_from_the_seed() {
+        let table = Subject::from_distances(&PROBLEM, &[0, 1, 2]);
+        let subject = Subject::starting_from(&table, 3, 5);
+
+        assert_eq!(subject.distances(), &[0]);
+        assert_eq!(subject.seed_distances(), &[0, 1, 2]);
+        assert_eq!(subject.next_goal(), 4);
+    }
+
+    #[test]
+    fn it_measures_depths_from_the_candidate_it_starts_from() {
+        let mut subject = Subject::starting_from(&Subject::seed(&PROBLEM), 3, 5);
+
+        subject.improve_based_on(2);
+
+        assert_eq!(subject.cost(3, 5), 3);
+        assert_eq!(subject.cost(3, 6), 2);
+        assert_eq!(subject.cost(4, 7), 1);
+    }
+
+    #[test]
+    fn it_can_be_read_back_with_read_from() {
+        use crate::codec::{Encoder, Decoder};
+
+        let mut subject = Subject::starting_from(&Subject::seed(&PROBLEM), 3, 5);
+        subject.improve_based_on(2);
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+        assert_eq!(result, subject);
+    }
+}
+
+mod from_distances {
+    use super::*;
+
+    #[test]
+    fn it_builds_the_same_heuristic_as_improving_based_on_each_distance() {
+        let mut expected = Subject::seed(&PROBLEM);
+
+        expected.improve_based_on(1);
+        expected.improve_based_on(3);
+        expected.improve_based_on(4);
+
+        let subject = Subject::from_distances(&PROBLEM, &[0, 1, 3, 4]);
+
+        assert_eq!(subject, expected);
+        assert_eq!(subject.next_goal(), 5);
+    }
+}
+
+mod published {
+    use super::*;
+
+    #[test]
+    fn it_preloads_the_published_distances_up_to_the_number_of_perms() {
+        let subject = Subject::published(&PROBLEM, 8).unwrap();
+
+        assert_eq!(subject.distances(), &[0, 1, 2, 3, 4, 6, 7, 8]);
+        assert_eq!(subject.next_goal(), 9);
+    }
+
+    #[test]
+    fn it_returns_none_if_there_is_no_published_table() {
+        let problem = Problem::new(6);
+        assert_eq!(Subject::published(&problem, 8), None);
+    }
+}
+
+mod write_distances {
+    use super::*;
+
+    #[test]
+    fn it_writes_one_line_per_number_of_perms() {
+        let subject = Subject::from_distances(&PROBLEM, &[0, 1, 3]);
+
+        let mut bytes = vec![];
+        subject.write_distances(&mut bytes).unwrap();
+
+        assert_eq!(String::from_utf8(bytes).unwrap(), "# perms distance\n1 0\n2 1\n3 3\n");
+    }
+
+    #[test]
+    fn it_can_be_read_back_with_read_distances() {
+        let subject = Subject::published(&PROBLEM, 20).unwrap();
+
+        let mut bytes = vec![];
+        subject.write_distances(&mut bytes).unwrap();
+
+        let result = Subject::read_distances(&PROBLEM, &bytes[..]).unwrap();
+
+        assert_eq!(result, subject);
+    }
+}
+
+mod read_distances {
+    use super::*;
+
+    #[test]
+    fn it_ignores_comments_and_blank_lines() {
+        let text = "# from a previous run\n\n1 0\n2 1\n";
+        let subject = Subject::read_distances(&PROBLEM, text.as_bytes()).unwrap();
+
+        assert_eq!(subject.distances(), &[0, 1]);
+    }
+
+    #[test]
+    fn it_rejects_lines_that_skip_a_number_of_perms() {
+        let result = Subject::read_distances(&PROBLEM, "1 0\n3 2\n".as_bytes());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_rejects_distances_that_do_not_increase() {
+        let result = Subject::read_distances(&PROBLEM, "1 0\n2 1\n3 1\n".as_bytes());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_rejects_tables_that_do_not_start_at_zero() {
+        let result = Subject::read_distances(&PROBLEM, "1 1\n".as_bytes());
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn it_rejects_malformed_lines() {
+        let result = Subject::read_distances(&PROBLEM, "1 0 extra\n".as_bytes());
+        assert!(result.is_err());
+    }
+}
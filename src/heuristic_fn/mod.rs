@@ -0,0 +1,149 @@
+use crate::candidate::Candidate;
+use crate::heuristic::Heuristic;
+use crate::problem::Problem;
+
+/// Estimates the distance from a candidate to the next goal. The searches call
+/// learn whenever the learned table improves so that estimates built on it
+/// keep up, and combinators pass it on to what they're made of.
+pub trait HeuristicFn: Send + Sync {
+    fn cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize) -> usize;
+
+    fn learn(&mut self, _heuristic: &Heuristic) {}
+
+    /// The cost multiplied by the weight and rounded up.
+    fn weighted_cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize, weight: f64) -> usize {
+        let cost = self.cost(problem, candidate, search_depth);
+
+        (cost as f64 * weight).ceil() as usize
+    }
+
+    /// The greater of this estimate and the other's, which is admissible if
+    /// both of them are.
+    fn max<H: HeuristicFn>(self, other: H) -> Max<Self, H> where Self: Sized {
+        Max::new(self, other)
+    }
+
+    fn scaled(self, factor: f64) -> Scaled<Self> where Self: Sized {
+        Scaled::new(self, factor)
+    }
+}
+
+/// The learned table, which only looks at the number of perms.
+impl HeuristicFn for Heuristic {
+    fn cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize) -> usize {
+        Heuristic::cost(self, candidate.number_of_permutations(problem), search_depth)
+    }
+
+    fn learn(&mut self, heuristic: &Heuristic) {
+        *self = heuristic.clone();
+    }
+}
+
+impl HeuristicFn for Box<dyn HeuristicFn> {
+    fn cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize) -> usize {
+        (**self).cost(problem, candidate, search_depth)
+    }
+
+    fn learn(&mut self, heuristic: &Heuristic) {
+        (**self).learn(heuristic)
+    }
+}
+
+/// Estimates nothing, which turns A* into a breadth-first search.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Zero;
+
+impl HeuristicFn for Zero {
+    fn cost(&self, _problem: &Problem, _candidate: &Candidate, _search_depth: usize) -> usize {
+        0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Max<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A: HeuristicFn, B: HeuristicFn> Max<A, B> {
+    pub fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+}
+
+impl<A: HeuristicFn, B: HeuristicFn> HeuristicFn for Max<A, B> {
+    fn cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize) -> usize {
+        let a = self.a.cost(problem, candidate, search_depth);
+        let b = self.b.cost(problem, candidate, search_depth);
+
+        a.max(b)
+    }
+
+    fn learn(&mut self, heuristic: &Heuristic) {
+        self.a.learn(heuristic);
+        self.b.learn(heuristic);
+    }
+}
+
+/// Multiplies the estimate by a factor and rounds down, so that scaling by
+/// less than one keeps an admissible estimate admissible.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Scaled<H> {
+    heuristic: H,
+    factor: f64,
+}
+
+impl<H: HeuristicFn> Scaled<H> {
+    pub fn new(heuristic: H, factor: f64) -> Self {
+        Self { heuristic, factor }
+    }
+}
+
+impl<H: HeuristicFn> HeuristicFn for Scaled<H> {
+    fn cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize) -> usize {
+        let cost = self.heuristic.cost(problem, candidate, search_depth);
+
+        (cost as f64 * self.factor).floor() as usize
+    }
+
+    fn learn(&mut self, heuristic: &Heuristic) {
+        self.heuristic.learn(heuristic);
+    }
+}
+
+/// The learned table's cost from where the candidate will first be ready,
+/// plus the symbols it needs to get there. None of them can add a permutation
+/// so this is admissible wherever the table is, and it's never less than the
+/// table's cost since that drops by at most one per symbol.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TailAware {
+    heuristic: Heuristic,
+}
+
+impl TailAware {
+    pub fn new(heuristic: Heuristic) -> Self {
+        Self { heuristic }
+    }
+}
+
+impl HeuristicFn for TailAware {
+    fn cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize) -> usize {
+        let perms = candidate.number_of_permutations(problem);
+
+        // The goal doesn't need to be ready.
+        if perms >= self.heuristic.next_goal() {
+            return self.heuristic.cost(perms, search_depth);
+        }
+
+        let until_ready = candidate.symbols_until_ready(problem);
+
+        until_ready + self.heuristic.cost(perms, search_depth + until_ready)
+    }
+
+    fn learn(&mut self, heuristic: &Heuristic) {
+        self.heuristic = heuristic.clone();
+    }
+}
+
+#[cfg(test)]
+mod test;
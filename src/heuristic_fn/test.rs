@@ -0,0 +1,207 @@
+use super::*;
+
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
+fn heuristic() -> Heuristic {
+    Heuristic::new(1, vec![0], vec![9, 3, 0, 0, 0, 0, 0], vec![0, 2, 0, 0, 0, 0, 0])
+}
+
+fn improved_heuristic() -> Heuristic {
+    Heuristic::new(1, vec![0], vec![9, 7, 0, 0, 0, 0, 0], vec![0, 2, 0, 0, 0, 0, 0])
+}
+
+struct Constant(usize);
+
+impl HeuristicFn for Constant {
+    fn cost(&self, _problem: &Problem, _candidate: &Candidate, _search_depth: usize) -> usize {
+        self.0
+    }
+}
+
+mod cost {
+    use super::*;
+
+    #[test]
+    fn it_looks_up_the_number_of_perms_in_the_learned_table() {
+        let seed = Candidate::seed(&PROBLEM);
+        let subject = heuristic();
+
+        assert_eq!(HeuristicFn::cost(&subject, &PROBLEM, &seed, 0), 5);
+        assert_eq!(HeuristicFn::cost(&subject, &PROBLEM, &seed, 1), 4);
+        assert_eq!(HeuristicFn::cost(&subject, &PROBLEM, &seed, 2), 3);
+    }
+
+    #[test]
+    fn it_is_always_zero_for_the_zero_heuristic() {
+        let seed = Candidate::seed(&PROBLEM);
+        let neighbor = seed.expand(&PROBLEM, 0);
+
+        assert_eq!(Zero.cost(&PROBLEM, &seed, 0), 0);
+        assert_eq!(Zero.cost(&PROBLEM, &neighbor, 5), 0);
+    }
+
+    #[test]
+    fn it_is_the_greater_of_the_two_for_max() {
+        let seed = Candidate::seed(&PROBLEM);
+
+        assert_eq!(Constant(2).max(Constant(7)).cost(&PROBLEM, &seed, 0), 7);
+        assert_eq!(Constant(7).max(Constant(2)).cost(&PROBLEM, &seed, 0), 7);
+        assert_eq!(heuristic().max(Constant(4)).cost(&PROBLEM, &seed, 0), 5);
+        assert_eq!(heuristic().max(Constant(4)).cost(&PROBLEM, &seed, 2), 4);
+    }
+
+    #[test]
+    fn it_multiplies_by_the_factor_and_rounds_down_for_scaled() {
+        let seed = Candidate::seed(&PROBLEM);
+
+        assert_eq!(Constant(7).scaled(0.5).cost(&PROBLEM, &seed, 0), 3);
+        assert_eq!(Constant(7).scaled(2.0).cost(&PROBLEM, &seed, 0), 14);
+        assert_eq!(Constant(7).scaled(0.0).cost(&PROBLEM, &seed, 0), 0);
+    }
+
+    #[test]
+    fn it_can_be_boxed() {
+        let seed = Candidate::seed(&PROBLEM);
+        let subject: Box<dyn HeuristicFn> = Box::new(heuristic().max(Zero));
+
+        assert_eq!(subject.cost(&PROBLEM, &seed, 0), 5);
+    }
+}
+
+mod weighted_cost {
+    use super::*;
+
+    #[test]
+    fn it_multiplies_the_cost_by_the_weight_and_rounds_up() {
+        let seed = Candidate::seed(&PROBLEM);
+
+        assert_eq!(Constant(7).weighted_cost(&PROBLEM, &seed, 0, 1.0), 7);
+        assert_eq!(Constant(7).weighted_cost(&PROBLEM, &seed, 0, 1.5), 11);
+        assert_eq!(Zero.weighted_cost(&PROBLEM, &seed, 0, 3.0), 0);
+    }
+}
+
+mod learn {
+    use super::*;
+
+    #[test]
+    fn it_replaces_the_learned_table() {
+        let seed = Candidate::seed(&PROBLEM);
+        let mut subject = heuristic();
+
+        subject.learn(&improved_heuristic());
+
+        assert_eq!(subject, improved_heuristic());
+        assert_eq!(HeuristicFn::cost(&subject, &PROBLEM, &seed, 0), 9);
+    }
+
+    #[test]
+    fn it_passes_the_table_through_combinators() {
+        let seed = Candidate::seed(&PROBLEM);
+        let mut subject: Box<dyn HeuristicFn> = Box::new(Zero.max(heuristic().scaled(2.0)));
+
+        assert_eq!(subject.cost(&PROBLEM, &seed, 0), 10);
+
+        subject.learn(&improved_heuristic());
+
+        assert_eq!(subject.cost(&PROBLEM, &seed, 0), 18);
+    }
+
+    #[test]
+    fn it_does_nothing_for_the_zero_heuristic() {
+        let mut subject = Zero;
+
+        subject.learn(&improved_heuristic());
+
+        assert_eq!(subject, Zero);
+    }
+}
+
+mod tail_aware {
+    use super::*;
+    use crate::admissibility::{Admissibility, Kind};
+
+    lazy_static! {
+        static ref FOUR: Problem = Problem::new(4);
+    }
+
+    // Only knows that each symbol adds at most one permutation, which can't
+    // overestimate, so overestimates would come from the tail alone.
+    struct OnePermPerSymbol<H>(H);
+
+    impl<H: HeuristicFn> HeuristicFn for OnePermPerSymbol<H> {
+        fn cost(&self, problem: &Problem, candidate: &Candidate, search_depth: usize) -> usize {
+            self.0.cost(problem, candidate, search_depth)
+        }
+
+        fn learn(&mut self, heuristic: &Heuristic) {
+            let distances = (0..heuristic.distances().len()).collect::<Vec<_>>();
+            self.0.learn(&Heuristic::from_distances(&FOUR, &distances));
+        }
+    }
+
+    #[test]
+    fn it_adds_the_symbols_until_ready_to_the_cost_from_that_depth() {
+        let seed = Candidate::seed(&PROBLEM);
+        let not_ready = seed.expand(&PROBLEM, 3);
+        let subject = TailAware::new(heuristic());
+
+        assert_eq!(not_ready.number_of_permutations(&PROBLEM), 1);
+        assert_eq!(not_ready.symbols_until_ready(&PROBLEM), 2);
+
+        assert_eq!(subject.cost(&PROBLEM, &seed, 0), 5);
+        assert_eq!(subject.cost(&PROBLEM, &not_ready, 0), 2 + 3);
+        assert_eq!(subject.cost(&PROBLEM, &not_ready, 1), 2 + 3);
+        assert_eq!(subject.cost(&PROBLEM, &not_ready, 2), 2 + 3);
+        assert_eq!(subject.cost(&PROBLEM, &not_ready, 3), 2 + 3);
+    }
+
+    #[test]
+    fn it_is_never_less_than_the_learned_cost() {
+        let subject = TailAware::new(heuristic());
+        let not_ready = Candidate::seed(&PROBLEM).expand(&PROBLEM, 3);
+
+        for depth in 0..5 {
+            let learned = HeuristicFn::cost(&heuristic(), &PROBLEM, &not_ready, depth);
+            assert!(subject.cost(&PROBLEM, &not_ready, depth) >= learned);
+        }
+    }
+
+    #[test]
+    fn it_does_not_add_to_the_cost_of_the_goal() {
+        let subject = TailAware::new(Heuristic::seed(&PROBLEM));
+        let goal = Candidate::seed(&PROBLEM).expand(&PROBLEM, 0).expand(&PROBLEM, 2);
+
+        assert_eq!(goal.number_of_permutations(&PROBLEM), 2);
+        assert_eq!(goal.symbols_until_ready(&PROBLEM), 1);
+        assert_eq!(subject.cost(&PROBLEM, &goal, 2), 0);
+    }
+
+    #[test]
+    fn it_is_admissible_and_consistent_wherever_the_table_is() {
+        let admissibility = Admissibility::new(&FOUR, 10);
+
+        let table = &mut OnePermPerSymbol(Heuristic::seed(&FOUR));
+        let subject = &mut OnePermPerSymbol(TailAware::new(Heuristic::seed(&FOUR)));
+
+        assert_eq!(admissibility.check(table), vec![]);
+        assert_eq!(admissibility.check(subject), vec![]);
+    }
+
+    #[test]
+    fn it_has_no_violations_but_those_of_the_learned_table() {
+        let admissibility = Admissibility::new(&FOUR, 10);
+
+        let table = admissibility.check(&mut Heuristic::seed(&FOUR));
+        let subject = admissibility.check(&mut TailAware::new(Heuristic::seed(&FOUR)));
+
+        // The exact distances make the table inconsistent in places, but it
+        // never overestimates, and the tail adds no violations of its own.
+        assert_eq!(table.len(), 39);
+        assert!(table.iter().all(|violation| violation.kind() == Kind::Inconsistent));
+
+        assert_eq!(subject, table);
+    }
+}
@@ -1,39 +1,351 @@
+use crate::backend::Backend;
 use crate::candidate::Candidate;
+use crate::checkpoint::Checkpoint;
+use crate::codec::{Encoder, Decoder};
+use crate::events::Events;
+use crate::format::Value;
 use crate::heuristic::Heuristic;
+use crate::heuristic_fn::HeuristicFn;
+use crate::limits::Limits;
+use crate::problem::Problem;
 use crate::search::Search;
+use crate::stats::SearchStats;
+use crate::verification::Verification;
+use std::io::{self, Read, Write};
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
 
-pub struct Incremental {
+/// Finds the shortest path to each number of perms in turn, improving the
+/// heuristic after each one. The A* search is used unless another backend is
+/// given, such as the iterative deepening search.
+///
+/// The heuristic only learns the shortest distances, so a weighted search
+/// leaves it behind. The search is given a heuristic that also learns lower
+/// bounds on the distances a weighted search didn't prove, which keeps it
+/// admissible.
+pub struct Incremental<'a, B: Backend<'a> = Search<'a>> {
+    problem: &'a Problem,
     heuristic: Heuristic,
-    search: Search,
+    search_heuristic: Heuristic,
+    search: B,
+    subgoal: usize,
+    target: Option<usize>,
+    checkpoint: Option<Checkpoint>,
+    events: Option<Events>,
+    weights: Vec<f64>,
+    checkpoint_error: Option<io::Error>,
 }
 
-impl Incremental {
-    pub fn new(heuristic: Heuristic, search: Search) -> Self {
-        Self { heuristic, search }
+impl<'a, B: Backend<'a>> Incremental<'a, B> {
+    pub fn new(heuristic: Heuristic, search: B) -> Self {
+        let problem = search.problem();
+        let search_heuristic = heuristic.clone();
+
+        Self { problem, heuristic, search_heuristic, search, subgoal: 0, target: None, checkpoint: None, events: None, weights: vec![1.0], checkpoint_error: None }
+    }
+
+    pub fn checkpoint_to(&mut self, checkpoint: Checkpoint) {
+        self.checkpoint = Some(checkpoint);
+    }
+
+    /// Writes an event when the search starts, reaches each subgoal, saves a
+    /// checkpoint and stops or finishes. The backend writes its progress
+    /// towards each subgoal to the same stream.
+    pub fn emit_events_to(&mut self, events: Events) {
+        self.search.emit_events_to(events.clone());
+        self.events = Some(events);
+    }
+
+    /// Estimates costs with this rather than the learned heuristic, which it
+    /// learns from after each subgoal.
+    pub fn use_heuristic_fn(&mut self, estimate: Box<dyn HeuristicFn>) {
+        self.search.use_heuristic_fn(estimate);
+    }
+
+    /// Stops once this many perms are reached rather than every permutation.
+    pub fn stop_at(&mut self, perms: usize) {
+        self.target = Some(perms);
+    }
+
+    pub fn limit_to(&mut self, limits: Limits) {
+        self.search.limit_to(limits);
+    }
+
+    /// Multiplies the heuristic by the weight to find paths sooner that are at
+    /// most that many times longer than the shortest.
+    pub fn weight_by(&mut self, weight: f64) {
+        self.weights = vec![weight];
+    }
+
+    /// Searches for each subgoal with the weight, then searches for it again
+    /// with the weight lowered by the step until it reaches one. With the A*
+    /// search this is ARA*: it continues from the same sets and only searches
+    /// again from the candidates that were kept aside as inconsistent. The
+    /// milestone is called with each shorter path that's found. The search
+    /// it's given has the weight and lower bound the path was found with.
+    pub fn anytime(&mut self, weight: f64, step: f64) {
+        let mut weight = weight;
+
+        self.weights = vec![];
+
+        while weight > 1.0 && step > 0.0 {
+            self.weights.push(weight);
+            weight -= step;
+        }
+
+        self.weights.push(1.0);
+    }
+
+    /// Returns the length of the shortest superpermutation, not counting the
+    /// first permutation. The milestone is called with the distance, subgoal,
+    /// string, search, heuristic and the search's stats each time a subgoal is
+    /// reached.
+    pub fn shortest_path<F>(&mut self, candidate: Candidate, milestone: F) -> Option<usize>
+        where F: FnMut(usize, usize, &str, &B, &Heuristic, &SearchStats)
+    {
+        let problem = self.problem;
+        let start = candidate.number_of_permutations(problem) + 1;
+
+        // Skip ahead if the heuristic already knows the earlier distances.
+        self.subgoal = start.max(self.heuristic.next_goal());
+        self.search_heuristic = self.heuristic.clone();
+
+        self.search.seed(candidate);
+        self.search.update_heuristic(&self.search_heuristic);
+
+        self.resume(milestone)
     }
 
-    pub fn shortest_path<F>(&mut self, candidate: Candidate, mut milestone: F) -> Option<usize>
-        where F: FnMut(usize, usize, &Search, &Heuristic)
+    /// Continues from a known prefix, which might not be the shortest way to
+    /// its perms. The search's heuristic starts from the prefix's candidate and
+    /// only learns the distances from it, while the heuristic it was given is
+    /// left as it was because these aren't distances from the seed.
+    pub fn shortest_path_from_string<F>(&mut self, string: &str, milestone: F) -> Result<Option<usize>, String>
+        where F: FnMut(usize, usize, &str, &B, &Heuristic, &SearchStats)
     {
-        let search = &mut self.search;
-        let heuristic = &mut self.heuristic;
+        self.search.seed_from_string(string)?;
+
+        let problem = self.problem;
+        let verification = Verification::new(problem, string)?;
+        let perms = verification.number_of_permutations();
+        let depth = verification.len() - problem.symbols();
 
-        let start = candidate.number_of_permutations() + 1;
-        let finish = Candidate::maximum_permutations();
+        self.search_heuristic = Heuristic::starting_from(&self.heuristic, perms, depth);
+        self.subgoal = perms + 1;
+
+        self.search.update_heuristic(&self.search_heuristic);
+
+        Ok(self.resume(milestone))
+    }
 
-        search.seed(candidate);
+    /// Continues from the current subgoal, e.g. after reading a checkpoint.
+    pub fn resume<F>(&mut self, mut milestone: F) -> Option<usize>
+        where F: FnMut(usize, usize, &str, &B, &Heuristic, &SearchStats)
+    {
+        let maximum = Candidate::maximum_permutations(self.problem);
+        let finish = self.target.map_or(maximum, |t| t.min(maximum));
+        let weights = self.weights.clone();
         let mut distance = None;
 
-        for subgoal in start..=finish {
-            distance = search.shortest_path(subgoal);
-            heuristic.improve_based_on(distance?);
+        self.emit("start", vec![
+            ("symbols", self.problem.symbols().into()),
+            ("backend", B::NAME.into()),
+            ("subgoal", self.subgoal.into()),
+            ("target", finish.into()),
+        ]);
+
+        while self.subgoal <= finish {
+            let mut shortest: Option<(usize, String)> = None;
+            let mut lower_bound = 0;
+
+            for (i, weight) in weights.iter().enumerate() {
+                if self.search.weight() != *weight {
+                    self.search.weight_by(*weight);
+                    self.search.update_heuristic(&self.search_heuristic);
+                }
+
+                let found = self.search_until_stopped();
+
+                if self.search.interrupted() {
+                    self.save_checkpoint();
+                    self.emit("stopped", vec![("subgoal", self.subgoal.into())]);
+                    return None;
+                }
+
+                let found = found?;
+
+                lower_bound = lower_bound.max(self.search.lower_bound()?);
+
+                if shortest.as_ref().is_some_and(|(d, _)| found >= *d) {
+                    continue;
+                }
+
+                let string = self.search.path()?.to_string();
+
+                // The last path is reported below once the heuristic learns from it.
+                if i + 1 < weights.len() {
+                    milestone(found, self.subgoal, &string, &self.search, &self.heuristic, self.search.stats());
+                }
+
+                shortest = Some((found, string));
+            }
+
+            let (found, string) = shortest?;
+
+            self.learn(found, lower_bound);
+            self.emit_subgoal(found, &string);
+
+            let search = &mut self.search;
+            let heuristic = &mut self.heuristic;
+
+            distance = Some(found);
+
+            milestone(found, self.subgoal, &string, search, heuristic, search.stats());
+
+            search.weight_by(weights[0]);
+            search.update_heuristic(&self.search_heuristic); // TODO: only if changed
 
-            milestone(distance?, subgoal, &search, &heuristic);
-            search.update_heuristic(heuristic); // TODO: only if changed
+            self.subgoal += 1;
+
+            if self.checkpoint.as_ref().is_some_and(|c| c.is_due()) {
+                self.save_checkpoint();
+            }
         }
 
+        self.emit("finished", vec![("distance", distance.map_or(Value::Text(String::new()), Value::Number))]);
+
         distance
     }
+
+    pub fn subgoal(&self) -> usize {
+        self.subgoal
+    }
+
+    pub fn problem(&self) -> &'a Problem {
+        self.problem
+    }
+
+    pub fn search(&self) -> &B {
+        &self.search
+    }
+
+    pub fn heuristic(&self) -> &Heuristic {
+        &self.heuristic
+    }
+
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        self.search.interrupt_flag()
+    }
+
+    pub fn interrupted(&self) -> bool {
+        self.search.interrupted()
+    }
+
+    /// The error from the last checkpoint that couldn't be saved, if any. The
+    /// search carries on regardless unless it was stopping anyway.
+    pub fn checkpoint_error(&self) -> Option<&io::Error> {
+        self.checkpoint_error.as_ref()
+    }
+
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_usize(self.subgoal)?;
+
+        self.heuristic.write_to(encoder)?;
+        self.search_heuristic.write_to(encoder)?;
+        self.search.write_to(encoder)
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>, problem: &'a Problem) -> io::Result<Self> {
+        let subgoal = decoder.read_usize()?;
+        let heuristic = Heuristic::read_from(decoder)?;
+        let search_heuristic = Heuristic::read_from(decoder)?;
+        let search = B::read_from(decoder, problem)?;
+
+        Ok(Self { problem, heuristic, search_heuristic, search, subgoal, target: None, checkpoint: None, events: None, weights: vec![1.0], checkpoint_error: None })
+    }
+
+    // The distance is the shortest once a search without a weight reached it,
+    // otherwise the search's heuristic learns the lower bound instead. Each
+    // perm needs another symbol so it's also more than the previous distance.
+    // Both are measured from where the search's heuristic starts, and only
+    // distances from the seed are learned by the heuristic itself.
+    fn learn(&mut self, distance: usize, lower_bound: usize) {
+        let depth = self.search_heuristic.starting_depth();
+        let (distance, lower_bound) = (distance - depth, lower_bound.saturating_sub(depth));
+
+        if self.search.weight() == 1.0 {
+            if self.heuristic.next_goal() == self.subgoal && depth == 0 {
+                self.heuristic.improve_based_on(distance);
+            }
+
+            self.search_heuristic.improve_based_on(distance);
+        } else {
+            let previous = self.search_heuristic.distances().last().map_or(0, |d| d + 1);
+
+            self.search_heuristic.improve_based_on(lower_bound.max(previous));
+        }
+    }
+
+    // Pauses the search whenever a checkpoint is due so that it's saved on time
+    // rather than once the subgoal is reached.
+    fn search_until_stopped(&mut self) -> Option<usize> {
+        loop {
+            self.search.pause_at(self.checkpoint.as_ref().map(|c| c.due_at()));
+
+            let found = self.search.shortest_path(self.subgoal);
+
+            if !self.search.paused() {
+                return found;
+            }
+
+            self.save_checkpoint();
+        }
+    }
+
+    fn save_checkpoint(&mut self) {
+        if let Some(mut checkpoint) = self.checkpoint.take() {
+            let saved = checkpoint.save(self);
+            self.checkpoint = Some(checkpoint);
+
+            match saved {
+                Ok(()) => {
+                    self.checkpoint_error = None;
+                    self.emit("checkpoint", vec![("subgoal", self.subgoal.into())]);
+                },
+                Err(error) => {
+                    self.emit("checkpoint_failed", vec![("subgoal", self.subgoal.into()), ("error", error.to_string().into())]);
+                    self.checkpoint_error = Some(error);
+                },
+            }
+        }
+    }
+
+    // The heuristic's distances are a snapshot of the table learned so far.
+    fn emit_subgoal(&self, distance: usize, string: &str) {
+        self.emit("subgoal", vec![
+            ("subgoal", self.subgoal.into()),
+            ("distance", distance.into()),
+            ("string", string.into()),
+            ("weight", self.search.weight().into()),
+            ("expansions", self.search.expansions().into()),
+            ("open_set", self.search.open_set_len().into()),
+            ("closed_set", self.search.closed_set_len().into()),
+            ("heuristic", self.heuristic.distances().to_vec().into()),
+        ]);
+    }
+
+    fn emit(&self, event: &str, fields: Vec<(&str, Value)>) {
+        if let Some(events) = &self.events {
+            events.emit(event, &fields);
+        }
+    }
+}
+
+impl<'a> Incremental<'a> {
+    /// Expands candidates on this many threads, which only the A* search can.
+    pub fn use_threads(&mut self, threads: usize) {
+        self.search.use_threads(threads);
+    }
 }
 
 #[cfg(test)]
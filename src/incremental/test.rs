@@ -1,16 +1,25 @@
-#![cfg(feature = "four_symbols")]
-
 use super::*;
 use crate::open_set::OpenSet;
 use crate::closed_set::ClosedSet;
+use crate::iterative_deepening::IterativeDeepening;
+use crate::parents::Parents;
+use crate::problem::Problem;
+use std::collections::HashSet;
+use std::env;
+use std::sync::atomic::Ordering::Relaxed;
+use std::time::Duration;
+
+type Subject<'a> = Incremental<'a>;
 
-type Subject = Incremental;
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(4);
+}
 
-fn subject() -> Subject {
+fn subject() -> Subject<'static> {
     let open_set = OpenSet::new();
     let closed_set = ClosedSet::new();
-    let search = Search::new(open_set, closed_set);
-    let heuristic = Heuristic::seed();
+    let search = Search::new(&PROBLEM, open_set, closed_set);
+    let heuristic = Heuristic::seed(&PROBLEM);
 
     Subject::new(heuristic, search)
 }
@@ -21,10 +30,10 @@ mod shortest_path {
     #[test]
     fn it_incrementally_finds_the_shortest_path() {
         let mut subject = subject();
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
         let mut milestones = vec![];
 
-        let distance = subject.shortest_path(candidate, |distance, subgoal, _, _| {
+        let distance = subject.shortest_path(candidate, |distance, subgoal, _, _, _, _| {
             milestones.push((distance, subgoal));
         });
 
@@ -44,12 +53,12 @@ mod shortest_path {
            (11, 10),
            (12, 11),
            (13, 12),
-           (16, 13),  // The shortest path to 13 perms has length 16.
-           (17, 14),
+           (15, 13),  // The shortest path to 13 perms has length 15.
+           (16, 14),
            (18, 15),
            (19, 16),
-           (21, 17),
-           (22, 18),
+           (20, 17),
+           (21, 18),
            (23, 19),
            (24, 20),
            (26, 21),
@@ -59,40 +68,453 @@ mod shortest_path {
         ]);
     }
 
+    #[test]
+    fn it_provides_the_string_for_each_subgoal() {
+        let mut subject = subject();
+        let candidate = Candidate::seed(&PROBLEM);
+        let mut strings = vec![];
+
+        subject.shortest_path(candidate, |_, _, string, _, _, _| {
+            strings.push(string.to_string());
+        });
+
+        assert_eq!(&strings[0], "01230");
+        assert_eq!(&strings[1], "012301");
+
+        let superpermutation = strings.last().unwrap();
+        let permutations = superpermutation.as_bytes().windows(4).filter(|w| {
+            w.iter().collect::<HashSet<_>>().len() == 4
+        }).collect::<HashSet<_>>();
+
+        assert_eq!(superpermutation.len(), 33);
+        assert_eq!(permutations.len(), 24);
+    }
+
+    #[test]
+    fn it_starts_from_the_next_goal_of_a_preloaded_heuristic() {
+        let mut subject = subject();
+        subject.heuristic = Heuristic::published(&PROBLEM, 12).unwrap();
+
+        let candidate = Candidate::seed(&PROBLEM);
+        let mut milestones = vec![];
+
+        let distance = subject.shortest_path(candidate, |distance, subgoal, _, _, _, _| {
+            milestones.push((distance, subgoal));
+        });
+
+        assert_eq!(distance, Some(29));
+        assert_eq!(milestones.first(), Some(&(15, 13)));
+        assert_eq!(milestones.len(), 12);
+    }
+
+    #[test]
+    fn it_continues_from_a_prefix_string() {
+        let mut subject = subject();
+        let mut milestones = vec![];
+        let mut strings = vec![];
+
+        let distance = subject.shortest_path_from_string("0123012031201", |distance, subgoal, string, _, _, _| {
+            milestones.push((distance, subgoal));
+            strings.push(string.to_string());
+        });
+
+        assert_eq!(distance, Ok(Some(29)));
+        assert_eq!(milestones.first(), Some(&(10, 9)));
+        assert_eq!(milestones.len(), 16);
+
+        assert!(strings.iter().all(|s| s.starts_with("0123012031201")));
+    }
+
+    #[test]
+    fn it_finds_the_same_distances_as_a_breadth_first_search_from_a_prefix_that_is_not_the_shortest() {
+        use crate::heuristic_fn::Zero;
+
+        let prefix = "012320123";
+        let mut zero = subject();
+        let mut learned = subject();
+        let mut expected = vec![];
+        let mut milestones = vec![];
+
+        zero.use_heuristic_fn(Box::new(Zero));
+        zero.stop_at(10);
+        learned.stop_at(10);
+
+        zero.shortest_path_from_string(prefix, |distance, subgoal, _, _, _, _| {
+            expected.push((distance, subgoal));
+        }).unwrap();
+
+        learned.shortest_path_from_string(prefix, |distance, subgoal, string, _, _, _| {
+            assert_eq!(string.len(), distance + 4);
+            milestones.push((distance, subgoal));
+        }).unwrap();
+
+        assert_eq!(milestones, expected);
+        assert_eq!(milestones[3], (10, 6));
+        assert_eq!(learned.heuristic(), &Heuristic::seed(&PROBLEM));
+    }
+
+    #[test]
+    fn it_returns_an_error_for_an_invalid_prefix() {
+        let mut subject = subject();
+        let result = subject.shortest_path_from_string("01234", |_, _, _, _, _, _| {});
+
+        assert_eq!(result, Err("'4' is not one of the 4 symbols".to_string()));
+    }
+
+    #[test]
+    fn it_stops_once_the_target_number_of_perms_is_reached() {
+        let mut subject = subject();
+        let mut milestones = vec![];
+
+        subject.stop_at(5);
+
+        let distance = subject.shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+            milestones.push((distance, subgoal));
+        });
+
+        assert_eq!(distance, Some(5));
+        assert_eq!(milestones, &[(1, 2), (2, 3), (3, 4), (5, 5)]);
+    }
+
+    #[test]
+    fn it_saves_checkpoints_that_are_due_while_searching_for_a_subgoal() {
+        let path = env::temp_dir().join(format!("supermutation-incremental-due-{}", std::process::id()));
+        let events = env::temp_dir().join(format!("supermutation-incremental-due-{}.ndjson", std::process::id()));
+        let mut subject = subject();
+
+        subject.checkpoint_to(Checkpoint::new(&path, Duration::from_secs(0)));
+        subject.emit_events_to(Events::to_file(&events).unwrap());
+        subject.stop_at(5);
+
+        let distance = subject.shortest_path(Candidate::seed(&PROBLEM), |_, _, _, _, _, _| {});
+        let contents = std::fs::read_to_string(&events).unwrap();
+        let checkpoints = contents.lines().filter(|l| l.contains("\"event\":\"checkpoint\"")).count();
+
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&events).unwrap();
+
+        assert_eq!(distance, Some(5));
+        assert!(checkpoints > 4);
+        assert!(subject.checkpoint_error().is_none());
+    }
+
+    #[test]
+    fn it_reports_a_checkpoint_that_could_not_be_saved_and_carries_on() {
+        let path = env::temp_dir().join("supermutation-missing-directory").join("run.checkpoint");
+        let mut subject = subject();
+
+        subject.checkpoint_to(Checkpoint::new(&path, Duration::from_secs(0)));
+        subject.stop_at(5);
+
+        let distance = subject.shortest_path(Candidate::seed(&PROBLEM), |_, _, _, _, _, _| {});
+
+        assert_eq!(distance, Some(5));
+        assert!(subject.checkpoint_error().is_some());
+    }
+
+    #[test]
+    fn it_stops_when_a_limit_is_exceeded() {
+        let mut subject = subject();
+
+        subject.limit_to(Limits::new(Some(1), None));
+
+        let distance = subject.shortest_path(Candidate::seed(&PROBLEM), |_, _, _, _, _, _| {});
+
+        assert_eq!(distance, None);
+        assert!(subject.interrupted());
+    }
+
+    #[test]
+    fn it_finds_the_same_distances_on_several_threads() {
+        let mut serial = vec![];
+
+        subject().shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+            serial.push((distance, subgoal));
+        });
+
+        assert_eq!(serial.last().map(|(_, subgoal)| *subgoal), Some(24));
+
+        for threads in 2..=4 {
+            let mut parallel = vec![];
+            let mut subject = subject();
+
+            subject.use_threads(threads);
+
+            subject.shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+                parallel.push((distance, subgoal));
+            });
+
+            assert_eq!(parallel, serial);
+        }
+    }
+
+    #[test]
+    fn it_finds_the_same_distances_with_a_hashed_or_verified_closed_set() {
+        let mut expected = vec![];
+
+        subject().shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+            expected.push((distance, subgoal));
+        });
+
+        for closed_set in [ClosedSet::hashed(), ClosedSet::verified()] {
+            let search = Search::new(&PROBLEM, OpenSet::new(), closed_set);
+            let mut subject = Subject::new(Heuristic::seed(&PROBLEM), search);
+            let mut milestones = vec![];
+
+            subject.shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+                milestones.push((distance, subgoal));
+            });
+
+            assert_eq!(milestones, expected);
+            assert_eq!(subject.search().closed_set().collisions(), 0);
+        }
+    }
+
+    #[test]
+    fn it_finds_the_same_distances_with_the_sets_and_parents_on_disk() {
+        let mut expected = vec![];
+
+        subject().shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+            expected.push((distance, subgoal));
+        });
+
+        for threads in [1, 3] {
+            let open_set = OpenSet::on_disk(env::temp_dir(), 1000).unwrap();
+            let closed_set = ClosedSet::on_disk(env::temp_dir(), 1000).unwrap();
+            let parents = Parents::on_disk(env::temp_dir(), 1000).unwrap();
+            let search = Search::with_parents(&PROBLEM, open_set, closed_set, parents);
+
+            let mut subject = Subject::new(Heuristic::seed(&PROBLEM), search);
+            let mut milestones = vec![];
+
+            subject.use_threads(threads);
+            subject.shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+                milestones.push((distance, subgoal));
+            });
+
+            assert_eq!(milestones, expected);
+        }
+    }
+
+    #[test]
+    fn it_finds_the_same_distances_with_the_iterative_deepening_backend() {
+        let mut expected = vec![];
+        let mut milestones = vec![];
+
+        subject().shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+            expected.push((distance, subgoal));
+        });
+
+        let search = IterativeDeepening::new(&PROBLEM);
+        let mut subject = Incremental::new(Heuristic::seed(&PROBLEM), search);
+
+        let distance = subject.shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+            milestones.push((distance, subgoal));
+        });
+
+        assert_eq!(distance, Some(29));
+        assert_eq!(milestones, expected);
+    }
+
+    #[test]
+    fn it_finds_paths_no_more_than_the_weight_times_longer_when_weighted() {
+        let mut shortest = vec![];
+        let mut weighted = vec![];
+
+        subject().shortest_path(Candidate::seed(&PROBLEM), |distance, _, _, _, _, _| {
+            shortest.push(distance);
+        });
+
+        let mut subject = subject();
+        subject.weight_by(2.0);
+
+        subject.shortest_path(Candidate::seed(&PROBLEM), |distance, _, _, search, _, _| {
+            assert_eq!(search.weight(), 2.0);
+            weighted.push(distance);
+        });
+
+        assert_eq!(weighted.len(), shortest.len());
+        assert!(weighted.iter().zip(&shortest).all(|(w, s)| w >= s && *w <= 2 * s));
+    }
+
+    #[test]
+    fn it_only_learns_the_shortest_distances_when_weighted() {
+        let mut shortest = subject();
+        shortest.shortest_path(Candidate::seed(&PROBLEM), |_, _, _, _, _, _| {});
+
+        let mut subject = subject();
+        let mut lower_bounds = vec![];
+
+        subject.weight_by(2.0);
+
+        subject.shortest_path(Candidate::seed(&PROBLEM), |distance, _, _, search, heuristic, _| {
+            assert_eq!(heuristic.distances(), &[0]);
+            assert!(search.lower_bound().unwrap() <= distance);
+
+            lower_bounds.push(search.lower_bound().unwrap());
+        });
+
+        let distances = shortest.heuristic().distances();
+        let learned = subject.search_heuristic.distances();
+
+        assert_eq!(subject.heuristic().distances(), &[0]);
+        assert_eq!(learned.len(), distances.len());
+        assert!(learned.iter().zip(distances).all(|(l, d)| l <= d));
+    }
+
+    #[test]
+    fn it_reports_shorter_paths_with_lower_weights_when_anytime() {
+        let mut shortest = vec![];
+        let mut milestones = vec![];
+
+        subject().shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, _, _, _| {
+            shortest.push((distance, subgoal));
+        });
+
+        let mut subject = subject();
+        subject.anytime(3.0, 1.0);
+
+        let distance = subject.shortest_path(Candidate::seed(&PROBLEM), |distance, subgoal, _, search, _, _| {
+            milestones.push((distance, subgoal, search.weight()));
+        });
+
+        let last_for_each_subgoal = milestones.iter()
+            .filter(|(_, _, weight)| *weight == 1.0)
+            .map(|(distance, subgoal, _)| (*distance, *subgoal))
+            .collect::<Vec<_>>();
+
+        assert_eq!(distance, Some(29));
+        assert_eq!(last_for_each_subgoal, shortest);
+        assert!(milestones.iter().any(|(_, _, weight)| *weight == 3.0));
+        assert_eq!(subject.heuristic().distances(), subject.search_heuristic.distances());
+    }
+
+    #[test]
+    fn it_emits_events_for_the_start_each_subgoal_and_the_end() {
+        let path = env::temp_dir().join(format!("supermutation-incremental-events-{}", std::process::id()));
+        let mut subject = subject();
+
+        subject.emit_events_to(Events::to_file(&path).unwrap());
+        subject.stop_at(4);
+        subject.shortest_path(Candidate::seed(&PROBLEM), |_, _, _, _, _, _| {});
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let lines = contents.lines().collect::<Vec<_>>();
+
+        std::fs::remove_file(&path).unwrap();
+        let names = |name: &str| lines.iter().filter(|l| l.contains(&format!("\"event\":\"{}\"", name))).count();
+
+        assert!(lines[0].contains("\"event\":\"start\""));
+        assert!(lines.last().unwrap().contains("\"event\":\"finished\""));
+
+        assert_eq!(names("subgoal"), 3);
+        assert!(names("f_cost") > 0);
+
+        let subgoal = lines.iter().find(|l| l.contains("\"subgoal\":4,\"distance\"")).unwrap();
+        assert!(subgoal.contains("\"heuristic\":[0,1,2,3]"));
+        assert!(subgoal.contains("\"open_set\":"));
+    }
+
+    #[test]
+    fn it_passes_the_stats_of_the_search_so_far_to_the_milestone() {
+        let mut expansions = vec![];
+
+        subject().shortest_path(Candidate::seed(&PROBLEM), |_, _, _, search, _, stats| {
+            assert_eq!(stats.expansions(), search.expansions());
+            expansions.push(stats.expansions());
+        });
+
+        assert!(expansions.windows(2).all(|w| w[0] <= w[1]));
+        assert!(expansions.last() > Some(&0));
+    }
+
+    #[test]
+    fn it_does_not_advance_the_subgoal_when_interrupted() {
+        let mut subject = subject();
+        let candidate = Candidate::seed(&PROBLEM);
+        let interrupt = subject.interrupt_flag();
+
+        let distance = subject.shortest_path(candidate, |_, subgoal, _, _, _, _| {
+            if subgoal == 10 {
+                interrupt.store(true, Relaxed);
+            }
+        });
+
+        assert_eq!(distance, None);
+        assert!(subject.interrupted());
+        assert_eq!(subject.subgoal(), 11);
+    }
+
     #[test]
     fn it_sets_all_bits_in_the_candidates_bitmap_when_a_superpermutation_is_reached() {
-        let candidate = Candidate::seed();   // 0123
-        let candidate = candidate.expand(0); // 01230
-        let candidate = candidate.expand(0); // 012301
-        let candidate = candidate.expand(0); // 0123012
-        let candidate = candidate.expand(1); // 01230120
-        let candidate = candidate.expand(0); // 012301203
-        let candidate = candidate.expand(0); // 0123012031
-        let candidate = candidate.expand(0); // 01230120312
-        let candidate = candidate.expand(0); // 012301203120
-        let candidate = candidate.expand(1); // 0123012031201
-        let candidate = candidate.expand(0); // 01230120312013
-        let candidate = candidate.expand(0); // 012301203120132
-        let candidate = candidate.expand(0); // 0123012031201320
-        let candidate = candidate.expand(0); // 01230120312013201
-        let candidate = candidate.expand(2); // 012301203120132010
-        let candidate = candidate.expand(1); // 0123012031201320102
-        let candidate = candidate.expand(0); // 01230120312013201023
-        let candidate = candidate.expand(0); // 012301203120132010231
-        let candidate = candidate.expand(0); // 0123012031201320102310
-        let candidate = candidate.expand(0); // 01230120312013201023102
-        let candidate = candidate.expand(1); // 012301203120132010231021
-        let candidate = candidate.expand(0); // 0123012031201320102310213
-        let candidate = candidate.expand(0); // 01230120312013201023102130
-        let candidate = candidate.expand(0); // 012301203120132010231021302
-        let candidate = candidate.expand(0); // 0123012031201320102310213021
-        let candidate = candidate.expand(1); // 01230120312013201023102130210
-        let candidate = candidate.expand(0); // 012301203120132010231021302103
-        let candidate = candidate.expand(0); // 0123012031201320102310213021032
-        let candidate = candidate.expand(0); // 01230120312013201023102130210321
-        let candidate = candidate.expand(0); // 012301203120132010231021302103210
+        let candidate = Candidate::seed(&PROBLEM);   // 0123
+        let candidate = candidate.expand(&PROBLEM, 0); // 01230
+        let candidate = candidate.expand(&PROBLEM, 0); // 012301
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123012
+        let candidate = candidate.expand(&PROBLEM, 1); // 01230120
+        let candidate = candidate.expand(&PROBLEM, 0); // 012301203
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123012031
+        let candidate = candidate.expand(&PROBLEM, 0); // 01230120312
+        let candidate = candidate.expand(&PROBLEM, 0); // 012301203120
+        let candidate = candidate.expand(&PROBLEM, 1); // 0123012031201
+        let candidate = candidate.expand(&PROBLEM, 0); // 01230120312013
+        let candidate = candidate.expand(&PROBLEM, 0); // 012301203120132
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123012031201320
+        let candidate = candidate.expand(&PROBLEM, 0); // 01230120312013201
+        let candidate = candidate.expand(&PROBLEM, 2); // 012301203120132010
+        let candidate = candidate.expand(&PROBLEM, 1); // 0123012031201320102
+        let candidate = candidate.expand(&PROBLEM, 0); // 01230120312013201023
+        let candidate = candidate.expand(&PROBLEM, 0); // 012301203120132010231
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123012031201320102310
+        let candidate = candidate.expand(&PROBLEM, 0); // 01230120312013201023102
+        let candidate = candidate.expand(&PROBLEM, 1); // 012301203120132010231021
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123012031201320102310213
+        let candidate = candidate.expand(&PROBLEM, 0); // 01230120312013201023102130
+        let candidate = candidate.expand(&PROBLEM, 0); // 012301203120132010231021302
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123012031201320102310213021
+        let candidate = candidate.expand(&PROBLEM, 1); // 01230120312013201023102130210
+        let candidate = candidate.expand(&PROBLEM, 0); // 012301203120132010231021302103
+        let candidate = candidate.expand(&PROBLEM, 0); // 0123012031201320102310213021032
+        let candidate = candidate.expand(&PROBLEM, 0); // 01230120312013201023102130210321
+        let candidate = candidate.expand(&PROBLEM, 0); // 012301203120132010231021302103210
 
         assert_eq!(candidate.number_of_bits(), 26);
-        assert_eq!(candidate.number_of_permutations(), 24);
+        assert_eq!(candidate.number_of_permutations(&PROBLEM), 24);
+    }
+}
+
+mod resume {
+    use super::*;
+    use crate::codec::{Encoder, Decoder};
+
+    #[test]
+    fn it_continues_from_the_next_subgoal_after_being_read_back() {
+        let mut subject = subject();
+        let candidate = Candidate::seed(&PROBLEM);
+        let interrupt = subject.interrupt_flag();
+
+        subject.shortest_path(candidate, |_, subgoal, _, _, _, _| {
+            if subgoal == 12 {
+                interrupt.store(true, Relaxed);
+            }
+        });
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let mut resumed = Subject::read_from(&mut Decoder::new(&bytes[..]), &PROBLEM).unwrap();
+        let mut milestones = vec![];
+
+        assert_eq!(resumed.subgoal(), 13);
+        assert!(!resumed.interrupted());
+
+        let distance = resumed.resume(|distance, subgoal, _, _, _, _| {
+            milestones.push((distance, subgoal));
+        });
+
+        assert_eq!(distance, Some(29));
+        assert_eq!(milestones.first(), Some(&(15, 13)));
+        assert_eq!(milestones.last(), Some(&(29, 24)));
+        assert_eq!(milestones.len(), 12);
     }
 }
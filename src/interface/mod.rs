@@ -1,18 +1,354 @@
+use std::env;
+use std::fmt::Display;
+use std::io::{self, Read};
+use std::process;
+use std::str::FromStr;
+use std::time::Duration;
+use supermutation::{Format, Representation};
+use supermutation::problem::SUPPORTED_SYMBOLS;
+
+const USAGE: &str = "\
+Usage: supermutation [COMMAND] [OPTIONS]
+
+Commands:
+  solve              Search for the shortest superpermutation (default)
+  verify [STRING]    Check a string, which is read from stdin if not given
+  table              Print the perms vs distance table from --heuristic,
+                     --resume or the published values
+  bench              Time the search to the target number of perms
+  export             Print the state of the checkpoint given by --resume
+  beam               Keep the best --width candidates at each length and print
+                     the best string and the perms at each length
+  check              Report where the heuristic overestimates the exact
+                     distance to each subgoal up to --target, or isn't
+                     consistent (three or four symbols)
+
+Options:
+  -n, --symbols N          The number of symbols, 3 to 8 (default 5)
+      --target PERMS       Stop once this many perms are reached
+      --time-limit SECS    Stop the search after this many seconds
+      --node-limit N       Stop the search after this many expansions
+      --threads N          Expand candidates on this many threads (default 1)
+      --algorithm NAME     astar, or ida to use less memory (default astar)
+      --estimate NAME      learned, tail to add the symbols each candidate needs
+                           before it's ready, analytic to raise it to the
+                           closed-form bounds on length, pattern to also look
+                           up the missing perms near the tail in a pattern
+                           database or zero for a breadth-first search
+                           (default learned)
+      --pattern-database FILE
+                           Load the pattern database, or build and save it if
+                           FILE doesn't exist (three to six symbols)
+      --closed-set KIND    exact, hashed to keep 128-bit fingerprints instead or
+                           verified to also check them (default exact)
+      --representation NAME
+                           words, or roaring to store candidates in compressed
+                           bitmaps (default words for up to six symbols)
+      --spill-to DIR       Write the open and closed sets and the parents to
+                           files in DIR once they use more than the memory budget
+      --memory-budget MB   The memory the sets share before spilling (default 1024)
+      --weight W           Find paths sooner that are at most W times longer
+      --anytime STEP       Lower the weight by STEP after each path is found
+                           until the shortest is found (default weight 2)
+      --width K            The number of candidates the beam keeps (default 100)
+      --format FORMAT      text, json or csv (default text)
+      --checkpoint FILE    Save progress every ten minutes and when stopped
+      --events FILE        Write progress to FILE as newline-delimited JSON, or
+                           to stderr if FILE is -
+      --resume FILE        Continue from a checkpoint
+      --prefix STRING      Continue from a known prefix
+      --heuristic FILE     Preload the heuristic's distances
+      --save-heuristic FILE
+                           Save the heuristic's distances after each subgoal
+      --published PERMS    Preload the published distances up to this many perms
+  -h, --help               Print this message";
+
+const FLAGS: &[&str] = &[
+    "-n", "--symbols", "--target", "--time-limit", "--node-limit", "--threads", "--algorithm", "--estimate", "--pattern-database", "--closed-set", "--representation", "--spill-to", "--memory-budget", "--weight", "--anytime", "--width", "--format",
+    "--checkpoint", "--events", "--resume", "--prefix", "--heuristic", "--save-heuristic",
+    "--published",
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Command {
+    Solve,
+    Verify,
+    Table,
+    Bench,
+    Export,
+    Beam,
+    Check,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Algorithm {
+    AStar,
+    IterativeDeepening,
+}
+
+impl FromStr for Algorithm {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "astar" => Ok(Algorithm::AStar),
+            "ida" => Ok(Algorithm::IterativeDeepening),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Estimate {
+    Learned,
+    TailAware,
+    Analytic,
+    PatternDatabase,
+    Zero,
+}
+
+impl FromStr for Estimate {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "learned" => Ok(Estimate::Learned),
+            "tail" => Ok(Estimate::TailAware),
+            "analytic" => Ok(Estimate::Analytic),
+            "pattern" => Ok(Estimate::PatternDatabase),
+            "zero" => Ok(Estimate::Zero),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClosedSetKind {
+    Exact,
+    Hashed,
+    Verified,
+}
+
+impl FromStr for ClosedSetKind {
+    type Err = ();
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "exact" => Ok(ClosedSetKind::Exact),
+            "hashed" => Ok(ClosedSetKind::Hashed),
+            "verified" => Ok(ClosedSetKind::Verified),
+            _ => Err(()),
+        }
+    }
+}
+
 pub struct Interface {
 
 }
 
 impl Interface {
+    pub fn ask_for_command() -> Command {
+        let args = Self::args();
+
+        if args.iter().any(|a| a == "-h" || a == "--help") {
+            println!("{}", USAGE);
+            process::exit(0);
+        }
+
+        Self::command(&args).unwrap_or_else(|message| Self::exit_with_usage(message))
+    }
+
     pub fn ask_for_symbols() -> usize {
-        if cfg!(feature = "four_symbols") {
-            return 4;
+        let symbols = Self::parsed(&["-n", "--symbols"]).unwrap_or(5);
+
+        if !SUPPORTED_SYMBOLS.contains(&symbols) {
+            Self::exit_with_usage("the number of symbols must be between 3 and 8");
+        }
+
+        symbols
+    }
+
+    pub fn ask_for_target() -> Option<usize> {
+        Self::parsed(&["--target"])
+    }
+
+    pub fn ask_for_time_limit() -> Option<Duration> {
+        Self::parsed(&["--time-limit"]).map(Duration::from_secs_f64)
+    }
+
+    pub fn ask_for_node_limit() -> Option<usize> {
+        Self::parsed(&["--node-limit"])
+    }
+
+    pub fn ask_for_threads() -> usize {
+        Self::parsed(&["--threads"]).unwrap_or(1)
+    }
+
+    pub fn ask_for_algorithm() -> Algorithm {
+        Self::parsed(&["--algorithm"]).unwrap_or(Algorithm::AStar)
+    }
+
+    pub fn ask_for_estimate() -> Estimate {
+        Self::parsed(&["--estimate"]).unwrap_or(Estimate::Learned)
+    }
+
+    pub fn ask_for_closed_set() -> ClosedSetKind {
+        Self::parsed(&["--closed-set"]).unwrap_or(ClosedSetKind::Exact)
+    }
+
+    pub fn ask_for_representation() -> Option<Representation> {
+        Self::parsed(&["--representation"])
+    }
+
+    pub fn ask_for_spill_directory() -> Option<String> {
+        Self::flag_value(&Self::args(), "--spill-to")
+    }
+
+    // In bytes, although it's given in megabytes.
+    pub fn ask_for_memory_budget() -> usize {
+        Self::parsed::<usize>(&["--memory-budget"]).unwrap_or(1024) * 1024 * 1024
+    }
+
+    pub fn ask_for_weight() -> Option<f64> {
+        let weight = Self::parsed(&["--weight"])?;
+
+        if weight < 1.0 {
+            Self::exit_with_usage("the weight must be at least 1");
         }
 
-        if cfg!(test) {
-            return 5;
+        Some(weight)
+    }
+
+    pub fn ask_for_anytime_step() -> Option<f64> {
+        let step = Self::parsed(&["--anytime"])?;
+
+        if step <= 0.0 {
+            Self::exit_with_usage("the anytime step must be greater than 0");
         }
 
-        5 // TODO: get user input
+        Some(step)
+    }
+
+    pub fn ask_for_width() -> usize {
+        Self::parsed(&["--width"]).unwrap_or(100)
+    }
+
+    pub fn ask_for_format() -> Format {
+        Self::parsed(&["--format"]).unwrap_or(Format::Text)
+    }
+
+    pub fn ask_for_pattern_database_path() -> Option<String> {
+        Self::flag_value(&Self::args(), "--pattern-database")
+    }
+
+    pub fn ask_for_resume_path() -> Option<String> {
+        Self::flag_value(&Self::args(), "--resume")
+    }
+
+    pub fn ask_for_events_path() -> Option<String> {
+        Self::flag_value(&Self::args(), "--events")
+    }
+
+    pub fn ask_for_checkpoint_path() -> Option<String> {
+        Self::flag_value(&Self::args(), "--checkpoint")
+    }
+
+    pub fn ask_for_prefix() -> Option<String> {
+        Self::flag_value(&Self::args(), "--prefix")
+    }
+
+    pub fn ask_for_heuristic_path() -> Option<String> {
+        Self::flag_value(&Self::args(), "--heuristic")
+    }
+
+    pub fn ask_for_save_heuristic_path() -> Option<String> {
+        Self::flag_value(&Self::args(), "--save-heuristic")
+    }
+
+    pub fn ask_for_published_perms() -> Option<usize> {
+        Self::parsed(&["--published"])
+    }
+
+    // Reads the string from stdin if it isn't given after 'verify'.
+    pub fn ask_for_string_to_verify() -> String {
+        Self::positional(&Self::args()).get(1).cloned().unwrap_or_else(|| {
+            let mut string = String::new();
+            io::stdin().read_to_string(&mut string).expect("failed to read stdin");
+            string
+        })
+    }
+
+    pub fn exit_with_error<M: Display>(message: M) -> ! {
+        eprintln!("error: {}", message);
+        process::exit(1);
+    }
+
+    fn exit_with_usage<M: Display>(message: M) -> ! {
+        eprintln!("error: {}\n\n{}", message, USAGE);
+        process::exit(2);
+    }
+
+    fn command(args: &[String]) -> Result<Command, String> {
+        let positional = Self::positional(args);
+
+        // Values of options, like - for stderr, aren't options themselves.
+        if let Some(flag) = positional.iter().find(|a| a.starts_with('-')) {
+            return Err(format!("unknown option '{}'", flag));
+        }
+
+        let command = match positional.first().map(|s| s.as_str()) {
+            None | Some("solve") => Command::Solve,
+            Some("verify") => Command::Verify,
+            Some("table") => Command::Table,
+            Some("bench") => Command::Bench,
+            Some("export") => Command::Export,
+            Some("beam") => Command::Beam,
+            Some("check") => Command::Check,
+            Some(other) => return Err(format!("unknown command '{}'", other)),
+        };
+
+        let expected = if command == Command::Verify { 2 } else { 1 };
+
+        if positional.len() > expected {
+            return Err(format!("unexpected argument '{}'", positional[expected]));
+        }
+
+        Ok(command)
+    }
+
+    fn parsed<T: FromStr>(flags: &[&str]) -> Option<T> {
+        let args = Self::args();
+        let (flag, value) = flags.iter().find_map(|f| Some((f, Self::flag_value(&args, f)?)))?;
+
+        match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(_) => Self::exit_with_usage(format!("invalid value for {}: '{}'", flag, value)),
+        }
+    }
+
+    // The arguments that aren't options or their values.
+    fn positional(args: &[String]) -> Vec<String> {
+        let mut positional = vec![];
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            if FLAGS.contains(&arg.as_str()) {
+                iter.next();
+            } else {
+                positional.push(arg.clone());
+            }
+        }
+
+        positional
+    }
+
+    fn args() -> Vec<String> {
+        env::args().skip(1).collect()
+    }
+
+    fn flag_value(args: &[String], flag: &str) -> Option<String> {
+        let index = args.iter().position(|a| a == flag)?;
+        args.get(index + 1).cloned()
     }
 }
 
@@ -6,14 +6,82 @@ mod ask_for_symbols {
     use super::*;
 
     #[test]
-    #[cfg(not(feature = "four_symbols"))]
-    fn it_returns_five_symbols_in_the_test_environment() {
+    fn it_returns_five_symbols_by_default() {
         assert_eq!(Subject::ask_for_symbols(), 5);
     }
+}
+
+mod flag_value {
+    use super::*;
+
+    fn args(slice: &[&str]) -> Vec<String> {
+        slice.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn it_returns_the_argument_after_the_flag() {
+        let args = args(&["--resume", "run.checkpoint"]);
+        assert_eq!(Subject::flag_value(&args, "--resume"), Some("run.checkpoint".to_string()));
+    }
+
+    #[test]
+    fn it_returns_none_if_the_flag_is_missing_or_has_no_value() {
+        assert_eq!(Subject::flag_value(&args(&[]), "--resume"), None);
+        assert_eq!(Subject::flag_value(&args(&["--resume"]), "--resume"), None);
+        assert_eq!(Subject::flag_value(&args(&["--checkpoint", "a"]), "--resume"), None);
+    }
+}
+
+mod command {
+    use super::*;
+
+    fn args(slice: &[&str]) -> Vec<String> {
+        slice.iter().map(|s| s.to_string()).collect()
+    }
 
     #[test]
-    #[cfg(feature = "four_symbols")]
-    fn it_returns_four_symbols_when_the_compiler_feature_is_set() {
-        assert_eq!(Subject::ask_for_symbols(), 4);
+    fn it_solves_by_default() {
+        assert_eq!(Subject::command(&args(&[])), Ok(Command::Solve));
+        assert_eq!(Subject::command(&args(&["-n", "4"])), Ok(Command::Solve));
+    }
+
+    #[test]
+    fn it_returns_the_subcommand_that_is_not_an_option_value() {
+        assert_eq!(Subject::command(&args(&["--format", "csv", "table"])), Ok(Command::Table));
+        assert_eq!(Subject::command(&args(&["verify", "012010210"])), Ok(Command::Verify));
+        assert_eq!(Subject::command(&args(&["bench", "--target", "10"])), Ok(Command::Bench));
+        assert_eq!(Subject::command(&args(&["export", "--resume", "run.checkpoint"])), Ok(Command::Export));
+        assert_eq!(Subject::command(&args(&["beam", "--width", "10"])), Ok(Command::Beam));
+        assert_eq!(Subject::command(&args(&["check", "-n", "4"])), Ok(Command::Check));
+    }
+
+    #[test]
+    fn it_returns_an_error_for_unknown_commands_options_or_arguments() {
+        assert!(Subject::command(&args(&["search"])).is_err());
+        assert!(Subject::command(&args(&["solve", "--fast"])).is_err());
+        assert!(Subject::command(&args(&["table", "extra"])).is_err());
+    }
+
+    #[test]
+    fn it_accepts_a_pattern_database_with_the_pattern_estimate() {
+        let args = args(&["solve", "--estimate", "pattern", "--pattern-database", "five.pdb"]);
+        assert_eq!(Subject::command(&args), Ok(Command::Solve));
+    }
+
+    #[test]
+    fn it_allows_option_values_that_start_with_a_dash() {
+        assert_eq!(Subject::command(&args(&["--events", "-", "solve"])), Ok(Command::Solve));
+    }
+}
+
+mod positional {
+    use super::*;
+
+    #[test]
+    fn it_skips_options_and_their_values() {
+        let args = ["-n", "3", "verify", "--format", "json", "012010210"];
+        let args = args.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(Subject::positional(&args), &["verify", "012010210"]);
     }
 }
@@ -0,0 +1,466 @@
+use crate::backend::Backend;
+use crate::candidate::Candidate;
+use crate::codec::{Encoder, Decoder};
+use crate::events::Events;
+use crate::heuristic::Heuristic;
+use crate::heuristic_fn::HeuristicFn;
+use crate::limits::{Limits, EXPANSIONS_BETWEEN_DEADLINE_CHECKS};
+use crate::path::Path;
+use crate::problem::Problem;
+use crate::stats::{Phase, SearchStats};
+use crate::symmetry::Symmetry;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::time::Instant;
+
+/// An IDA* search. It repeatedly searches depth-first up to an f-cost threshold
+/// that grows each time, so it only stores the current path rather than open
+/// and closed sets. The price is that candidates are expanded many times.
+pub struct IterativeDeepening<'a> {
+    problem: &'a Problem,
+    heuristic: Heuristic,
+    estimate: Box<dyn HeuristicFn>,
+    start: Option<Candidate>,
+    prefix: Vec<usize>,
+    labels: Option<Vec<u8>>,
+    root: Option<Candidate>,
+    goal: Option<Vec<usize>>,
+    lower_bound: Option<usize>,
+    iteration: Option<Iteration>,
+    next_threshold: Option<usize>,
+    interrupt: Arc<AtomicBool>,
+    limits: Limits,
+    stats: SearchStats,
+    events: Option<Events>,
+    weight: f64,
+    pause: Option<Instant>,
+    next_pause_check: usize,
+    paused: bool,
+}
+
+// How far the search got towards a goal with a weight. A search that paused
+// or stopped continues from the path it reached, and a later goal starts from
+// the threshold, which is also a lower bound for it.
+#[derive(Clone, Debug, PartialEq)]
+struct Iteration {
+    goal: usize,
+    weight: f64,
+    threshold: usize,
+    next_threshold: Option<usize>,
+    symbols: Vec<usize>,
+}
+
+enum Outcome {
+    Found(usize),
+    Exceeded,
+    Stopped,
+}
+
+impl<'a> IterativeDeepening<'a> {
+    pub fn new(problem: &'a Problem) -> Self {
+        let heuristic = Heuristic::seed(problem);
+
+        Self::from_parts(problem, heuristic, None, vec![], None)
+    }
+
+    fn from_parts(problem: &'a Problem, heuristic: Heuristic, start: Option<Candidate>, prefix: Vec<usize>, goal: Option<Vec<usize>>) -> Self {
+        let root = start.as_ref().map(|start| {
+            prefix.iter().fold(start.clone(), |candidate, symbol| candidate.expand(problem, *symbol))
+        });
+
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let limits = Limits::none();
+        let estimate = Box::new(heuristic.clone());
+
+        Self { problem, heuristic, estimate, start, prefix, labels: None, root, goal, lower_bound: None, iteration: None, next_threshold: None, interrupt, limits, stats: SearchStats::new(), events: None, weight: 1.0, pause: None, next_pause_check: 0, paused: false }
+    }
+
+    /// Searches from the candidate. Unlike the A* search, seeding again
+    /// replaces the previous candidate rather than adding to it.
+    pub fn seed(&mut self, candidate: Candidate) {
+        self.start = Some(candidate.clone());
+        self.prefix = vec![];
+        self.labels = None;
+        self.root = Some(candidate);
+        self.iteration = None;
+    }
+
+    pub fn seed_from_string(&mut self, string: &str) -> Result<(), String> {
+        let problem = self.problem;
+        let symbols = Candidate::canonical_symbols(problem, string)?;
+
+        let labels = Candidate::first_permutation(problem, string)?;
+
+        let seeded = Self::from_parts(problem, self.heuristic.clone(), Some(Candidate::seed(problem)), symbols, None);
+        let previous = mem::replace(self, seeded);
+
+        self.labels = Some(labels);
+
+        // Keep what was set up before seeding, such as the interrupt flag.
+        self.estimate = previous.estimate;
+        self.interrupt = previous.interrupt;
+        self.limits = previous.limits;
+        self.stats = previous.stats;
+        self.events = previous.events;
+        self.weight = previous.weight;
+        self.pause = previous.pause;
+
+        Ok(())
+    }
+
+    /// Returns the shortest distance to a candidate with the goal number of
+    /// perms, or None if it was interrupted or the goal can't be reached.
+    pub fn shortest_path(&mut self, goal: usize) -> Option<usize> {
+        self.paused = false;
+
+        let start = Instant::now();
+
+        // Only count the mappings chosen by the expansions below.
+        Symmetry::take_disambiguations();
+
+        let distance = self.deepen(goal);
+
+        self.stats.record_time(Phase::Expanding, start.elapsed());
+
+        distance
+    }
+
+    // Only the iteration is kept when the search pauses or stops so it's the
+    // path that was reached, rather than the candidates on it, that the search
+    // continues from. It's discarded if the weight changed because a weighted
+    // threshold could be past the shortest distance.
+    fn deepen(&mut self, goal: usize) -> Option<usize> {
+        let root = self.root.clone()?;
+        let search_depth = self.prefix.len();
+        let root_f_cost = search_depth + self.estimate.weighted_cost(self.problem, &root, search_depth, self.weight);
+
+        self.next_threshold = None;
+
+        let (mut threshold, mut resume) = match self.iteration.take() {
+            Some(i) if i.weight != self.weight || i.goal > goal => (root_f_cost, vec![]),
+            Some(i) if i.goal == goal && i.threshold >= root_f_cost => {
+                self.next_threshold = i.next_threshold;
+                (i.threshold, i.symbols)
+            },
+            Some(i) => (i.threshold.max(root_f_cost), vec![]),
+            None => (root_f_cost, vec![]),
+        };
+
+        let mut symbols = vec![];
+
+        loop {
+            if let Some(events) = &self.events {
+                events.emit("threshold", &[
+                    ("goal", goal.into()),
+                    ("threshold", threshold.into()),
+                    ("expansions", self.stats.expansions().into()),
+                ]);
+            }
+
+            match self.depth_first(&root, search_depth, threshold, goal, &resume, &mut symbols) {
+                Outcome::Found(distance) => {
+                    self.goal = Some(symbols);
+
+                    // Nothing tighter is known about a weighted search than the
+                    // unweighted cost of the root.
+                    self.lower_bound = match self.weight == 1.0 {
+                        true => Some(distance),
+                        false => Some((search_depth + self.estimate.cost(self.problem, &root, search_depth)).min(distance)),
+                    };
+
+                    // Each symbol adds at most one perm so the next goal is at
+                    // least one further away, unless the distance is weighted.
+                    self.iteration = match self.weight == 1.0 {
+                        true => Some(Iteration { goal: goal + 1, weight: self.weight, threshold: distance + 1, next_threshold: None, symbols: vec![] }),
+                        false => None,
+                    };
+
+                    return Some(distance);
+                },
+                Outcome::Exceeded => match self.next_threshold.take() {
+                    Some(next_threshold) => threshold = next_threshold,
+                    None => return None,
+                },
+                Outcome::Stopped => {
+                    self.iteration = Some(Iteration { goal, weight: self.weight, threshold, next_threshold: self.next_threshold, symbols });
+                    return None;
+                },
+            }
+
+            resume = vec![];
+        }
+    }
+
+    // Keeps the smallest f-cost over the threshold if the goal wasn't found so
+    // that the next iteration can search up to it. The limits and the pause are
+    // checked before each expansion, and a search that continues from a path
+    // skips the symbols before it.
+    fn depth_first(&mut self, candidate: &Candidate, search_depth: usize, threshold: usize, goal: usize, resume: &[usize], symbols: &mut Vec<usize>) -> Outcome {
+        if self.interrupt.load(Relaxed) || self.limits.exceeded(self.stats.expansions()) {
+            self.interrupt.store(true, Relaxed);
+            return Outcome::Stopped;
+        }
+
+        if self.pause_is_due() {
+            self.paused = true;
+            return Outcome::Stopped;
+        }
+
+        let problem = self.problem;
+        let first = resume.first().copied().unwrap_or(0);
+
+        self.stats.record_expansion();
+
+        // The only memory used is the stack of candidates on the current path.
+        self.stats.record_memory((symbols.len() + 1) * candidate.size_in_bytes());
+
+        for symbol in first..problem.expansions() {
+            let neighbor = candidate.expand(problem, symbol);
+            let perms = neighbor.number_of_permutations(problem);
+
+            self.stats.record_generated(1);
+            self.stats.record_disambiguations(Symmetry::take_disambiguations());
+
+            let g_cost = search_depth + 1;
+            let h_cost = self.estimate.weighted_cost(problem, &neighbor, g_cost, self.weight);
+            let f_cost = g_cost + h_cost;
+
+            if f_cost > threshold {
+                self.next_threshold = Some(self.next_threshold.map_or(f_cost, |t| t.min(f_cost)));
+                continue;
+            }
+
+            symbols.push(symbol);
+
+            if perms == goal {
+                return Outcome::Found(g_cost);
+            }
+
+            let resume = match symbol == first && !resume.is_empty() {
+                true => &resume[1..],
+                false => &[],
+            };
+
+            match self.depth_first(&neighbor, g_cost, threshold, goal, resume, symbols) {
+                Outcome::Exceeded => {},
+                outcome => return outcome,
+            }
+
+            symbols.pop();
+        }
+
+        Outcome::Exceeded
+    }
+
+    // The clock is only read every so many expansions, as it is for the limits.
+    fn pause_is_due(&mut self) -> bool {
+        let expansions = self.stats.expansions();
+
+        if self.pause.is_none() || expansions < self.next_pause_check {
+            return false;
+        }
+
+        self.next_pause_check = expansions + EXPANSIONS_BETWEEN_DEADLINE_CHECKS;
+        self.pause.is_some_and(|p| Instant::now() >= p)
+    }
+
+    pub fn path(&self) -> Option<Path<'a>> {
+        let start = self.start.clone()?;
+        let goal = self.goal.as_ref()?;
+        let symbols = self.prefix.iter().chain(goal).cloned().collect();
+
+        Some(match &self.labels {
+            Some(labels) => Path::with_labels(self.problem, start, symbols, labels.clone()),
+            None => Path::new(self.problem, start, symbols),
+        })
+    }
+
+    /// A lower bound on the shortest distance to the goal that was reached most
+    /// recently. It's the distance itself unless the search was weighted.
+    pub fn lower_bound(&self) -> Option<usize> {
+        self.lower_bound
+    }
+
+    pub fn problem(&self) -> &'a Problem {
+        self.problem
+    }
+
+    pub fn limit_to(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Multiplies the heuristic's costs by the weight.
+    pub fn weight_by(&mut self, weight: f64) {
+        self.weight = weight;
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// The number of candidates expanded, counting each re-expansion.
+    pub fn expansions(&self) -> usize {
+        self.stats.expansions()
+    }
+
+    /// There are no closed set hits or reopenings because there's no closed set.
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Writes an event each time the threshold is raised.
+    pub fn emit_events_to(&mut self, events: Events) {
+        self.events = Some(events);
+    }
+
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        self.interrupt.clone()
+    }
+
+    pub fn interrupted(&self) -> bool {
+        self.interrupt.load(Relaxed)
+    }
+
+    /// Pauses once it's past the deadline, which is checked every so many
+    /// expansions. Searching for the same goal again continues from the path
+    /// it reached.
+    pub fn pause_at(&mut self, deadline: Option<Instant>) {
+        self.pause = deadline;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The threshold is raised if the new cost of the root is higher.
+    pub fn update_heuristic(&mut self, heuristic: &Heuristic) {
+        self.heuristic = heuristic.clone();
+        self.estimate.learn(heuristic);
+    }
+
+    /// Estimates costs with this rather than the learned heuristic, which it
+    /// learns from as the search goes. It isn't saved in checkpoints.
+    pub fn use_heuristic_fn(&mut self, estimate: Box<dyn HeuristicFn>) {
+        self.estimate = estimate;
+        self.estimate.learn(&self.heuristic);
+    }
+
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        self.heuristic.write_to(encoder)?;
+
+        match &self.start {
+            Some(start) => {
+                encoder.write_usize(1)?;
+                start.write_to(encoder)?;
+            },
+            None => encoder.write_usize(0)?,
+        }
+
+        encoder.write_usizes(&self.prefix)?;
+
+        match &self.labels {
+            Some(labels) => {
+                encoder.write_usize(1)?;
+                encoder.write_bytes(labels)?;
+            },
+            None => encoder.write_usize(0)?,
+        }
+
+        match &self.goal {
+            Some(goal) => {
+                encoder.write_usize(1)?;
+                encoder.write_usizes(goal)?;
+            },
+            None => encoder.write_usize(0)?,
+        }
+
+        match &self.iteration {
+            Some(iteration) => {
+                encoder.write_usize(1)?;
+                encoder.write_usize(iteration.goal)?;
+                encoder.write_f64(iteration.weight)?;
+                encoder.write_usize(iteration.threshold)?;
+                encoder.write_usizes(iteration.next_threshold.as_slice())?;
+                encoder.write_usizes(&iteration.symbols)
+            },
+            None => encoder.write_usize(0),
+        }
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>, problem: &'a Problem) -> io::Result<Self> {
+        let heuristic = Heuristic::read_from(decoder)?;
+
+        let start = match decoder.read_usize()? {
+            0 => None,
+            _ => Some(Candidate::read_from(decoder)?),
+        };
+
+        let prefix = decoder.read_usizes()?;
+
+        let labels = match decoder.read_usize()? {
+            0 => None,
+            _ => Some(Path::read_labels(decoder, problem)?),
+        };
+
+        let goal = match decoder.read_usize()? {
+            0 => None,
+            _ => Some(decoder.read_usizes()?),
+        };
+
+        let iteration = match decoder.read_usize()? {
+            0 => None,
+            _ => Some(Iteration {
+                goal: decoder.read_usize()?,
+                weight: decoder.read_f64()?,
+                threshold: decoder.read_usize()?,
+                next_threshold: decoder.read_usizes()?.first().copied(),
+                symbols: decoder.read_usizes()?,
+            }),
+        };
+
+        let mut search = Self::from_parts(problem, heuristic, start, prefix, goal);
+        search.labels = labels;
+        search.iteration = iteration;
+
+        Ok(search)
+    }
+}
+
+impl<'a> Backend<'a> for IterativeDeepening<'a> {
+    const NAME: &'static str = "ida";
+
+    fn problem(&self) -> &'a Problem { self.problem() }
+    fn seed(&mut self, candidate: Candidate) { self.seed(candidate) }
+    fn seed_from_string(&mut self, string: &str) -> Result<(), String> { self.seed_from_string(string) }
+    fn shortest_path(&mut self, goal: usize) -> Option<usize> { self.shortest_path(goal) }
+    fn path(&self) -> Option<Path<'a>> { self.path() }
+    fn lower_bound(&self) -> Option<usize> { self.lower_bound() }
+    fn update_heuristic(&mut self, heuristic: &Heuristic) { self.update_heuristic(heuristic) }
+    fn limit_to(&mut self, limits: Limits) { self.limit_to(limits) }
+    fn weight_by(&mut self, weight: f64) { self.weight_by(weight) }
+    fn weight(&self) -> f64 { self.weight() }
+    fn expansions(&self) -> usize { self.expansions() }
+    fn stats(&self) -> &SearchStats { self.stats() }
+    fn open_set_len(&self) -> usize { 0 }
+    fn closed_set_len(&self) -> usize { 0 }
+    fn emit_events_to(&mut self, events: Events) { self.emit_events_to(events) }
+    fn use_heuristic_fn(&mut self, estimate: Box<dyn HeuristicFn>) { self.use_heuristic_fn(estimate) }
+    fn interrupt_flag(&self) -> Arc<AtomicBool> { self.interrupt_flag() }
+    fn interrupted(&self) -> bool { self.interrupted() }
+    fn error(&self) -> Option<&io::Error> { None }
+    fn pause_at(&mut self, deadline: Option<Instant>) { self.pause_at(deadline) }
+    fn paused(&self) -> bool { self.paused() }
+
+    fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        self.write_to(encoder)
+    }
+
+    fn read_from<R: Read>(decoder: &mut Decoder<R>, problem: &'a Problem) -> io::Result<Self> {
+        Self::read_from(decoder, problem)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,349 @@
+use super::*;
+use crate::closed_set::ClosedSet;
+use crate::open_set::OpenSet;
+use crate::search::Search;
+
+type Subject<'a> = IterativeDeepening<'a>;
+
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
+fn subject(candidate: &Candidate) -> Subject<'static> {
+    let mut subject = Subject::new(&PROBLEM);
+
+    subject.update_heuristic(&simplified_heuristic());
+    subject.seed(candidate.clone());
+    subject
+}
+
+fn threshold(subject: &Subject) -> Option<(usize, usize)> {
+    subject.iteration.as_ref().map(|i| (i.goal, i.threshold))
+}
+
+fn simplified_heuristic() -> Heuristic {
+    Heuristic::new(4, vec![0], vec![2, 1, 0, 0, 0, 0, 0], vec![0, 0, 0, 0, 0, 0, 0])
+}
+
+mod seed {
+    use super::*;
+
+    #[test]
+    fn it_replaces_the_candidate_to_search_from() {
+        let seed = Candidate::seed(&PROBLEM);
+        let other = seed.expand(&PROBLEM, 1);
+
+        let mut subject = subject(&seed);
+        subject.seed(other.clone());
+
+        assert_eq!(subject.root, Some(other));
+    }
+}
+
+mod seed_from_string {
+    use super::*;
+
+    #[test]
+    fn it_finds_paths_that_start_with_the_string() {
+        let mut subject = Subject::new(&PROBLEM);
+        subject.update_heuristic(&simplified_heuristic());
+        subject.seed_from_string("01234021").unwrap();
+
+        let distance = subject.shortest_path(4).unwrap();
+        let string = subject.path().unwrap().to_string();
+
+        assert_eq!(distance, 4);
+        assert_eq!(string, "012340213");
+    }
+
+    #[test]
+    fn it_keeps_the_symbols_of_a_string_that_does_not_start_with_0123() {
+        let mut subject = Subject::new(&PROBLEM);
+        subject.update_heuristic(&simplified_heuristic());
+        subject.seed_from_string("43210423").unwrap();
+
+        subject.shortest_path(4).unwrap();
+
+        assert_eq!(subject.path().unwrap().to_string(), "432104231");
+    }
+
+    #[test]
+    fn it_returns_an_error_for_an_invalid_string() {
+        let mut subject = Subject::new(&PROBLEM);
+
+        assert!(subject.seed_from_string("0120").is_err());
+        assert_eq!(subject.root, None);
+    }
+}
+
+mod shortest_path {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_length_of_the_shortest_path_to_the_goal_number_of_permutations() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        let goal = start.number_of_permutations(&PROBLEM);
+
+        assert_eq!(subject.shortest_path(goal + 1), Some(1));
+        assert_eq!(subject.shortest_path(goal + 2), Some(2));
+        assert_eq!(subject.shortest_path(goal + 3), Some(3));
+        assert_eq!(subject.shortest_path(goal + 4), Some(4));
+        assert_eq!(subject.shortest_path(goal + 5), Some(6));
+    }
+
+    #[test]
+    fn it_finds_the_same_distances_as_the_a_star_search() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        let mut search = Search::new(&PROBLEM, OpenSet::new(), ClosedSet::new());
+        search.update_heuristic(&simplified_heuristic());
+        search.seed(start.clone());
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        for subgoal in (goal - 4)..=goal {
+            assert_eq!(subject.shortest_path(subgoal), search.shortest_path(subgoal));
+        }
+    }
+
+    #[test]
+    fn it_starts_the_next_goal_from_a_threshold_past_the_previous_distance() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 4;
+
+        let distance = subject.shortest_path(goal).unwrap();
+        assert_eq!(threshold(subject), Some((goal + 1, distance + 1)));
+
+        subject.weight_by(2.0);
+        subject.shortest_path(goal + 1);
+
+        assert_eq!(threshold(subject), None);
+    }
+
+    #[test]
+    fn it_returns_none_if_it_has_not_been_seeded() {
+        let mut subject = Subject::new(&PROBLEM);
+
+        assert_eq!(subject.shortest_path(2), None);
+    }
+}
+
+mod stats {
+    use super::*;
+
+    #[test]
+    fn it_counts_expansions_and_the_memory_of_the_deepest_path_searched() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        let distance = subject.shortest_path(start.number_of_permutations(&PROBLEM) + 5).unwrap();
+        let stats = subject.stats();
+
+        assert_eq!(stats.expansions(), subject.expansions());
+        // It stops generating neighbors once one reaches the goal.
+        assert!(stats.generated() <= stats.expansions() * PROBLEM.expansions());
+        assert_eq!(stats.closed_set_hits(), 0);
+        // Earlier iterations can search past the goal's depth.
+        assert!(stats.peak_memory() >= distance * start.size_in_bytes());
+    }
+}
+
+mod use_heuristic_fn {
+    use super::*;
+    use crate::heuristic_fn::Zero;
+
+    #[test]
+    fn it_finds_the_same_shortest_paths_with_another_admissible_heuristic() {
+        let start = Candidate::seed(&PROBLEM);
+        let learned = &mut subject(&start);
+        let zero = &mut subject(&start);
+
+        zero.use_heuristic_fn(Box::new(Zero));
+
+        let goal = start.number_of_permutations(&PROBLEM) + 4;
+
+        for subgoal in (goal - 3)..=goal {
+            assert_eq!(zero.shortest_path(subgoal), learned.shortest_path(subgoal));
+        }
+
+        assert!(zero.expansions() > learned.expansions());
+    }
+}
+
+mod path {
+    use super::*;
+
+    #[test]
+    fn it_returns_none_if_no_goal_has_been_reached() {
+        let start = Candidate::seed(&PROBLEM);
+
+        assert_eq!(subject(&start).path(), None);
+    }
+
+    #[test]
+    fn it_returns_a_path_with_the_length_of_the_shortest_path() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+        let distance = subject.shortest_path(goal).unwrap();
+
+        assert_eq!(subject.path().unwrap().len(), distance);
+        assert_eq!(subject.path().unwrap().to_string(), "01234012304");
+    }
+}
+
+mod limit_to {
+    use super::*;
+
+    #[test]
+    fn it_stops_the_search_once_the_limits_are_exceeded() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        subject.limit_to(Limits::new(Some(3), None));
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        assert_eq!(subject.shortest_path(goal), None);
+        assert!(subject.interrupted());
+        assert_eq!(subject.expansions(), 3);
+    }
+}
+
+mod interrupt_flag {
+    use super::*;
+
+    #[test]
+    fn it_can_continue_the_search_once_cleared() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.interrupt_flag().store(true, Relaxed);
+        assert_eq!(subject.shortest_path(goal), None);
+
+        subject.interrupt_flag().store(false, Relaxed);
+        assert_eq!(subject.shortest_path(goal), Some(6));
+    }
+}
+
+mod pause_at {
+    use super::*;
+    use crate::heuristic_fn::Zero;
+
+    #[test]
+    fn it_pauses_and_continues_from_the_same_threshold() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.pause_at(Some(Instant::now()));
+
+        assert!(subject.shortest_path(goal).is_none());
+        assert!(subject.paused());
+        assert!(!subject.interrupted());
+
+        let paused_at = threshold(subject);
+        subject.pause_at(None);
+
+        assert_eq!(paused_at.map(|(g, _)| g), Some(goal));
+        assert_eq!(subject.shortest_path(goal), Some(6));
+        assert_eq!(threshold(subject), Some((goal + 1, 7)));
+    }
+
+    #[test]
+    fn it_pauses_within_an_iteration_and_continues_from_the_path_it_reached() {
+        let start = Candidate::seed(&PROBLEM);
+        let goal = start.number_of_permutations(&PROBLEM) + 6;
+
+        let mut expected = subject(&start);
+        expected.use_heuristic_fn(Box::new(Zero));
+        let distance = expected.shortest_path(goal);
+
+        let subject = &mut subject(&start);
+        subject.use_heuristic_fn(Box::new(Zero));
+        let mut pauses = 0;
+
+        let result = loop {
+            subject.pause_at(Some(Instant::now()));
+
+            let result = subject.shortest_path(goal);
+
+            if !subject.paused() {
+                break result;
+            }
+
+            pauses += 1;
+        };
+
+        assert_eq!(result, distance);
+        assert_eq!(subject.path(), expected.path());
+
+        // Each pause only expands the candidates on the path to it again.
+        assert!(pauses > 1);
+        assert!(subject.expansions() <= expected.expansions() + pauses * (goal + 1));
+    }
+
+    #[test]
+    fn it_discards_the_threshold_of_a_search_with_a_different_weight() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.weight_by(3.0);
+        subject.pause_at(Some(Instant::now()));
+        subject.shortest_path(goal);
+
+        subject.weight_by(1.0);
+        subject.pause_at(None);
+
+        assert_eq!(subject.shortest_path(goal), Some(6));
+    }
+}
+
+mod write_to {
+    use super::*;
+
+    #[test]
+    fn it_can_be_read_back_and_continue_the_search() {
+        let mut subject = Subject::new(&PROBLEM);
+        subject.update_heuristic(&simplified_heuristic());
+        subject.seed_from_string("01234021").unwrap();
+        subject.shortest_path(3);
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let mut result = Subject::read_from(&mut Decoder::new(&bytes[..]), &PROBLEM).unwrap();
+
+        assert_eq!(result.heuristic, subject.heuristic);
+        assert_eq!(result.root, subject.root);
+        assert_eq!(result.path(), subject.path());
+
+        assert_eq!(result.shortest_path(4), subject.shortest_path(4));
+        assert_eq!(result.path(), subject.path());
+    }
+
+    #[test]
+    fn it_keeps_the_threshold_of_a_paused_search() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.pause_at(Some(Instant::now()));
+        subject.shortest_path(goal);
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let mut result = Subject::read_from(&mut Decoder::new(&bytes[..]), &PROBLEM).unwrap();
+
+        assert_eq!(result.iteration, subject.iteration);
+        assert_eq!(result.shortest_path(goal), Some(6));
+    }
+}
@@ -0,0 +1,73 @@
+//! Searches for the shortest superpermutations with an incremental A* search
+//! over canonical candidates. See the README for an explanation of the ideas.
+//!
+//! ```no_run
+//! use supermutation::{Candidate, ClosedSet, Heuristic, Incremental, OpenSet, Problem, Search};
+//!
+//! let problem = Problem::new(4);
+//! let search = Search::new(&problem, OpenSet::new(), ClosedSet::new());
+//! let mut incremental = Incremental::new(Heuristic::seed(&problem), search);
+//!
+//! incremental.shortest_path(Candidate::seed(&problem), |distance, subgoal, string, _, _, _| {
+//!     println!("The shortest path to {} is {}: {}", subgoal, distance, string);
+//! });
+//! ```
+
+#[cfg(test)]
+#[macro_use]
+extern crate lazy_static;
+
+pub mod admissibility;
+pub mod analytic_bound;
+pub mod backend;
+pub mod beam_search;
+pub mod bitset;
+pub mod candidate;
+pub mod checkpoint;
+pub mod closed_set;
+pub mod codec;
+pub mod events;
+pub mod format;
+pub mod heuristic;
+pub mod heuristic_fn;
+pub mod incremental;
+pub mod iterative_deepening;
+pub mod limits;
+pub mod open_set;
+pub mod parents;
+pub mod path;
+pub mod pattern_database;
+pub mod problem;
+pub mod published;
+pub mod search;
+mod shard;
+mod spill;
+pub mod stats;
+pub mod symmetry;
+mod utility;
+pub mod verification;
+
+pub use admissibility::Admissibility;
+pub use analytic_bound::AnalyticBound;
+pub use backend::Backend;
+pub use beam_search::BeamSearch;
+pub use candidate::Candidate;
+pub use checkpoint::Checkpoint;
+pub use closed_set::ClosedSet;
+pub use events::Events;
+pub use format::Format;
+pub use heuristic::Heuristic;
+pub use heuristic_fn::HeuristicFn;
+pub use incremental::Incremental;
+pub use iterative_deepening::IterativeDeepening;
+pub use limits::Limits;
+pub use open_set::OpenSet;
+pub use parents::Parents;
+pub use path::Path;
+pub use pattern_database::PatternDatabase;
+pub use problem::{Problem, Representation};
+pub use published::Published;
+pub use search::Search;
+pub use stats::{Phase, SearchStats};
+pub use symmetry::Symmetry;
+pub use verification::Verification;
@@ -0,0 +1,42 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+// Reading the clock costs more than expanding a candidate in some searches.
+pub(crate) const EXPANSIONS_BETWEEN_DEADLINE_CHECKS: usize = 1024;
+
+/// Stops a search after a number of expansions or an amount of time. The time
+/// is only checked every so many expansions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Limits {
+    expansions: Option<usize>,
+    deadline: Option<Instant>,
+    next_deadline_check: Cell<usize>,
+}
+
+impl Limits {
+    pub fn new(expansions: Option<usize>, duration: Option<Duration>) -> Self {
+        let deadline = duration.map(|d| Instant::now() + d);
+
+        Self { expansions, deadline, next_deadline_check: Cell::new(0) }
+    }
+
+    pub fn none() -> Self {
+        Self::new(None, None)
+    }
+
+    pub fn exceeded(&self, expansions: usize) -> bool {
+        if self.expansions.is_some_and(|e| expansions >= e) {
+            return true;
+        }
+
+        if expansions < self.next_deadline_check.get() {
+            return false;
+        }
+
+        self.next_deadline_check.set(expansions.saturating_add(EXPANSIONS_BETWEEN_DEADLINE_CHECKS));
+        self.deadline.is_some_and(|d| Instant::now() >= d)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,37 @@
+use super::*;
+
+type Subject = Limits;
+
+mod exceeded {
+    use super::*;
+
+    #[test]
+    fn it_returns_false_if_there_are_no_limits() {
+        assert!(!Subject::none().exceeded(usize::MAX));
+    }
+
+    #[test]
+    fn it_returns_true_once_the_number_of_expansions_is_reached() {
+        let subject = Subject::new(Some(10), None);
+
+        assert!(!subject.exceeded(9));
+        assert!(subject.exceeded(10));
+    }
+
+    #[test]
+    fn it_returns_true_once_the_deadline_has_passed() {
+        assert!(!Subject::new(None, Some(Duration::from_secs(60))).exceeded(0));
+        assert!(Subject::new(None, Some(Duration::from_secs(0))).exceeded(0));
+    }
+
+    #[test]
+    fn it_only_checks_the_deadline_every_so_many_expansions() {
+        let subject = Subject::new(None, Some(Duration::from_millis(10)));
+
+        assert!(!subject.exceeded(0));
+        std::thread::sleep(Duration::from_millis(20));
+
+        assert!(!subject.exceeded(EXPANSIONS_BETWEEN_DEADLINE_CHECKS - 1));
+        assert!(subject.exceeded(EXPANSIONS_BETWEEN_DEADLINE_CHECKS));
+    }
+}
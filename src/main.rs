@@ -1,46 +1,508 @@
-#![feature(uniform_paths)]
-#![feature(drain_filter)]
+mod interface;
 
-#[macro_use]
-extern crate lazy_static;
+use interface::{Algorithm, ClosedSetKind, Command, Estimate, Interface};
+use std::io;
+use std::sync::atomic::Ordering::Relaxed;
+use std::time::{Duration, Instant};
+use supermutation::format::{Format, Value};
+use supermutation::heuristic_fn::{TailAware, Zero};
+use supermutation::pattern_database;
+use supermutation::{Admissibility, AnalyticBound, Backend, BeamSearch, Candidate, Checkpoint, ClosedSet, Events, Heuristic, HeuristicFn, Incremental, IterativeDeepening, Limits, OpenSet, Parents, PatternDatabase, Problem, Representation, Search, SearchStats, Verification};
 
-mod candidate;
-mod closed_set;
-mod heuristic;
-mod incremental;
-mod interface;
-mod open_set;
-mod search;
-mod symmetry;
-mod utility;
-
-use candidate::Candidate;
-use closed_set::ClosedSet;
-use heuristic::Heuristic;
-use incremental::Incremental;
-use interface::Interface;
-use open_set::OpenSet;
-use search::Search;
-use symmetry::Symmetry;
-use utility::Utility;
-
-lazy_static! {
-    static ref SYMBOLS: usize = Interface::ask_for_symbols();
-    static ref EXPANSIONS: usize = *SYMBOLS - 1;
-    static ref FACTORIAL: usize = Utility::factorial(*SYMBOLS);
-    static ref CAPACITY: u32 = (*FACTORIAL + *SYMBOLS - 2) as u32;
-    static ref SYMMETRY: Symmetry = Symmetry::precompute(*SYMBOLS);
-}
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(10 * 60);
 
 fn main() {
-    let open_set = OpenSet::new();
-    let closed_set = ClosedSet::new();
-    let search = Search::new(open_set, closed_set);
-    let heuristic = Heuristic::seed();
-    let candidate = Candidate::seed();
-    let mut incremental = Incremental::new(heuristic, search);
-
-    incremental.shortest_path(candidate, |distance, subgoal, _search, _heuristic| {
-        println!("The shortest path to {} is {}", subgoal, distance);
+    let format = Interface::ask_for_format();
+
+    match Interface::ask_for_command() {
+        Command::Solve => solve(format),
+        Command::Verify => verify(format),
+        Command::Table => table(format),
+        Command::Bench => bench(format),
+        Command::Export => export(format),
+        Command::Beam => beam(format),
+        Command::Check => check(format),
+    }
+}
+
+fn solve(format: Format) {
+    let problem = problem();
+
+    match Interface::ask_for_algorithm() {
+        Algorithm::AStar => solve_with(format, a_star(&problem)),
+        Algorithm::IterativeDeepening => solve_with(format, incremental(&problem, IterativeDeepening::new)),
+    }
+}
+
+fn solve_with<'a, B: Backend<'a>>(format: Format, mut incremental: Incremental<'a, B>) {
+    let save_path = Interface::ask_for_save_heuristic_path();
+
+    let weighted = Interface::ask_for_weight().is_some() || Interface::ask_for_anytime_step().is_some();
+
+    if format != Format::Text {
+        match weighted {
+            true => print_header(format, &["subgoal", "distance", "string", "bound"]),
+            false => print_header(format, &["subgoal", "distance", "string"]),
+        }
+    }
+
+    let milestone = |distance: usize, subgoal: usize, string: &str, search: &B, heuristic: &Heuristic, _: &SearchStats| {
+        let bound = suboptimality_bound(distance, search);
+
+        match format {
+            Format::Text if bound > 1.0 => {
+                println!("A path to {} within {:.3}x of the shortest is {}: {}", subgoal, bound, distance, string)
+            },
+            Format::Text => println!("The shortest path to {} is {}: {}", subgoal, distance, string),
+            _ => {
+                let mut fields = vec![
+                    ("subgoal", subgoal.into()),
+                    ("distance", distance.into()),
+                    ("string", string.into()),
+                ];
+
+                if weighted {
+                    fields.push(("bound", bound.into()));
+                }
+
+                println!("{}", format.record(&fields));
+            },
+        }
+
+        if let Some(path) = &save_path {
+            heuristic.save_distances(path).unwrap_or_else(|e| Interface::exit_with_error(e));
+        }
+    };
+
+    run(&mut incremental, milestone);
+
+    print_stats(format, incremental.search().stats());
+    report_if_stopped(&incremental);
+}
+
+// How many times longer than the shortest the path can be. The weight bounds
+// it, as does the distance over a lower bound on the shortest distance.
+fn suboptimality_bound<'a, B: Backend<'a>>(distance: usize, search: &B) -> f64 {
+    let lower_bound = search.lower_bound().unwrap_or(0).max(1);
+
+    search.weight().min(distance as f64 / lower_bound as f64)
+}
+
+fn verify(format: Format) {
+    let problem = problem();
+    let string = Interface::ask_for_string_to_verify();
+    let verification = Verification::new(&problem, &string).unwrap_or_else(|e| Interface::exit_with_error(e));
+
+    match format {
+        Format::Text => println!("{}", verification),
+        Format::Json => println!("{}", format.record(&[
+            ("symbols", verification.symbols().into()),
+            ("length", verification.len().into()),
+            ("permutations", verification.number_of_permutations().into()),
+            ("wasted", verification.wasted().into()),
+            ("superpermutation", verification.is_superpermutation().into()),
+            ("first_appearances", verification.first_appearances().to_vec().into()),
+        ])),
+        Format::Csv => {
+            print_header(format, &["perms", "distance"]);
+
+            for (perms, distance) in verification.profile() {
+                println!("{}", format.record(&[("perms", perms.into()), ("distance", distance.into())]));
+            }
+        },
+    }
+}
+
+// The text format can be loaded back in with --heuristic.
+fn table(format: Format) {
+    let problem = problem();
+
+    let heuristic = match Interface::ask_for_resume_path() {
+        Some(path) => {
+            match Interface::ask_for_algorithm() {
+                Algorithm::AStar => read_checkpoint::<Search>(&path, &problem).heuristic().clone(),
+                Algorithm::IterativeDeepening => read_checkpoint::<IterativeDeepening>(&path, &problem).heuristic().clone(),
+            }
+        },
+        None => match Interface::ask_for_heuristic_path() {
+            Some(_) => preloaded_heuristic(&problem),
+            None => {
+                let perms = Interface::ask_for_published_perms().unwrap_or(problem.factorial());
+                Heuristic::published(&problem, perms).unwrap_or_else(|| {
+                    Interface::exit_with_error("there is no published table for this many symbols")
+                })
+            },
+        },
+    };
+
+    print_header(format, &["perms", "distance"]);
+
+    for (perms_added, distance) in heuristic.distances().iter().enumerate() {
+        println!("{}", format.record(&[("perms", (perms_added + 1).into()), ("distance", (*distance).into())]));
+    }
+}
+
+fn bench(format: Format) {
+    let problem = problem();
+
+    match Interface::ask_for_algorithm() {
+        Algorithm::AStar => bench_with(format, a_star(&problem), |search| {
+            Some(search.bytes_per_candidate())
+        }),
+        Algorithm::IterativeDeepening => bench_with(format, incremental(&problem, IterativeDeepening::new), |_| None),
+    }
+}
+
+// Only the A* search has a closed set whose memory per candidate is reported.
+fn bench_with<'a, B: Backend<'a>>(format: Format, mut incremental: Incremental<'a, B>, bytes_per_candidate: fn(&B) -> Option<f64>) {
+    let start = Instant::now();
+
+    let distance = run(&mut incremental, |_, _, _, _, _, _| {});
+
+    let seconds = start.elapsed().as_secs_f64();
+    let expansions = incremental.search().expansions();
+    let per_second = expansions as f64 / seconds;
+    let reached = incremental.subgoal() - 1;
+    let bytes = bytes_per_candidate(incremental.search());
+
+    match format {
+        Format::Text => {
+            println!(
+                "Reached {} perms in {:.3}s with {} expansions ({:.0} per second)",
+                reached, seconds, expansions, per_second,
+            );
+
+            if let Some(bytes) = bytes {
+                println!("The closed set and parents used {:.1} bytes per closed candidate", bytes);
+            }
+        },
+        _ => {
+            let names = ["perms", "distance", "seconds", "expansions", "expansions_per_second", "bytes_per_candidate"];
+            print_header(format, &names);
+
+            println!("{}", format.record(&[
+                (names[0], reached.into()),
+                (names[1], distance.map_or(Value::Text(String::new()), Value::Number)),
+                (names[2], seconds.into()),
+                (names[3], expansions.into()),
+                (names[4], per_second.into()),
+                (names[5], bytes.map_or(Value::Text(String::new()), Value::from)),
+            ]));
+        },
+    }
+
+    print_stats(format, incremental.search().stats());
+    report_if_stopped(&incremental);
+}
+
+fn export(format: Format) {
+    let path = Interface::ask_for_resume_path().unwrap_or_else(|| {
+        Interface::exit_with_error("export needs a checkpoint given by --resume")
     });
+
+    let problem = problem();
+
+    match Interface::ask_for_algorithm() {
+        Algorithm::AStar => export_from(format, read_checkpoint::<Search>(&path, &problem)),
+        Algorithm::IterativeDeepening => export_from(format, read_checkpoint::<IterativeDeepening>(&path, &problem)),
+    }
+}
+
+fn export_from<'a, B: Backend<'a>>(format: Format, incremental: Incremental<'a, B>) {
+    let problem = incremental.problem();
+    let string = incremental.search().path().map(|p| p.to_string()).unwrap_or_default();
+    let distances = incremental.heuristic().distances().to_vec();
+
+    match format {
+        Format::Text => {
+            println!("Symbols: {}", problem.symbols());
+            println!("Subgoal: {}", incremental.subgoal());
+            println!("String: {}", string);
+            println!("Distances: {}", format.record(&[("distances", distances.into())]));
+        },
+        _ => {
+            let names = ["symbols", "subgoal", "string", "distances"];
+            print_header(format, &names);
+
+            println!("{}", format.record(&[
+                (names[0], problem.symbols().into()),
+                (names[1], incremental.subgoal().into()),
+                (names[2], string.into()),
+                (names[3], distances.into()),
+            ]));
+        },
+    }
+}
+
+fn beam(format: Format) {
+    let problem = Problem::new(Interface::ask_for_symbols());
+    let mut beam_search = BeamSearch::new(&problem, Interface::ask_for_width());
+
+    if let Some(perms) = Interface::ask_for_target() {
+        beam_search.stop_at(perms);
+    }
+
+    beam_search.limit_to(Limits::new(Interface::ask_for_node_limit(), Interface::ask_for_time_limit()));
+
+    let string = beam_search.search(Candidate::seed(&problem)).to_string();
+    let verification = Verification::new(&problem, &string).unwrap_or_else(|e| Interface::exit_with_error(e));
+    let lengths = (0..beam_search.curve().len()).map(|depth| depth + problem.symbols());
+
+    match format {
+        Format::Json => println!("{}", format.record(&[
+            ("string", string.as_str().into()),
+            ("length", verification.len().into()),
+            ("permutations", verification.number_of_permutations().into()),
+            ("curve", beam_search.curve().to_vec().into()),
+        ])),
+        _ => {
+            if format == Format::Text {
+                println!("String: {}", string);
+                println!("Length: {}", verification.len());
+                println!("Permutations: {} of {}", verification.number_of_permutations(), problem.factorial());
+                println!();
+            }
+
+            print_header(format, &["length", "perms"]);
+
+            for (length, perms) in lengths.zip(beam_search.curve()) {
+                println!("{}", format.record(&[("length", length.into()), ("perms", (*perms).into())]));
+            }
+        },
+    }
+}
+
+// Every candidate is kept in memory, which is millions for four symbols.
+fn check(format: Format) {
+    let problem = Problem::new(Interface::ask_for_symbols());
+
+    if problem.symbols() > 4 {
+        Interface::exit_with_error("the heuristic can only be checked for up to four symbols");
+    }
+
+    let target = Interface::ask_for_target().unwrap_or(problem.factorial());
+    let admissibility = Admissibility::new(&problem, target);
+    let violations = admissibility.check(&mut estimate(&problem));
+
+    if format != Format::Text {
+        print_header(format, &["goal", "kind", "perms", "depth", "cost", "bound", "candidates"]);
+    }
+
+    for violation in &violations {
+        match format {
+            Format::Text => println!("{}", violation),
+            _ => println!("{}", format.record(&[
+                ("goal", violation.goal().into()),
+                ("kind", violation.kind().name().into()),
+                ("perms", violation.perms().into()),
+                ("depth", violation.depth().into()),
+                ("cost", violation.cost().into()),
+                ("bound", violation.bound().into()),
+                ("candidates", violation.candidates().into()),
+            ])),
+        }
+    }
+
+    let summary = format!("Checked {} candidates: {} violations", admissibility.len(), violations.len());
+
+    match format {
+        Format::Text => println!("{}", summary),
+        _ => eprintln!("{}", summary),
+    }
+}
+
+// A checkpoint knows how many symbols it's for so use that when resuming.
+fn problem() -> Problem {
+    let symbols = match Interface::ask_for_resume_path() {
+        Some(path) => Checkpoint::read_symbols(path).unwrap_or_else(|e| Interface::exit_with_error(e)),
+        None => Interface::ask_for_symbols(),
+    };
+
+    match Interface::ask_for_representation() {
+        Some(Representation::Words) if symbols > 6 => {
+            Interface::exit_with_error("candidates only fit in words for up to six symbols")
+        },
+        Some(representation) => Problem::with_representation(symbols, representation),
+        None => Problem::new(symbols),
+    }
+}
+
+fn a_star(problem: &Problem) -> Incremental<'_> {
+    let mut incremental = incremental(problem, |problem| {
+        let (open_set, closed_set, parents) = match Interface::ask_for_spill_directory() {
+            Some(directory) => on_disk(&directory),
+            None => (OpenSet::new(), in_memory_closed_set(), Parents::new()),
+        };
+
+        Search::with_parents(problem, open_set, closed_set, parents)
+    });
+
+    incremental.use_threads(Interface::ask_for_threads());
+    incremental
+}
+
+fn in_memory_closed_set() -> ClosedSet {
+    match Interface::ask_for_closed_set() {
+        ClosedSetKind::Exact => ClosedSet::new(),
+        ClosedSetKind::Hashed => ClosedSet::hashed(),
+        ClosedSetKind::Verified => ClosedSet::verified(),
+    }
+}
+
+// The open set, closed set and parents get a third of the memory budget each.
+fn on_disk(directory: &str) -> (OpenSet, ClosedSet, Parents) {
+    if Interface::ask_for_closed_set() != ClosedSetKind::Exact {
+        Interface::exit_with_error("--closed-set can't be used with --spill-to");
+    }
+
+    let budget = Interface::ask_for_memory_budget() / 3;
+
+    let open_set = OpenSet::on_disk(directory, budget).unwrap_or_else(|e| Interface::exit_with_error(e));
+    let closed_set = ClosedSet::on_disk(directory, budget).unwrap_or_else(|e| Interface::exit_with_error(e));
+    let parents = Parents::on_disk(directory, budget).unwrap_or_else(|e| Interface::exit_with_error(e));
+
+    (open_set, closed_set, parents)
+}
+
+fn incremental<'a, B: Backend<'a>>(problem: &'a Problem, new_search: fn(&'a Problem) -> B) -> Incremental<'a, B> {
+    let mut incremental = match Interface::ask_for_resume_path() {
+        Some(path) => read_checkpoint(&path, problem),
+        None => Incremental::new(preloaded_heuristic(problem), new_search(problem)),
+    };
+
+    if let Some(perms) = Interface::ask_for_target() {
+        incremental.stop_at(perms);
+    }
+
+    // This isn't saved in checkpoints so it's set again when resuming.
+    incremental.use_heuristic_fn(estimate(problem));
+
+    let node_limit = Interface::ask_for_node_limit();
+    let time_limit = Interface::ask_for_time_limit();
+
+    incremental.limit_to(Limits::new(node_limit, time_limit));
+
+    match (Interface::ask_for_anytime_step(), Interface::ask_for_weight()) {
+        (Some(step), weight) => incremental.anytime(weight.unwrap_or(2.0), step),
+        (None, Some(weight)) => incremental.weight_by(weight),
+        (None, None) => {},
+    }
+
+    if let Some(path) = Interface::ask_for_events_path() {
+        // Not stdout, so that the events aren't mixed with the solve output.
+        let events = match path.as_str() {
+            "-" => Events::new(io::stderr()),
+            path => Events::to_file(path).unwrap_or_else(|e| Interface::exit_with_error(e)),
+        };
+
+        incremental.emit_events_to(events);
+    }
+
+    if let Some(path) = checkpoint_path() {
+        incremental.checkpoint_to(Checkpoint::new(path, CHECKPOINT_INTERVAL));
+
+        let interrupt = incremental.interrupt_flag();
+        ctrlc::set_handler(move || interrupt.store(true, Relaxed)).expect("failed to handle SIGINT");
+    }
+
+    incremental
+}
+
+fn estimate(problem: &Problem) -> Box<dyn HeuristicFn> {
+    match Interface::ask_for_estimate() {
+        Estimate::Learned => Box::new(Heuristic::seed(problem)),
+        Estimate::TailAware => Box::new(TailAware::new(Heuristic::seed(problem))),
+        Estimate::Analytic => Box::new(Heuristic::seed(problem).max(AnalyticBound::new(problem))),
+        Estimate::PatternDatabase => Box::new(Heuristic::seed(problem).max(pattern_database(problem))),
+        Estimate::Zero => Box::new(Zero),
+    }
+}
+
+// Building one takes a couple of seconds for six symbols so it's saved for reuse.
+fn pattern_database(problem: &Problem) -> PatternDatabase {
+    if !(3..=pattern_database::MAXIMUM_SYMBOLS).contains(&problem.symbols()) {
+        Interface::exit_with_error("the pattern database can only be built for three to six symbols");
+    }
+
+    let path = match Interface::ask_for_pattern_database_path() {
+        Some(path) => path,
+        None => return PatternDatabase::build(problem),
+    };
+
+    if std::path::Path::new(&path).exists() {
+        return PatternDatabase::load(problem, &path).unwrap_or_else(|e| Interface::exit_with_error(e));
+    }
+
+    let database = PatternDatabase::build(problem);
+    database.save(&path).unwrap_or_else(|e| Interface::exit_with_error(e));
+    database
+}
+
+fn read_checkpoint<'a, B: Backend<'a>>(path: &str, problem: &'a Problem) -> Incremental<'a, B> {
+    Checkpoint::read(path, problem).unwrap_or_else(|e| Interface::exit_with_error(e))
+}
+
+fn run<'a, B: Backend<'a>, F>(incremental: &mut Incremental<'a, B>, milestone: F) -> Option<usize>
+    where F: FnMut(usize, usize, &str, &B, &Heuristic, &SearchStats)
+{
+    let problem = incremental.problem();
+
+    match (Interface::ask_for_resume_path(), Interface::ask_for_prefix()) {
+        (Some(_), _) => incremental.resume(milestone),
+        (None, Some(string)) => {
+            incremental.shortest_path_from_string(&string, milestone).unwrap_or_else(|e| Interface::exit_with_error(e))
+        },
+        (None, None) => incremental.shortest_path(Candidate::seed(problem), milestone),
+    }
+}
+
+// Printed to stderr for the other formats so that their output can be parsed.
+fn print_stats(format: Format, stats: &SearchStats) {
+    match format {
+        Format::Text => println!("\n{}", stats),
+        _ => eprintln!("{}", stats),
+    }
+}
+
+fn report_if_stopped<'a, B: Backend<'a>>(incremental: &Incremental<'a, B>) {
+    if let Some(error) = incremental.search().error() {
+        Interface::exit_with_error(format!("the search stopped at subgoal {}: {}", incremental.subgoal(), error));
+    }
+
+    if let Some(error) = incremental.checkpoint_error() {
+        Interface::exit_with_error(format!("failed to write checkpoint: {}", error));
+    }
+
+    if !incremental.interrupted() {
+        return;
+    }
+
+    match checkpoint_path() {
+        Some(path) => eprintln!("Stopped. Resume from subgoal {} with --resume {}", incremental.subgoal(), path),
+        None => eprintln!("Stopped at subgoal {}", incremental.subgoal()),
+    }
+}
+
+fn checkpoint_path() -> Option<String> {
+    Interface::ask_for_checkpoint_path().or_else(Interface::ask_for_resume_path)
+}
+
+fn print_header(format: Format, names: &[&str]) {
+    if let Some(header) = format.header(names) {
+        println!("{}", header);
+    }
+}
+
+fn preloaded_heuristic(problem: &Problem) -> Heuristic {
+    if let Some(path) = Interface::ask_for_heuristic_path() {
+        return Heuristic::load_distances(problem, path).unwrap_or_else(|e| Interface::exit_with_error(e));
+    }
+
+    if let Some(perms) = Interface::ask_for_published_perms() {
+        return Heuristic::published(problem, perms).unwrap_or_else(|| {
+            Interface::exit_with_error("there is no published table for this many symbols")
+        });
+    }
+
+    Heuristic::seed(problem)
 }
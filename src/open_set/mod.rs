@@ -1,40 +1,383 @@
 use ::bucket_queue::*;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Read, Write};
+use std::mem;
+use std::path::Path;
+use std::sync::Arc;
 use crate::candidate::Candidate;
+use crate::codec::{Encoder, Decoder};
+use crate::spill::{Record, Spill};
 
+const PENDING_RECORDS: usize = 1024;
+
+/// Candidates that are yet to be expanded, ordered by f-cost then g-cost. On
+/// disk, the buckets with the highest f-costs are spilled to files once the
+/// candidates in memory exceed the budget.
 pub struct OpenSet {
     candidates: BucketQueue<BucketQueue<Vec<Candidate>>>,
+    spilled: BTreeMap<usize, Spilled>,
+    disk: Option<Disk>,
+    bytes: usize,
+}
+
+struct Disk {
+    spill: Arc<Spill>,
+    budget: usize,
+}
+
+// Candidates are added to a spilled bucket in batches so files aren't opened
+// for every one. Duplicates are removed when the bucket is read back.
+struct Spilled {
+    file: usize,
+    len: usize,
+    pending: Vec<Record>,
 }
 
 impl OpenSet {
     pub fn new() -> Self {
-        Self { candidates: BucketQueue::new() }
+        Self { candidates: BucketQueue::new(), spilled: BTreeMap::new(), disk: None, bytes: 0 }
+    }
+
+    /// Spills to files in a new directory inside this one once the candidates
+    /// in memory use more than the budget in bytes.
+    pub fn on_disk<P: AsRef<Path>>(directory: P, budget: usize) -> io::Result<Self> {
+        Ok(Self::spilling_to(Spill::new(directory, budget)?, budget))
+    }
+
+    fn spilling_to(spill: Arc<Spill>, budget: usize) -> Self {
+        Self { disk: Some(Disk { spill, budget }), ..Self::new() }
     }
 
-    pub fn add(&mut self, candidate: Candidate, f_cost: usize, g_cost: usize) {
+    /// Spills to disk if the open set is on disk and over its budget.
+    pub fn add(&mut self, candidate: Candidate, f_cost: usize, g_cost: usize) -> io::Result<()> {
+        if let Some(spilled) = self.spilled.get_mut(&f_cost) {
+            spilled.len += 1;
+            spilled.pending.push((candidate, g_cost));
+
+            if spilled.pending.len() >= PENDING_RECORDS {
+                let spill = &self.disk.as_ref().unwrap().spill;
+
+                spill.append(spilled.file, Some(&spilled.pending[..]))?;
+                spilled.pending.clear();
+            }
+
+            return Ok(());
+        }
+
+        self.bytes += candidate.size_in_bytes();
+
         let bucket = self.candidates.bucket_for_adding(f_cost);
         bucket.push(candidate, g_cost);
+
+        self.spill_while_over_budget()
+    }
+
+    /// Reads the next bucket back if it was spilled.
+    pub fn pop(&mut self) -> io::Result<Option<(Candidate, usize)>> {
+        let (f_cost, g_cost) = match self.next_costs()? {
+            Some(costs) => costs,
+            None => return Ok(None),
+        };
+
+        let candidate = self.candidates.bucket_for_removing(f_cost).and_then(|bucket| bucket.pop(g_cost));
+
+        if let Some(candidate) = &candidate {
+            self.bytes -= candidate.size_in_bytes();
+        }
+
+        Ok(candidate.map(|candidate| (candidate, g_cost)))
+    }
+
+    /// The f-cost and g-cost of the candidate that pop() would return. A
+    /// spilled bucket is read back so that its g-costs are known.
+    pub fn next_costs(&mut self) -> io::Result<Option<(usize, usize)>> {
+        let f_cost = match self.minimum_f_cost() {
+            Some(f_cost) => f_cost,
+            None => return Ok(None),
+        };
+
+        if self.spilled.contains_key(&f_cost) {
+            self.read_back(f_cost)?;
+        }
+
+        let g_cost = self.candidates.bucket_for_peeking(f_cost).and_then(|bucket| bucket.max_priority());
+
+        Ok(g_cost.map(|g_cost| (f_cost, g_cost)))
     }
 
-    pub fn next(&mut self) -> Option<(Candidate, usize)> {
-        let f_cost = self.minimum_f_cost()?;
-        let bucket = self.candidates.bucket_for_removing(f_cost)?;
+    /// Passes the candidates that pop() would return in turn, those with the
+    /// next f-cost and g-cost, to the function so it can remove some of them
+    /// in a batch, e.g. those in a closed set on disk. Returns what it returns,
+    /// if there is a next bucket.
+    pub fn retain_next<T, F>(&mut self, retain: F) -> io::Result<Option<T>>
+        where F: FnOnce(&mut Vec<Candidate>, usize) -> io::Result<T>
+    {
+        let (f_cost, g_cost) = match self.next_costs()? {
+            Some(costs) => costs,
+            None => return Ok(None),
+        };
+
+        let bucket = self.candidates.bucket_for_replacing(f_cost).as_mut().unwrap();
+        let mut candidates = bucket.replace(g_cost, None).unwrap_or_default();
+        let (len, bytes) = (candidates.len(), Self::size_of(&candidates));
+
+        let result = retain(&mut candidates, g_cost);
 
-        let g_cost = bucket.max_priority()?;
-        let candidate = bucket.pop(g_cost)?;
+        self.bytes -= bytes - Self::size_of(&candidates);
 
-        Some((candidate, g_cost))
+        self.candidates.items_replaced(f_cost, len, candidates.len());
+
+        if !candidates.is_empty() {
+            let bucket = self.candidates.bucket_for_replacing(f_cost).as_mut().unwrap();
+            bucket.replace(g_cost, Some(candidates));
+        }
+
+        result.map(Some)
     }
 
     pub fn len(&self) -> usize {
-        self.candidates.len()
+        self.candidates.len() + self.spilled.values().map(|s| s.len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// An estimate of the memory used by the candidates in memory. It's kept
+    /// as they're added and removed rather than counted each time.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bytes
     }
 
     pub fn minimum_f_cost(&self) -> Option<usize> {
-        self.candidates.min_priority()
+        let spilled = self.spilled.keys().next().cloned();
+        self.candidates.min_priority().into_iter().chain(spilled).min()
+    }
+
+    /// The lowest f-cost of a bucket that has been spilled to disk.
+    pub fn minimum_spilled_f_cost(&self) -> Option<usize> {
+        self.spilled.keys().next().cloned()
     }
 
     pub fn maximum_f_cost(&self) -> Option<usize> {
-        self.candidates.max_priority()
+        let spilled = self.spilled.keys().next_back().cloned();
+        self.candidates.max_priority().into_iter().chain(spilled).max()
+    }
+
+    /// Yields (candidate, f_cost, g_cost) without removing anything. Adding the
+    /// candidates back in this order rebuilds the buckets in the same order.
+    /// Candidates that have been spilled to disk are not included.
+    pub fn iter(&self) -> impl Iterator<Item = (&Candidate, usize, usize)> {
+        let queue = &self.candidates;
+
+        Self::priorities(queue).flat_map(move |f_cost| {
+            queue.bucket_for_peeking(f_cost).into_iter().flat_map(move |bucket| {
+                Self::priorities(bucket).flat_map(move |g_cost| {
+                    let candidates = bucket.bucket_for_peeking(g_cost).into_iter().flatten();
+                    candidates.map(move |candidate| (candidate, f_cost, g_cost))
+                })
+            })
+        })
+    }
+
+    /// Recalculates the f-cost of every candidate, e.g. when the heuristic
+    /// changes. Candidates in memory keep their order within each bucket.
+    pub fn update_costs<F: FnMut(&Candidate, usize) -> usize>(&mut self, mut f_cost: F) -> io::Result<()> {
+        let spilled = mem::take(&mut self.spilled);
+        let mut stack = vec![];
+
+        while let Some(item) = self.pop()? {
+            stack.push(item);
+        }
+
+        while let Some((candidate, g_cost)) = stack.pop() {
+            let f = f_cost(&candidate, g_cost);
+            self.add(candidate, f, g_cost)?;
+        }
+
+        for (_, spilled) in spilled {
+            for (candidate, g_cost) in self.take_spilled(spilled)? {
+                let f = f_cost(&candidate, g_cost);
+                self.add(candidate, f, g_cost)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// An empty open set of the same kind.
+    pub fn empty(&self) -> Self {
+        match &self.disk {
+            Some(disk) => Self::spilling_to(disk.spill.clone(), disk.spill.budget()),
+            None => Self::new(),
+        }
+    }
+
+    /// Splits by fingerprint, the same as the closed set, e.g. so that each
+    /// thread of a parallel search can own a part. Parts on disk share the
+    /// directory and budget.
+    pub fn partition(mut self, parts: usize) -> io::Result<Vec<Self>> {
+        let mut result = (0..parts).map(|_| match &self.disk {
+            Some(disk) => Self::spilling_to(disk.spill.clone(), disk.spill.budget() / parts),
+            None => Self::new(),
+        }).collect::<Vec<_>>();
+
+        let part = |candidate: &Candidate| (candidate.fingerprint() % parts as u128) as usize;
+
+        self.move_into(|candidate, f_cost, g_cost| {
+            result[part(&candidate)].add(candidate, f_cost, g_cost)
+        })?;
+
+        Ok(result)
+    }
+
+    /// Adds the candidates of another open set.
+    pub fn extend(&mut self, mut other: Self) -> io::Result<()> {
+        other.move_into(|candidate, f_cost, g_cost| self.add(candidate, f_cost, g_cost))
+    }
+
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_usize(self.len())?;
+
+        for (candidate, f_cost, g_cost) in self.iter() {
+            encoder.write_usize(f_cost)?;
+            encoder.write_usize(g_cost)?;
+            candidate.write_to(encoder)?;
+        }
+
+        for (f_cost, spilled) in &self.spilled {
+            let spill = &self.disk.as_ref().unwrap().spill;
+            let written: Vec<Record> = spill.read(spilled.file, 0, spilled.len - spilled.pending.len())?;
+
+            for (candidate, g_cost) in written.iter().chain(&spilled.pending) {
+                encoder.write_usize(*f_cost)?;
+                encoder.write_usize(*g_cost)?;
+                candidate.write_to(encoder)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Reads the candidates back into memory, even if they were on disk.
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self> {
+        let mut open_set = Self::new();
+
+        for _ in 0..decoder.read_usize()? {
+            let f_cost = decoder.read_usize()?;
+            let g_cost = decoder.read_usize()?;
+            let candidate = Candidate::read_from(decoder)?;
+
+            open_set.add(candidate, f_cost, g_cost)?;
+        }
+
+        Ok(open_set)
+    }
+
+    // The bucket being expanded is never spilled.
+    fn spill_while_over_budget(&mut self) -> io::Result<()> {
+        loop {
+            match &self.disk {
+                Some(disk) if self.bytes > disk.budget => {},
+                _ => return Ok(()),
+            }
+
+            let f_cost = match (self.candidates.min_priority(), self.candidates.max_priority()) {
+                (Some(min), Some(max)) if min < max => max,
+                _ => return Ok(()),
+            };
+
+            let records = self.take_bucket(f_cost);
+            let disk = self.disk.as_mut().unwrap();
+            let file = disk.spill.new_file();
+
+            disk.spill.append(file, Some(&records[..]))?;
+            self.spilled.insert(f_cost, Spilled { file, len: records.len(), pending: vec![] });
+        }
+    }
+
+    // Keeps the lowest g-cost of each candidate that was added more than once
+    // while its bucket was on disk.
+    fn read_back(&mut self, f_cost: usize) -> io::Result<()> {
+        let spilled = self.spilled.remove(&f_cost).unwrap();
+        let mut positions = HashMap::new();
+        let mut unique: Vec<Record> = vec![];
+
+        for (candidate, g_cost) in self.take_spilled(spilled)? {
+            let position = *positions.entry(candidate.fingerprint()).or_insert(unique.len());
+
+            match unique.get_mut(position) {
+                Some((c, g)) if *c == candidate => *g = (*g).min(g_cost),
+                _ => unique.push((candidate, g_cost)),
+            }
+        }
+
+        for (candidate, g_cost) in unique {
+            self.bytes += candidate.size_in_bytes();
+            self.candidates.bucket_for_adding(f_cost).push(candidate, g_cost);
+        }
+
+        self.spill_while_over_budget()
+    }
+
+    fn take_spilled(&self, spilled: Spilled) -> io::Result<Vec<Record>> {
+        let spill = &self.disk.as_ref().unwrap().spill;
+        let mut records = spill.read(spilled.file, 0, spilled.len - spilled.pending.len())?;
+
+        spill.remove(spilled.file)?;
+        records.extend(spilled.pending);
+
+        Ok(records)
+    }
+
+    // Removes the bucket in the order it would be iterated.
+    fn take_bucket(&mut self, f_cost: usize) -> Vec<Record> {
+        let mut bucket = match self.candidates.replace(f_cost, None) {
+            Some(bucket) => bucket,
+            None => return vec![],
+        };
+
+        let mut records = vec![];
+
+        for g_cost in Self::priorities(&bucket).collect::<Vec<_>>() {
+            for candidate in bucket.replace(g_cost, None).into_iter().flatten() {
+                self.bytes -= candidate.size_in_bytes();
+                records.push((candidate, g_cost));
+            }
+        }
+
+        records
+    }
+
+    fn move_into<F: FnMut(Candidate, usize, usize) -> io::Result<()>>(&mut self, mut add: F) -> io::Result<()> {
+        while let Some(f_cost) = self.candidates.min_priority() {
+            for (candidate, g_cost) in self.take_bucket(f_cost) {
+                add(candidate, f_cost, g_cost)?;
+            }
+        }
+
+        for (f_cost, spilled) in mem::take(&mut self.spilled) {
+            for (candidate, g_cost) in self.take_spilled(spilled)? {
+                add(candidate, f_cost, g_cost)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn size_of(candidates: &[Candidate]) -> usize {
+        candidates.iter().map(|candidate| candidate.size_in_bytes()).sum()
+    }
+
+    fn priorities<B: Bucket, Q: Queue<B>>(queue: &Q) -> impl Iterator<Item = usize> {
+        let range = queue.min_priority().zip(queue.max_priority());
+        range.into_iter().flat_map(|(min, max)| min..=max)
+    }
+}
+
+impl Default for OpenSet {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
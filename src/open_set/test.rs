@@ -1,9 +1,12 @@
-#![cfg(not(feature = "four_symbols"))]
-
 use super::*;
+use crate::problem::Problem;
 
 type Subject = OpenSet;
 
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
 mod new {
     use super::*;
 
@@ -20,9 +23,9 @@ mod add {
     #[test]
     fn it_adds_the_candidate_and_sets_their_f_cost() {
         let mut subject = Subject::new();
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
 
-        subject.add(candidate, 12, 34);
+        subject.add(candidate, 12, 34).unwrap();
 
         let f_cost = subject.candidates.min_priority().unwrap();
 
@@ -32,9 +35,9 @@ mod add {
     #[test]
     fn it_adds_the_candidate_and_sets_their_g_cost() {
         let mut subject = Subject::new();
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
 
-        subject.add(candidate, 12, 34);
+        subject.add(candidate, 12, 34).unwrap();
 
         let bucket = subject.candidates.min_bucket();
         let g_cost = bucket.min_priority().unwrap();
@@ -43,23 +46,23 @@ mod add {
     }
 }
 
-mod next {
+mod pop {
     use super::*;
 
     #[test]
     fn it_returns_the_candidates_ordered_by_f_cost_ascending() {
         let mut subject = Subject::new();
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
 
-        let first = candidate.expand(0);
-        let second = candidate.expand(1);
+        let first = candidate.expand(&PROBLEM, 0);
+        let second = candidate.expand(&PROBLEM, 1);
 
-        subject.add(first.clone(), 12, 34);
-        subject.add(second.clone(), 56, 78);
+        subject.add(first.clone(), 12, 34).unwrap();
+        subject.add(second.clone(), 56, 78).unwrap();
 
-        assert_eq!(subject.next(), Some((first, 34)));
-        assert_eq!(subject.next(), Some((second, 78)));
-        assert_eq!(subject.next(), None);
+        assert_eq!(subject.pop().unwrap(), Some((first, 34)));
+        assert_eq!(subject.pop().unwrap(), Some((second, 78)));
+        assert_eq!(subject.pop().unwrap(), None);
     }
 
     mod when_candidates_have_the_same_f_cost {
@@ -68,50 +71,115 @@ mod next {
         #[test]
         fn it_returns_the_candidates_ordered_by_g_cost_descending() {
             let mut subject = Subject::new();
-            let candidate = Candidate::seed();
+            let candidate = Candidate::seed(&PROBLEM);
 
-            let first = candidate.expand(0);
-            let second = candidate.expand(1);
-            let third = candidate.expand(2);
+            let first = candidate.expand(&PROBLEM, 0);
+            let second = candidate.expand(&PROBLEM, 1);
+            let third = candidate.expand(&PROBLEM, 2);
 
-            subject.add(first.clone(), 12, 34);
-            subject.add(second.clone(), 56, 78);
-            subject.add(third.clone(), 12, 33);
+            subject.add(first.clone(), 12, 34).unwrap();
+            subject.add(second.clone(), 56, 78).unwrap();
+            subject.add(third.clone(), 12, 33).unwrap();
 
-            assert_eq!(subject.next(), Some((first, 34)));
-            assert_eq!(subject.next(), Some((third, 33)));
-            assert_eq!(subject.next(), Some((second, 78)));
+            assert_eq!(subject.pop().unwrap(), Some((first, 34)));
+            assert_eq!(subject.pop().unwrap(), Some((third, 33)));
+            assert_eq!(subject.pop().unwrap(), Some((second, 78)));
         }
     }
 }
 
+mod retain_next {
+    use super::*;
+
+    #[test]
+    fn it_retains_candidates_in_the_next_bucket_and_leaves_the_others() {
+        let mut subject = Subject::new();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        let first = candidate.expand(&PROBLEM, 0);
+        let second = candidate.expand(&PROBLEM, 1);
+        let third = candidate.expand(&PROBLEM, 2);
+
+        subject.add(first.clone(), 12, 56).unwrap();
+        subject.add(second.clone(), 12, 56).unwrap();
+        subject.add(third.clone(), 12, 34).unwrap();
+
+        let result = subject.retain_next(|candidates, g_cost| {
+            candidates.retain(|c| c != &first);
+            Ok(g_cost)
+        });
+
+        assert_eq!(result.unwrap(), Some(56));
+        assert_eq!(subject.len(), 2);
+
+        assert_eq!(subject.pop().unwrap(), Some((second, 56)));
+        assert_eq!(subject.pop().unwrap(), Some((third, 34)));
+    }
+
+    #[test]
+    fn it_removes_the_bucket_when_nothing_is_retained() {
+        let mut subject = Subject::new();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        subject.add(candidate.clone(), 12, 34).unwrap();
+        subject.retain_next(|candidates, _| { candidates.clear(); Ok(()) }).unwrap();
+
+        assert_eq!(subject.len(), 0);
+        assert_eq!(subject.pop().unwrap(), None);
+        assert_eq!(subject.retain_next(|_, _| Ok(())).unwrap(), None);
+    }
+}
+
 mod len {
     use super::*;
 
     #[test]
     fn it_returns_the_number_of_candidates_in_the_open_set() {
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
 
         let mut subject = Subject::new();
         assert_eq!(subject.len(), 0);
 
-        subject.add(candidate, 12, 34);
+        subject.add(candidate, 12, 34).unwrap();
         assert_eq!(subject.len(), 1);
     }
 }
 
+mod size_in_bytes {
+    use super::*;
+
+    #[test]
+    fn it_counts_the_candidates_as_they_are_added_and_removed() {
+        let mut subject = Subject::new();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        let first = candidate.expand(&PROBLEM, 0);
+        let second = candidate.expand(&PROBLEM, 1);
+
+        subject.add(first.clone(), 12, 34).unwrap();
+        subject.add(second.clone(), 12, 34).unwrap();
+        assert_eq!(subject.size_in_bytes(), first.size_in_bytes() + second.size_in_bytes());
+
+        subject.pop().unwrap();
+        assert_eq!(subject.size_in_bytes(), first.size_in_bytes());
+
+        subject.retain_next(|candidates, _| { candidates.clear(); Ok(()) }).unwrap();
+        assert_eq!(subject.size_in_bytes(), 0);
+    }
+}
+
 mod minimum_f_cost {
     use super::*;
 
     #[test]
     fn it_returns_the_minimum_f_cost_in_the_open_set() {
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
 
         let mut subject = Subject::new();
         assert_eq!(subject.minimum_f_cost(), None);
 
-        subject.add(candidate.clone(), 12, 34);
-        subject.add(candidate.clone(), 56, 78);
+        subject.add(candidate.clone(), 12, 34).unwrap();
+        subject.add(candidate.clone(), 56, 78).unwrap();
 
         assert_eq!(subject.minimum_f_cost(), Some(12));
     }
@@ -122,14 +190,193 @@ mod maximum_f_cost {
 
     #[test]
     fn it_returns_the_maximum_f_cost_in_the_open_set() {
-        let candidate = Candidate::seed();
+        let candidate = Candidate::seed(&PROBLEM);
 
         let mut subject = Subject::new();
         assert_eq!(subject.maximum_f_cost(), None);
 
-        subject.add(candidate.clone(), 12, 34);
-        subject.add(candidate.clone(), 56, 78);
+        subject.add(candidate.clone(), 12, 34).unwrap();
+        subject.add(candidate.clone(), 56, 78).unwrap();
 
         assert_eq!(subject.maximum_f_cost(), Some(56));
     }
 }
+
+mod iter {
+    use super::*;
+
+    #[test]
+    fn it_yields_every_candidate_with_its_f_and_g_cost_without_removing_them() {
+        let mut subject = Subject::new();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        let first = candidate.expand(&PROBLEM, 0);
+        let second = candidate.expand(&PROBLEM, 1);
+        let third = candidate.expand(&PROBLEM, 2);
+
+        subject.add(first.clone(), 12, 34).unwrap();
+        subject.add(second.clone(), 56, 78).unwrap();
+        subject.add(third.clone(), 12, 33).unwrap();
+
+        let items = subject.iter().collect::<Vec<_>>();
+
+        assert_eq!(items, &[(&third, 12, 33), (&first, 12, 34), (&second, 56, 78)]);
+        assert_eq!(subject.len(), 3);
+    }
+}
+
+mod write_to {
+    use super::*;
+    use crate::codec::{Encoder, Decoder};
+
+    #[test]
+    fn it_can_be_read_back_with_the_same_order_of_candidates() {
+        let mut subject = Subject::new();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        let first = candidate.expand(&PROBLEM, 0);
+        let second = candidate.expand(&PROBLEM, 1);
+        let third = candidate.expand(&PROBLEM, 2);
+
+        subject.add(first.clone(), 12, 34).unwrap();
+        subject.add(second.clone(), 12, 34).unwrap();
+        subject.add(third.clone(), 5, 6).unwrap();
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let mut result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result.pop().unwrap(), Some((third, 6)));
+        assert_eq!(result.pop().unwrap(), Some((second, 34)));
+        assert_eq!(result.pop().unwrap(), Some((first, 34)));
+        assert_eq!(result.pop().unwrap(), None);
+    }
+}
+
+mod on_disk {
+    use super::*;
+    use std::env;
+
+    fn candidates() -> Vec<Candidate> {
+        let candidate = Candidate::seed(&PROBLEM);
+        (0..PROBLEM.expansions()).map(|symbol| candidate.expand(&PROBLEM, symbol)).collect()
+    }
+
+    #[test]
+    fn it_returns_the_candidates_in_the_same_order_after_spilling_them() {
+        let mut in_memory = Subject::new();
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+
+        for (i, candidate) in candidates().into_iter().enumerate() {
+            in_memory.add(candidate.clone(), 10 + i % 2, i).unwrap();
+            subject.add(candidate.clone(), 10 + i % 2, i).unwrap();
+            subject.add(candidate, 12, i).unwrap();
+        }
+
+        assert_eq!(subject.spilled.len(), 2);
+        assert_eq!(subject.len(), 8);
+        assert_eq!(subject.minimum_f_cost(), Some(10));
+        assert_eq!(subject.maximum_f_cost(), Some(12));
+
+        for _ in 0..in_memory.len() {
+            assert_eq!(subject.pop().unwrap(), in_memory.pop().unwrap());
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_lowest_g_cost_of_duplicates_when_a_bucket_is_read_back() {
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+        let candidates = candidates();
+
+        subject.add(candidates[0].clone(), 10, 0).unwrap();
+        subject.add(candidates[1].clone(), 20, 7).unwrap();
+        subject.add(candidates[1].clone(), 20, 5).unwrap();
+        subject.add(candidates[2].clone(), 20, 6).unwrap();
+
+        assert_eq!(subject.len(), 4);
+        assert_eq!(subject.pop().unwrap(), Some((candidates[0].clone(), 0)));
+
+        assert_eq!(subject.pop().unwrap(), Some((candidates[2].clone(), 6)));
+        assert_eq!(subject.pop().unwrap(), Some((candidates[1].clone(), 5)));
+        assert_eq!(subject.pop().unwrap(), None);
+    }
+
+    #[test]
+    fn it_writes_the_spilled_candidates_to_be_read_back_into_memory() {
+        use crate::codec::{Encoder, Decoder};
+
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+        let candidates = candidates();
+
+        subject.add(candidates[0].clone(), 10, 1).unwrap();
+        subject.add(candidates[1].clone(), 20, 2).unwrap();
+        subject.add(candidates[2].clone(), 20, 3).unwrap();
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let mut result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+        assert_eq!(result.len(), 3);
+        assert_eq!(result.pop().unwrap(), Some((candidates[0].clone(), 1)));
+        assert_eq!(result.pop().unwrap(), Some((candidates[2].clone(), 3)));
+        assert_eq!(result.pop().unwrap(), Some((candidates[1].clone(), 2)));
+    }
+}
+
+mod update_costs {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn it_recalculates_the_f_costs_of_candidates_in_memory_and_on_disk() {
+        let candidate = Candidate::seed(&PROBLEM);
+        let first = candidate.expand(&PROBLEM, 0);
+        let second = candidate.expand(&PROBLEM, 1);
+
+        for mut subject in [Subject::new(), Subject::on_disk(env::temp_dir(), 0).unwrap()] {
+            subject.add(first.clone(), 10, 1).unwrap();
+            subject.add(second.clone(), 20, 2).unwrap();
+
+            subject.update_costs(|_, g_cost| 30 - g_cost).unwrap();
+
+            assert_eq!(subject.minimum_f_cost(), Some(28));
+            assert_eq!(subject.pop().unwrap(), Some((second.clone(), 2)));
+            assert_eq!(subject.pop().unwrap(), Some((first.clone(), 1)));
+        }
+    }
+}
+
+mod partition {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn it_splits_by_fingerprint_and_can_be_extended_back_into_one() {
+        let candidate = Candidate::seed(&PROBLEM);
+
+        for subject in [Subject::new(), Subject::on_disk(env::temp_dir(), 0).unwrap()] {
+            let mut subject = subject;
+
+            for symbol in 0..PROBLEM.expansions() {
+                subject.add(candidate.expand(&PROBLEM, symbol), 10 + symbol, symbol).unwrap();
+            }
+
+            let parts = subject.partition(3).unwrap();
+
+            assert_eq!(parts.iter().map(|p| p.len()).sum::<usize>(), PROBLEM.expansions());
+
+            let mut merged = parts[0].empty();
+
+            for part in parts {
+                merged.extend(part).unwrap();
+            }
+
+            for symbol in 0..PROBLEM.expansions() {
+                assert_eq!(merged.pop().unwrap(), Some((candidate.expand(&PROBLEM, symbol), symbol)));
+            }
+        }
+    }
+}
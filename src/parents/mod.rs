@@ -0,0 +1,307 @@
+use crate::candidate::Candidate;
+use crate::codec::{Encoder, Decoder};
+use crate::spill::{Entry, Run, Spill};
+use std::collections::HashMap;
+use std::mem;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// The g-cost and, unless it's a root, the parent's fingerprint and the symbol
+/// that was expanded.
+pub type Link = (usize, Option<(u128, usize)>);
+
+/// Links each candidate to the parent it was reached from most cheaply so that
+/// paths can be unwound. Candidates are identified by their fingerprints, like
+/// the hashed closed set, so that a link doesn't copy its parent. Only the
+/// roots are kept in full. On disk, links are written in runs sorted by
+/// fingerprint once those in memory exceed the budget, like the closed set.
+pub struct Parents {
+    links: HashMap<u128, Link>,
+    roots: HashMap<u128, Candidate>,
+    disk: Option<Disk>,
+}
+
+struct Disk {
+    spill: Arc<Spill>,
+    budget: usize,
+    runs: Vec<Run<(u128, Link)>>,
+}
+
+impl Parents {
+    pub fn new() -> Self {
+        Self { links: HashMap::new(), roots: HashMap::new(), disk: None }
+    }
+
+    /// Writes runs to a new directory inside this one once the links in memory
+    /// use more than the budget in bytes.
+    pub fn on_disk<P: AsRef<Path>>(directory: P, budget: usize) -> io::Result<Self> {
+        Ok(Self::spilling_to(Spill::new(directory, budget)?, budget))
+    }
+
+    fn spilling_to(spill: Arc<Spill>, budget: usize) -> Self {
+        Self { disk: Some(Disk { spill, budget, runs: vec![] }), ..Self::new() }
+    }
+
+    /// An empty set of parents of the same kind.
+    pub fn empty(&self) -> Self {
+        match &self.disk {
+            Some(disk) => Self::spilling_to(disk.spill.clone(), disk.spill.budget()),
+            None => Self::new(),
+        }
+    }
+
+    pub fn seed(&mut self, candidate: Candidate) -> io::Result<()> {
+        let fingerprint = candidate.fingerprint();
+
+        self.roots.insert(fingerprint, candidate);
+        self.insert(fingerprint, (0, None))
+    }
+
+    /// Writes a run if the parents are on disk and over their budget.
+    pub fn add(&mut self, candidate: &Candidate, g_cost: usize, parent: u128, symbol: usize) -> io::Result<()> {
+        self.insert(candidate.fingerprint(), (g_cost, Some((parent, symbol))))
+    }
+
+    pub fn unwind(&self, candidate: &Candidate) -> io::Result<Option<(Candidate, Vec<usize>)>> {
+        Self::unwind_partitioned(&[self], candidate)
+    }
+
+    /// Unwinds a path whose links are spread across the parts that partition
+    /// moved them into, e.g. the shards of a parallel search.
+    pub fn unwind_partitioned(parts: &[&Self], candidate: &Candidate) -> io::Result<Option<(Candidate, Vec<usize>)>> {
+        let part = |fingerprint: u128| &parts[(fingerprint % parts.len() as u128) as usize];
+        let mut fingerprint = candidate.fingerprint();
+        let mut symbols = vec![];
+
+        // A parent's g-cost is always lower than its child's so this ends.
+        loop {
+            match part(fingerprint).link(fingerprint)? {
+                Some((_, Some((parent, symbol)))) => {
+                    symbols.push(symbol);
+                    fingerprint = parent;
+                },
+                Some((_, None)) => break,
+                None => return Ok(None),
+            }
+        }
+
+        symbols.reverse();
+
+        Ok(part(fingerprint).roots.get(&fingerprint).map(|root| (root.clone(), symbols)))
+    }
+
+    /// The number of links, counting those written to more than one run once
+    /// for each until the runs are merged.
+    pub fn len(&self) -> usize {
+        self.links.len() + self.runs().iter().map(|run| run.len()).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// An estimate of the memory used, counting the capacity of the links' hash
+    /// map like the hashed closed set does, and the roots' bitmaps. On disk, it
+    /// counts the runs' indexes and filters but not their files.
+    pub fn size_in_bytes(&self) -> usize {
+        let links = self.links.capacity() * Self::link_size_in_bytes();
+        let roots = self.roots.values().map(|c| mem::size_of::<u128>() + c.size_in_bytes()).sum::<usize>();
+        let runs = self.runs().iter().map(|run| run.size_in_bytes()).sum::<usize>();
+
+        links + roots + runs
+    }
+
+    /// Combines the links of another set of the same kind, keeping the shortest
+    /// for each candidate, e.g. when merging the shards of a parallel search.
+    pub fn extend(&mut self, other: Self) -> io::Result<()> {
+        if let (Some(disk), Some(other)) = (&mut self.disk, other.disk) {
+            disk.runs.extend(other.runs);
+            Run::merge_newest(&disk.spill, &mut disk.runs)?;
+        }
+
+        for (fingerprint, link) in other.links {
+            self.insert(fingerprint, link)?;
+        }
+
+        self.roots.extend(other.roots);
+
+        Ok(())
+    }
+
+    /// Moves the links into the parts their fingerprints pick. Roots go with
+    /// their links. Parts on disk split each run and the budget.
+    pub fn partition(self, parts: usize) -> io::Result<Vec<Self>> {
+        let mut result = (0..parts).map(|_| match &self.disk {
+            Some(disk) => Self::spilling_to(disk.spill.clone(), disk.spill.budget() / parts),
+            None => Self::new(),
+        }).collect::<Vec<_>>();
+
+        let part = |fingerprint: u128| (fingerprint % parts as u128) as usize;
+
+        for run in self.runs() {
+            for (parents, run) in result.iter_mut().zip(run.partition(parts, |f| part(*f))?) {
+                match &mut parents.disk {
+                    Some(disk) if run.len() > 0 => disk.runs.push(run),
+                    _ => {},
+                }
+            }
+        }
+
+        for (fingerprint, link) in self.links {
+            result[part(fingerprint)].insert(fingerprint, link)?;
+        }
+
+        for (fingerprint, root) in self.roots {
+            result[part(fingerprint)].roots.insert(fingerprint, root);
+        }
+
+        Ok(result)
+    }
+
+    // The link with the lowest g-cost, which may be in memory or in any run.
+    fn link(&self, fingerprint: u128) -> io::Result<Option<Link>> {
+        let mut lowest = self.links.get(&fingerprint).cloned();
+
+        for run in self.runs() {
+            if let Some((_, link)) = run.get(&fingerprint)? {
+                if lowest.is_none_or(|(g_cost, _)| link.0 < g_cost) {
+                    lowest = Some(link);
+                }
+            }
+        }
+
+        Ok(lowest)
+    }
+
+    // Keeps the link with the lowest g-cost, so a root is never replaced. On
+    // disk, a link in memory can have a higher g-cost than one in a run until
+    // the run is merged or looked up.
+    fn insert(&mut self, fingerprint: u128, link: Link) -> io::Result<()> {
+        match self.links.get(&fingerprint) {
+            Some((previous, _)) if link.0 >= *previous => {},
+            _ => { self.links.insert(fingerprint, link); },
+        }
+
+        match &mut self.disk {
+            Some(disk) if self.links.len() * Self::link_size_in_bytes() > disk.budget => {
+                let mut links = mem::take(&mut self.links).into_iter().collect::<Vec<_>>();
+                links.sort_unstable_by_key(|(fingerprint, _)| *fingerprint);
+
+                disk.runs.push(Run::write(&disk.spill, links)?);
+                Run::merge_newest(&disk.spill, &mut disk.runs)
+            },
+            _ => Ok(()),
+        }
+    }
+
+    fn runs(&self) -> &[Run<(u128, Link)>] {
+        self.disk.as_ref().map_or(&[], |disk| &disk.runs)
+    }
+
+    fn link_size_in_bytes() -> usize {
+        mem::size_of::<(u128, Link)>() + 1
+    }
+
+    // Links on disk are read back into memory, keeping the lowest g-cost of
+    // any that were written to more than one run.
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_usize(self.roots.len())?;
+
+        for root in self.roots.values() {
+            root.write_to(encoder)?;
+        }
+
+        encoder.write_usize(self.len())?;
+
+        for run in self.runs() {
+            for entry in run.entries()? {
+                entry?.write_to(encoder)?;
+            }
+        }
+
+        for (fingerprint, link) in &self.links {
+            (*fingerprint, *link).write_to(encoder)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self> {
+        let mut parents = Self::new();
+
+        for _ in 0..decoder.read_usize()? {
+            let root = Candidate::read_from(decoder)?;
+            parents.roots.insert(root.fingerprint(), root);
+        }
+
+        for _ in 0..decoder.read_usize()? {
+            let (fingerprint, link) = <(u128, Link)>::read_from(decoder)?;
+            parents.insert(fingerprint, link)?;
+        }
+
+        Ok(parents)
+    }
+}
+
+impl Default for Parents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Entry for (u128, Link) {
+    type Key = u128;
+
+    fn key(&self) -> &u128 {
+        &self.0
+    }
+
+    fn fingerprint(fingerprint: &u128) -> u128 {
+        *fingerprint
+    }
+
+    fn key_size_in_bytes(_: &u128) -> usize {
+        mem::size_of::<u128>()
+    }
+
+    fn g_cost(&self) -> usize {
+        (self.1).0
+    }
+
+    fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        let (fingerprint, (g_cost, link)) = self;
+
+        encoder.write_u128(*fingerprint)?;
+        encoder.write_usize(*g_cost)?;
+
+        match link {
+            Some((parent, symbol)) => {
+                encoder.write_usize(1)?;
+                encoder.write_usize(*symbol)?;
+                encoder.write_u128(*parent)
+            },
+            None => encoder.write_usize(0),
+        }
+    }
+
+    fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self> {
+        let fingerprint = decoder.read_u128()?;
+        let g_cost = decoder.read_usize()?;
+
+        let link = match decoder.read_usize()? {
+            0 => None,
+            _ => {
+                let symbol = decoder.read_usize()?;
+                let parent = decoder.read_u128()?;
+
+                Some((parent, symbol))
+            },
+        };
+
+        Ok((fingerprint, (g_cost, link)))
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,294 @@
+use super::*;
+use crate::problem::Problem;
+
+type Subject = Parents;
+
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
+fn link(subject: &Subject, candidate: &Candidate) -> Option<Link> {
+    subject.links.get(&candidate.fingerprint()).cloned()
+}
+
+mod seed {
+    use super::*;
+
+    #[test]
+    fn it_adds_the_candidate_without_a_parent() {
+        let mut subject = Subject::new();
+        let candidate = Candidate::seed(&PROBLEM);
+
+        subject.seed(candidate.clone()).unwrap();
+
+        assert_eq!(link(&subject, &candidate), Some((0, None)));
+    }
+}
+
+mod add {
+    use super::*;
+
+    #[test]
+    fn it_links_the_candidate_to_its_parents_fingerprint_and_the_expanded_symbol() {
+        let mut subject = Subject::new();
+        let parent = Candidate::seed(&PROBLEM);
+        let child = parent.expand(&PROBLEM, 2);
+
+        subject.add(&child, 1, parent.fingerprint(), 2).unwrap();
+
+        assert_eq!(link(&subject, &child), Some((1, Some((parent.fingerprint(), 2)))));
+    }
+
+    #[test]
+    fn it_keeps_the_link_with_the_lowest_g_cost() {
+        let mut subject = Subject::new();
+        let first = Candidate::seed(&PROBLEM);
+        let second = first.expand(&PROBLEM, 0);
+        let child = second.expand(&PROBLEM, 1);
+
+        subject.add(&child, 5, first.fingerprint(), 1).unwrap();
+        subject.add(&child, 6, second.fingerprint(), 1).unwrap();
+        assert_eq!(link(&subject, &child), Some((5, Some((first.fingerprint(), 1)))));
+
+        subject.add(&child, 4, second.fingerprint(), 1).unwrap();
+        assert_eq!(link(&subject, &child), Some((4, Some((second.fingerprint(), 1)))));
+    }
+
+    #[test]
+    fn it_does_not_replace_the_seed() {
+        let mut subject = Subject::new();
+        let seed = Candidate::seed(&PROBLEM);
+        let other = seed.expand(&PROBLEM, 1);
+
+        subject.seed(seed.clone()).unwrap();
+        subject.add(&seed, 3, other.fingerprint(), 0).unwrap();
+
+        assert_eq!(link(&subject, &seed), Some((0, None)));
+    }
+}
+
+mod extend {
+    use super::*;
+
+    #[test]
+    fn it_adds_the_links_of_the_other_parents_keeping_the_lowest_g_cost() {
+        let a = Candidate::seed(&PROBLEM);
+        let b = a.expand(&PROBLEM, 0);
+        let c = b.expand(&PROBLEM, 1);
+
+        let mut subject = Subject::new();
+        subject.seed(a.clone()).unwrap();
+        subject.add(&c, 2, b.fingerprint(), 1).unwrap();
+
+        let mut other = Subject::new();
+        other.add(&b, 1, a.fingerprint(), 0).unwrap();
+        other.add(&c, 3, a.fingerprint(), 1).unwrap();
+
+        subject.extend(other).unwrap();
+
+        assert_eq!(subject.len(), 3);
+        assert_eq!(link(&subject, &b), Some((1, Some((a.fingerprint(), 0)))));
+        assert_eq!(link(&subject, &c), Some((2, Some((b.fingerprint(), 1)))));
+        assert_eq!(subject.unwind(&c).unwrap(), Some((a, vec![0, 1])));
+    }
+}
+
+mod partition {
+    use super::*;
+
+    #[test]
+    fn it_moves_each_link_and_root_into_the_part_its_fingerprint_picks() {
+        let a = Candidate::seed(&PROBLEM);
+        let mut subject = Subject::new();
+
+        subject.seed(a.clone()).unwrap();
+
+        for symbol in 0..PROBLEM.expansions() {
+            subject.add(&a.expand(&PROBLEM, symbol), 1, a.fingerprint(), symbol).unwrap();
+        }
+
+        let parts = subject.partition(3).unwrap();
+        let owner = (a.fingerprint() % 3) as usize;
+
+        assert_eq!(parts.iter().map(|p| p.len()).sum::<usize>(), PROBLEM.expansions() + 1);
+        assert_eq!(parts[owner].unwind(&a).unwrap(), Some((a.clone(), vec![])));
+
+        for (i, part) in parts.iter().enumerate() {
+            assert!(part.links.keys().all(|f| (f % 3) as usize == i));
+        }
+    }
+}
+
+mod unwind {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_root_candidate_and_the_symbols_expanded_to_reach_the_candidate() {
+        let mut subject = Subject::new();
+
+        let a = Candidate::seed(&PROBLEM);
+        let b = a.expand(&PROBLEM, 0);
+        let c = b.expand(&PROBLEM, 3);
+        let d = c.expand(&PROBLEM, 0);
+
+        subject.seed(a.clone()).unwrap();
+        subject.add(&b, 1, a.fingerprint(), 0).unwrap();
+        subject.add(&c, 2, b.fingerprint(), 3).unwrap();
+        subject.add(&d, 3, c.fingerprint(), 0).unwrap();
+
+        assert_eq!(subject.unwind(&d).unwrap(), Some((a.clone(), vec![0, 3, 0])));
+        assert_eq!(subject.unwind(&b).unwrap(), Some((a.clone(), vec![0])));
+        assert_eq!(subject.unwind(&a).unwrap(), Some((a, vec![])));
+    }
+
+    #[test]
+    fn it_returns_none_if_the_candidate_has_not_been_added() {
+        let subject = Subject::new();
+        assert_eq!(subject.unwind(&Candidate::seed(&PROBLEM)).unwrap(), None);
+    }
+
+    #[test]
+    fn it_returns_none_if_the_path_does_not_lead_back_to_a_root() {
+        let mut subject = Subject::new();
+
+        let a = Candidate::seed(&PROBLEM);
+        let b = a.expand(&PROBLEM, 0);
+
+        subject.add(&b, 1, a.fingerprint(), 0).unwrap();
+
+        assert_eq!(subject.unwind(&b).unwrap(), None);
+    }
+}
+
+mod on_disk {
+    use super::*;
+    use std::env;
+
+    #[test]
+    fn it_unwinds_paths_whose_links_were_written_to_runs() {
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+
+        let a = Candidate::seed(&PROBLEM);
+        let b = a.expand(&PROBLEM, 0);
+        let c = b.expand(&PROBLEM, 3);
+
+        subject.seed(a.clone()).unwrap();
+        subject.add(&c, 5, a.fingerprint(), 1).unwrap();
+        subject.add(&b, 1, a.fingerprint(), 0).unwrap();
+        subject.add(&c, 2, b.fingerprint(), 3).unwrap();
+
+        assert_eq!(subject.links.len(), 0);
+        assert_eq!(subject.unwind(&c).unwrap(), Some((a, vec![0, 3])));
+    }
+
+    #[test]
+    fn it_splits_the_runs_between_parts_and_merges_them_back() {
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+        let a = Candidate::seed(&PROBLEM);
+
+        subject.seed(a.clone()).unwrap();
+
+        for symbol in 0..PROBLEM.expansions() {
+            subject.add(&a.expand(&PROBLEM, symbol), 1, a.fingerprint(), symbol).unwrap();
+        }
+
+        let parts = subject.partition(3).unwrap();
+        let part_refs = parts.iter().collect::<Vec<_>>();
+        let b = a.expand(&PROBLEM, 2);
+
+        assert_eq!(Subject::unwind_partitioned(&part_refs, &b).unwrap(), Some((a.clone(), vec![2])));
+
+        let mut merged = parts[0].empty();
+
+        for part in parts {
+            merged.extend(part).unwrap();
+        }
+
+        assert_eq!(merged.len(), PROBLEM.expansions() + 1);
+        assert_eq!(merged.unwind(&b).unwrap(), Some((a, vec![2])));
+    }
+
+    #[test]
+    fn it_is_written_with_the_links_in_its_runs() {
+        use crate::codec::{Encoder, Decoder};
+
+        let mut subject = Subject::on_disk(env::temp_dir(), 0).unwrap();
+
+        let a = Candidate::seed(&PROBLEM);
+        let b = a.expand(&PROBLEM, 0);
+
+        subject.seed(a.clone()).unwrap();
+        subject.add(&b, 3, a.fingerprint(), 2).unwrap();
+        subject.add(&b, 1, a.fingerprint(), 0).unwrap();
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+        assert_eq!(link(&result, &b), Some((1, Some((a.fingerprint(), 0)))));
+        assert_eq!(result.unwind(&b).unwrap(), Some((a, vec![0])));
+    }
+}
+
+mod len {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_number_of_linked_candidates() {
+        let mut subject = Subject::new();
+        let candidate = Candidate::seed(&PROBLEM);
+        assert_eq!(subject.len(), 0);
+
+        subject.seed(candidate.clone()).unwrap();
+        subject.add(&candidate.expand(&PROBLEM, 0), 1, candidate.fingerprint(), 0).unwrap();
+        assert_eq!(subject.len(), 2);
+    }
+}
+
+mod size_in_bytes {
+    use super::*;
+
+    #[test]
+    fn it_counts_the_links_and_the_roots() {
+        let mut subject = Subject::new();
+        let candidate = Candidate::seed(&PROBLEM);
+        assert_eq!(subject.size_in_bytes(), 0);
+
+        subject.seed(candidate.clone()).unwrap();
+        let seeded = subject.size_in_bytes();
+        assert!(seeded > candidate.size_in_bytes());
+
+        for symbol in 0..PROBLEM.expansions() {
+            subject.add(&candidate.expand(&PROBLEM, symbol), 1, candidate.fingerprint(), symbol).unwrap();
+        }
+
+        assert!(subject.size_in_bytes() > seeded);
+    }
+}
+
+mod write_to {
+    use super::*;
+    use crate::codec::{Encoder, Decoder};
+
+    #[test]
+    fn it_can_be_read_back_with_read_from() {
+        let mut subject = Subject::new();
+
+        let a = Candidate::seed(&PROBLEM);
+        let b = a.expand(&PROBLEM, 0);
+        let c = b.expand(&PROBLEM, 3);
+
+        subject.seed(a.clone()).unwrap();
+        subject.add(&b, 1, a.fingerprint(), 0).unwrap();
+        subject.add(&c, 2, b.fingerprint(), 3).unwrap();
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let result = Subject::read_from(&mut Decoder::new(&bytes[..])).unwrap();
+
+        assert_eq!(result.links, subject.links);
+        assert_eq!(result.unwind(&c).unwrap(), Some((a, vec![0, 3])));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::candidate::Candidate;
+use crate::codec::Decoder;
+use crate::problem::Problem;
+use crate::utility::Utility;
+use std::fmt;
+use std::io::{self, ErrorKind, Read};
+
+/// The symbols that were expanded to get from a start candidate to a goal.
+#[derive(Clone)]
+pub struct Path<'a> {
+    problem: &'a Problem,
+    start: Candidate,
+    symbols: Vec<usize>,
+    labels: Vec<u8>,
+}
+
+impl<'a> Path<'a> {
+    pub fn new(problem: &'a Problem, start: Candidate, symbols: Vec<usize>) -> Self {
+        let labels = (0..problem.symbols() as u8).collect();
+
+        Self::with_labels(problem, start, symbols, labels)
+    }
+
+    /// The labels are the actual symbols of the start candidate's tail, e.g.
+    /// the first permutation of a prefix the search was seeded with, so that
+    /// the string starts the same way rather than with 0123...
+    pub fn with_labels(problem: &'a Problem, start: Candidate, symbols: Vec<usize>, labels: Vec<u8>) -> Self {
+        Self { problem, start, symbols, labels }
+    }
+
+    /// Candidates are canonicalised at every expansion so the symbols in the
+    /// path refer to the relabelled tail. Replay the expansions and track what
+    /// each label refers to so that we can recover the original string.
+    pub fn to_symbols(&self) -> Vec<u8> {
+        let problem = self.problem;
+
+        let mut labels = self.labels.clone();
+        let mut string = labels.clone();
+        let mut candidate = self.start.clone();
+
+        for &symbol in &self.symbols {
+            string.push(labels[symbol]);
+
+            let relabelling = candidate.relabelling(problem, symbol);
+            labels = relabelling.iter().map(|l| labels[*l as usize]).collect();
+
+            candidate = candidate.expand(problem, symbol);
+        }
+
+        string
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.symbols.is_empty()
+    }
+
+    /// Reads labels that were written as bytes, e.g. in a checkpoint, and
+    /// checks that they're a permutation of the problem's symbols.
+    pub fn read_labels<R: Read>(decoder: &mut Decoder<R>, problem: &Problem) -> io::Result<Vec<u8>> {
+        let labels = decoder.read_bytes()?;
+        let in_range = labels.iter().all(|l| (*l as usize) < problem.symbols());
+
+        if labels.len() != problem.symbols() || !in_range || !Utility::is_permutation(&labels) {
+            return Err(io::Error::new(ErrorKind::InvalidData, "labels are not a permutation of the symbols"));
+        }
+
+        Ok(labels)
+    }
+}
+
+impl<'a> PartialEq for Path<'a> {
+    fn eq(&self, other: &Self) -> bool {
+        self.start == other.start && self.symbols == other.symbols && self.labels == other.labels
+    }
+}
+
+impl<'a> fmt::Debug for Path<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Path({})", self)
+    }
+}
+
+impl<'a> fmt::Display for Path<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for symbol in self.to_symbols() {
+            write!(f, "{}", symbol)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,123 @@
+use lehmer::Lehmer;
+use std::collections::HashSet;
+use super::*;
+
+type Subject<'a> = Path<'a>;
+
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
+fn number_of_permutations(string: &[u8]) -> usize {
+    string.windows(PROBLEM.symbols()).filter(|window| {
+        window.iter().collect::<HashSet<_>>().len() == PROBLEM.symbols()
+    }).map(|window| {
+        Lehmer::from_permutation(window).to_decimal()
+    }).collect::<HashSet<_>>().len()
+}
+
+fn subject(symbols: &[usize]) -> Subject<'static> {
+    Subject::new(&PROBLEM, Candidate::seed(&PROBLEM), symbols.to_vec())
+}
+
+mod to_symbols {
+    use super::*;
+
+    #[test]
+    fn it_starts_with_the_tail_of_the_starting_candidate() {
+        assert_eq!(subject(&[]).to_symbols(), &[0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn it_starts_with_the_labels_it_was_given() {
+        let subject = Subject::with_labels(&PROBLEM, Candidate::seed(&PROBLEM), vec![0, 0], vec![4, 2, 0, 1, 3]);
+        assert_eq!(subject.to_symbols(), &[4, 2, 0, 1, 3, 4, 2]);
+    }
+
+    #[test]
+    fn it_undoes_the_relabelling_of_each_expansion() {
+        assert_eq!(subject(&[0]).to_symbols(), &[0, 1, 2, 3, 4, 0]);
+        assert_eq!(subject(&[0, 0]).to_symbols(), &[0, 1, 2, 3, 4, 0, 1]);
+        assert_eq!(subject(&[1]).to_symbols(), &[0, 1, 2, 3, 4, 1]);
+        assert_eq!(subject(&[3]).to_symbols(), &[0, 1, 2, 3, 4, 3]);
+        assert_eq!(subject(&[1, 0]).to_symbols(), &[0, 1, 2, 3, 4, 1, 0]);
+        assert_eq!(subject(&[0, 0, 0, 0, 0, 1, 0, 1, 0]).to_symbols(),
+                   &[0, 1, 2, 3, 4, 0, 1, 2, 3, 4, 1, 0, 3, 2]);
+    }
+
+    #[test]
+    fn it_produces_a_string_with_the_same_permutations_as_the_candidate() {
+        let symbols = [0, 0, 0, 0, 0, 1, 0, 1, 0, 2, 1, 0, 3, 0, 0, 2, 0, 1, 0];
+
+        for length in 0..symbols.len() {
+            let subject = subject(&symbols[0..length]);
+            let candidate = symbols[0..length].iter()
+                .fold(Candidate::seed(&PROBLEM), |c, s| c.expand(&PROBLEM, *s));
+
+            let string = subject.to_symbols();
+
+            assert_eq!(string.len(), PROBLEM.symbols() + length);
+            assert_eq!(number_of_permutations(&string), candidate.number_of_permutations(&PROBLEM));
+        }
+    }
+}
+
+mod six_symbols {
+    use super::*;
+
+    lazy_static! {
+        static ref SIX: Problem = Problem::new(6);
+    }
+
+    #[test]
+    fn it_produces_a_string_with_the_same_permutations_as_the_candidate() {
+        let symbols = [0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 1, 0, 0, 0, 0, 2, 1, 0, 0, 3, 0];
+        let mut candidate = Candidate::seed(&SIX);
+
+        for length in 0..symbols.len() {
+            let subject = Path::new(&SIX, Candidate::seed(&SIX), symbols[0..length].to_vec());
+            let string = subject.to_symbols();
+
+            let permutations = string.windows(6).filter(|window| {
+                window.iter().collect::<HashSet<_>>().len() == 6
+            }).collect::<HashSet<_>>();
+
+            assert_eq!(string.len(), 6 + length);
+            assert_eq!(permutations.len(), candidate.number_of_permutations(&SIX));
+
+            candidate = candidate.expand(&SIX, symbols[length]);
+        }
+    }
+}
+
+mod len {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_number_of_expansions_in_the_path() {
+        assert_eq!(subject(&[]).len(), 0);
+        assert_eq!(subject(&[0, 1, 0]).len(), 3);
+    }
+}
+
+mod eq {
+    use super::*;
+
+    #[test]
+    fn it_compares_the_labels_as_well_as_the_symbols() {
+        let relabelled = Subject::with_labels(&PROBLEM, Candidate::seed(&PROBLEM), vec![0, 0], vec![4, 2, 0, 1, 3]);
+
+        assert_eq!(subject(&[0, 0]), subject(&[0, 0]));
+        assert_ne!(subject(&[0, 0]), subject(&[0, 1]));
+        assert_ne!(subject(&[0, 0]), relabelled);
+    }
+}
+
+mod display {
+    use super::*;
+
+    #[test]
+    fn it_formats_the_string_as_digits() {
+        assert_eq!(subject(&[0, 0, 1, 0]).to_string(), "012340132");
+    }
+}
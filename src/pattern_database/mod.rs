@@ -0,0 +1,306 @@
+use crate::candidate::Candidate;
+use crate::codec::{Encoder, Decoder};
+use crate::heuristic::Heuristic;
+use crate::heuristic_fn::HeuristicFn;
+use crate::problem::Problem;
+use lehmer::Lehmer;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Write};
+use std::path::Path;
+
+const MAGIC: &[u8] = b"supermutation pattern database";
+const VERSION: usize = 1;
+
+/// The database grows with the square of the number of permutations.
+pub const MAXIMUM_SYMBOLS: usize = 6;
+
+/// A lower bound on the distance to the next goal that knows which
+/// permutations are missing near the tail. Permutations are grouped into
+/// 1-cycles, whose members follow each other for one symbol each. A ready
+/// candidate can add one from the 1-cycle of the permutation it's about to
+/// complete for a symbol, enter a neighboring 1-cycle that's a weight-2 edge
+/// away for two symbols, or enter any other for at least three. One that isn't
+/// ready can't add one until it is.
+///
+/// A ready candidate is projected onto the permutations missing from its
+/// 1-cycle and its neighbors, the total missing and the number still needed
+/// for the goal. One that isn't ready is projected onto the symbols until it
+/// is instead of its 1-cycle and neighbors. The cheapest way to the goal from
+/// every such state is worked out backwards from the goal, one permutation at
+/// a time. Which neighbors the next 1-cycle has isn't known, so the cheapest
+/// number missing from them is assumed.
+///
+/// This stands in for grouping the permutations by 2-cycle. Each 1-cycle is in
+/// n 2-cycles, one for each kernel its rotations have, so a tail picks out its
+/// 1-cycle but not which 2-cycle it's in. Its neighbors are the next 1-cycle in
+/// each of them. Every abstract move adds one permutation, so rather than a
+/// breadth-first search with a queue, the states are settled in layers by the
+/// number missing and each takes the cheapest move into the layer before.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternDatabase {
+    symbols: usize,
+    factorial: usize,
+    neighborhood: usize,
+    goal: usize,
+    one_cycles: Vec<usize>,
+    neighbors: Vec<bool>,
+    ready: Vec<u16>,
+    not_ready: Vec<u16>,
+}
+
+impl PatternDatabase {
+    pub fn build(problem: &Problem) -> Self {
+        let mut database = Self::empty(problem);
+
+        let n = database.symbols;
+        let neighborhood = database.neighborhood;
+
+        for missing in 1..=database.factorial {
+            for needed in 1..=missing {
+                let previous = |a: usize, b: usize| database.ready[database.ready_index(a, b, missing - 1, needed - 1)];
+
+                // The cheapest after entering a 1-cycle, given how many are
+                // missing from it and at least how many from its neighbors.
+                let mut entered = vec![vec![u16::MAX; neighborhood + 2]; n];
+
+                for (a, entered) in entered.iter_mut().enumerate().take(missing) {
+                    for b in (0..=neighborhood.min(missing - 1 - a)).rev() {
+                        entered[b] = entered[b + 1].min(previous(a, b));
+                    }
+                }
+
+                // The cheapest to enter any 1-cycle, given how many are missing
+                // outside the current one and its neighbors.
+                let mut entering = vec![u16::MAX; missing + 1];
+
+                for outside in 1..=missing {
+                    entering[outside] = entering[outside - 1];
+
+                    for x in 1..=n.min(outside) {
+                        entering[outside] = entering[outside].min(entered[x - 1][0]);
+                    }
+                }
+
+                let mut costs = vec![];
+
+                for a in 0..=n.min(missing) {
+                    for b in 0..=neighborhood.min(missing - a) {
+                        let outside = missing - a - b;
+                        let mut cost = u16::MAX;
+
+                        if a > 0 {
+                            cost = cost.min(previous(a - 1, b).saturating_add(1));
+                        }
+
+                        // The current 1-cycle is one of the neighbors of the next.
+                        for x in 1..=n.min(b) {
+                            cost = cost.min(entered[x - 1][a.min(neighborhood + 1)].saturating_add(2));
+                        }
+
+                        if outside > 0 {
+                            cost = cost.min(entering[outside].saturating_add(3));
+                        }
+
+                        costs.push((database.ready_index(a, b, missing, needed), cost));
+                    }
+                }
+
+                for (index, cost) in costs {
+                    database.ready[index] = cost;
+                }
+
+                for until_ready in 1..n - 1 {
+                    let index = database.not_ready_index(until_ready, missing, needed);
+                    database.not_ready[index] = entering[missing].saturating_add(until_ready as u16 + 1);
+                }
+            }
+        }
+
+        database
+    }
+
+    pub fn load<P: AsRef<Path>>(problem: &Problem, path: P) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Self::read_from(&mut Decoder::new(BufReader::new(file)), problem)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let file = File::create(path)?;
+        let mut encoder = Encoder::new(BufWriter::new(file));
+
+        self.write_to(&mut encoder)?;
+        encoder.flush()
+    }
+
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_bytes(MAGIC)?;
+        encoder.write_usize(VERSION)?;
+        encoder.write_usize(self.symbols)?;
+        encoder.write_bytes(&to_bytes(&self.ready))?;
+        encoder.write_bytes(&to_bytes(&self.not_ready))
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>, problem: &Problem) -> io::Result<Self> {
+        if decoder.read_bytes()? != MAGIC {
+            return Err(invalid("not a pattern database file"));
+        }
+
+        if decoder.read_usize()? != VERSION {
+            return Err(invalid("unsupported pattern database version"));
+        }
+
+        if decoder.read_usize()? != problem.symbols() {
+            return Err(invalid("pattern database is for a different number of symbols"));
+        }
+
+        let mut database = Self::empty(problem);
+
+        let ready = from_bytes(&decoder.read_bytes()?);
+        let not_ready = from_bytes(&decoder.read_bytes()?);
+
+        if ready.len() != database.ready.len() || not_ready.len() != database.not_ready.len() {
+            return Err(invalid("pattern database has the wrong number of states"));
+        }
+
+        database.ready = ready;
+        database.not_ready = not_ready;
+
+        Ok(database)
+    }
+
+    /// The number of abstract states.
+    pub fn len(&self) -> usize {
+        self.ready.len() + self.not_ready.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn empty(problem: &Problem) -> Self {
+        let symbols = problem.symbols();
+        let factorial = problem.factorial();
+
+        assert!(symbols >= 3, "the pattern database needs at least three symbols");
+        assert!(symbols <= MAXIMUM_SYMBOLS, "the pattern database is too big for {} symbols", symbols);
+
+        let one_cycles = Self::one_cycles(symbols, factorial);
+        let neighbors = Self::neighbors(symbols, &one_cycles);
+        let neighborhood = neighbors.iter().filter(|n| **n).count();
+
+        let mut database = Self { symbols, factorial, neighborhood, goal: 0, one_cycles, neighbors, ready: vec![], not_ready: vec![] };
+
+        let states = database.triangle(factorial + 1);
+
+        database.ready = vec![0; states * (symbols + 1) * (neighborhood + 1)];
+        database.not_ready = vec![0; states * (symbols - 2)];
+        database
+    }
+
+    // Numbers the 1-cycle of each permutation by the Lehmer index of its
+    // rotation that starts with zero.
+    fn one_cycles(n: usize, factorial: usize) -> Vec<usize> {
+        (0..factorial).map(|i| {
+            let mut permutation = Lehmer::from_decimal(i, n).to_permutation();
+            let zero = permutation.iter().position(|s| *s == 0).unwrap();
+
+            permutation.rotate_left(zero);
+            Lehmer::from_permutation(&permutation).to_decimal()
+        }).collect()
+    }
+
+    // Finds the permutations in the 1-cycles a weight-2 edge away from the
+    // identity's, such as 0123 to 2310.
+    fn neighbors(n: usize, one_cycles: &[usize]) -> Vec<bool> {
+        let mut neighbors = vec![false; one_cycles.len()];
+        let mut permutation = (0..n as u8).collect::<Vec<_>>();
+
+        for _ in 0..n {
+            let mut two_apart = permutation[2..].to_vec();
+            two_apart.extend([permutation[1], permutation[0]]);
+
+            let one_cycle = one_cycles[Lehmer::from_permutation(&two_apart).to_decimal()];
+
+            for (i, c) in one_cycles.iter().enumerate() {
+                if *c == one_cycle && *c != one_cycles[0] {
+                    neighbors[i] = true;
+                }
+            }
+
+            permutation.rotate_left(1);
+        }
+
+        neighbors
+    }
+
+    fn triangle(&self, n: usize) -> usize {
+        n * (n + 1) / 2
+    }
+
+    fn ready_index(&self, a: usize, b: usize, missing: usize, needed: usize) -> usize {
+        let state = self.triangle(missing) + needed;
+        (state * (self.symbols + 1) + a) * (self.neighborhood + 1) + b
+    }
+
+    fn not_ready_index(&self, until_ready: usize, missing: usize, needed: usize) -> usize {
+        let state = self.triangle(missing) + needed;
+        state * (self.symbols - 2) + until_ready - 1
+    }
+
+    // The permutation the candidate completes next is the identity, or one in
+    // its 1-cycle, since the tail is relabelled into ascending order.
+    fn distance(&self, problem: &Problem, candidate: &Candidate, perms: usize, goal: usize) -> usize {
+        let missing = self.factorial - perms;
+        let needed = goal - perms;
+        let until_ready = candidate.symbols_until_ready(problem);
+
+        if until_ready > 0 {
+            return self.not_ready[self.not_ready_index(until_ready, missing, needed)] as usize;
+        }
+
+        let (mut in_one_cycle, mut in_neighbors) = (0, 0);
+
+        for index in candidate.permutation_indexes(problem) {
+            if self.one_cycles[index] == self.one_cycles[0] {
+                in_one_cycle += 1;
+            } else if self.neighbors[index] {
+                in_neighbors += 1;
+            }
+        }
+
+        let a = self.symbols - in_one_cycle;
+        let b = self.neighborhood - in_neighbors;
+
+        self.ready[self.ready_index(a, b, missing, needed)] as usize
+    }
+}
+
+impl HeuristicFn for PatternDatabase {
+    fn cost(&self, problem: &Problem, candidate: &Candidate, _search_depth: usize) -> usize {
+        let perms = candidate.number_of_permutations(problem);
+
+        match perms < self.goal {
+            true => self.distance(problem, candidate, perms, self.goal),
+            false => 0,
+        }
+    }
+
+    fn learn(&mut self, heuristic: &Heuristic) {
+        self.goal = heuristic.next_goal();
+    }
+}
+
+fn to_bytes(costs: &[u16]) -> Vec<u8> {
+    costs.iter().flat_map(|c| c.to_le_bytes()).collect()
+}
+
+fn from_bytes(bytes: &[u8]) -> Vec<u16> {
+    bytes.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect()
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod test;
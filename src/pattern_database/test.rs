@@ -0,0 +1,228 @@
+use super::*;
+use crate::admissibility::{Admissibility, Kind};
+use crate::closed_set::ClosedSet;
+use crate::incremental::Incremental;
+use crate::open_set::OpenSet;
+use crate::published::Published;
+use crate::search::Search;
+use std::{env, fs};
+
+type Subject = PatternDatabase;
+
+lazy_static! {
+    static ref THREE: Problem = Problem::new(3);
+    static ref FOUR: Problem = Problem::new(4);
+    static ref DATABASE: Subject = Subject::build(&FOUR);
+}
+
+fn path(name: &str) -> std::path::PathBuf {
+    env::temp_dir().join(format!("supermutation-{}-{}.pdb", name, std::process::id()))
+}
+
+fn cost(subject: &Subject, a: usize, b: usize, missing: usize, needed: usize) -> u16 {
+    subject.ready[subject.ready_index(a, b, missing, needed)]
+}
+
+mod one_cycles {
+    use super::*;
+
+    #[test]
+    fn it_groups_the_permutations_into_one_cycles_of_n() {
+        let one_cycles = Subject::one_cycles(4, 24);
+
+        for i in 0..24 {
+            assert_eq!(one_cycles.iter().filter(|c| **c == one_cycles[i]).count(), 4);
+        }
+    }
+
+    #[test]
+    fn it_puts_the_rotations_of_the_identity_in_its_one_cycle() {
+        let one_cycles = Subject::one_cycles(4, 24);
+        let index = |p: &[u8]| Lehmer::from_permutation(p).to_decimal();
+
+        assert_eq!(one_cycles[index(&[1, 2, 3, 0])], one_cycles[0]);
+        assert_eq!(one_cycles[index(&[3, 0, 1, 2])], one_cycles[0]);
+        assert_ne!(one_cycles[index(&[1, 0, 2, 3])], one_cycles[0]);
+    }
+}
+
+mod neighbors {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_one_cycles_a_weight_two_edge_away_from_the_identity() {
+        let one_cycles = Subject::one_cycles(4, 24);
+        let neighbors = Subject::neighbors(4, &one_cycles);
+        let index = |p: &[u8]| Lehmer::from_permutation(p).to_decimal();
+
+        assert!(neighbors[index(&[2, 3, 1, 0])]);
+        assert!(neighbors[index(&[0, 2, 3, 1])]);
+        assert!(!neighbors[0]);
+        assert_eq!(neighbors.iter().filter(|n| **n).count(), 16);
+    }
+
+    #[test]
+    fn it_finds_the_other_one_cycle_for_three_symbols() {
+        let one_cycles = Subject::one_cycles(3, 6);
+        let neighbors = Subject::neighbors(3, &one_cycles);
+
+        assert_eq!(neighbors.iter().filter(|n| **n).count(), 3);
+    }
+}
+
+mod build {
+    use super::*;
+
+    #[test]
+    fn it_costs_one_symbol_per_permutation_from_the_current_one_cycle() {
+        assert_eq!(cost(&DATABASE, 4, 8, 20, 1), 1);
+        assert_eq!(cost(&DATABASE, 4, 8, 20, 4), 4);
+    }
+
+    #[test]
+    fn it_costs_two_symbols_to_move_to_a_neighboring_one_cycle() {
+        assert_eq!(cost(&DATABASE, 0, 8, 20, 1), 2);
+        assert_eq!(cost(&DATABASE, 0, 8, 20, 4), 5);
+        assert_eq!(cost(&DATABASE, 1, 8, 20, 6), 8);
+    }
+
+    #[test]
+    fn it_costs_three_symbols_to_move_to_any_other_one_cycle() {
+        assert_eq!(cost(&DATABASE, 0, 0, 12, 1), 3);
+        assert_eq!(cost(&DATABASE, 0, 0, 12, 4), 6);
+    }
+
+    #[test]
+    fn it_adds_the_symbols_until_ready_and_one_more() {
+        let index = DATABASE.not_ready_index(2, 20, 1);
+        assert_eq!(DATABASE.not_ready[index], 2 + 1);
+    }
+
+    #[test]
+    fn it_costs_the_published_distance_from_the_seed_to_every_permutation() {
+        let table = Published::max_perms_per_waste(3).unwrap();
+        let distances = Published::distances(table);
+
+        let subject = Subject::build(&THREE);
+        let all = distances[distances.len() - 1];
+
+        assert_eq!(cost(&subject, 2, 3, 5, 5) as usize, all);
+    }
+}
+
+mod cost {
+    use super::*;
+
+    #[test]
+    fn it_is_zero_once_the_candidate_has_the_goals_perms() {
+        let mut subject = DATABASE.clone();
+        let seed = Candidate::seed(&FOUR);
+
+        subject.learn(&Heuristic::seed(&FOUR));
+
+        assert_eq!(subject.cost(&FOUR, &seed, 0), 1);
+        assert_eq!(subject.cost(&FOUR, &seed.expand(&FOUR, 0), 1), 0);
+    }
+
+    #[test]
+    fn it_ignores_the_search_depth() {
+        let mut subject = DATABASE.clone();
+        let seed = Candidate::seed(&FOUR);
+
+        subject.learn(&Heuristic::from_distances(&FOUR, &[0, 1, 2, 3, 4]));
+
+        // The fifth perm is in another 1-cycle so takes two symbols.
+        assert_eq!(subject.cost(&FOUR, &seed, 0), 6);
+        assert_eq!(subject.cost(&FOUR, &seed, 9), 6);
+    }
+
+    #[test]
+    fn it_never_overestimates_for_three_symbols() {
+        let admissibility = Admissibility::new(&THREE, 6);
+        let violations = admissibility.check(&mut Subject::build(&THREE));
+
+        assert!(violations.iter().all(|v| v.kind() != Kind::Overestimate));
+    }
+
+    #[test]
+    fn it_never_overestimates_for_four_symbols_up_to_the_target() {
+        let admissibility = Admissibility::new(&FOUR, 10);
+        let violations = admissibility.check(&mut DATABASE.clone());
+
+        assert!(violations.iter().all(|v| v.kind() != Kind::Overestimate));
+    }
+
+    #[test]
+    fn it_finds_the_published_distances_when_combined_with_the_learned_heuristic() {
+        let table = Published::max_perms_per_waste(4).unwrap();
+        let published = Published::distances(table);
+
+        let search = Search::new(&FOUR, OpenSet::new(), ClosedSet::new());
+        let mut incremental = Incremental::new(Heuristic::seed(&FOUR), search);
+
+        incremental.stop_at(12);
+        incremental.use_heuristic_fn(Box::new(Heuristic::seed(&FOUR).max(DATABASE.clone())));
+
+        let distance = incremental.shortest_path(Candidate::seed(&FOUR), |_, _, _, _, _, _| {});
+        assert_eq!(distance, Some(published[12 - 1]));
+    }
+
+    #[test]
+    fn it_expands_fewer_candidates_than_the_learned_heuristic_alone_for_five_symbols() {
+        let problem = Problem::new(5);
+        let database = Subject::build(&problem);
+
+        let expansions = |estimate: Box<dyn HeuristicFn>| {
+            let search = Search::new(&problem, OpenSet::new(), ClosedSet::new());
+            let mut incremental = Incremental::new(Heuristic::seed(&problem), search);
+
+            incremental.stop_at(60);
+            incremental.use_heuristic_fn(estimate);
+
+            let distance = incremental.shortest_path(Candidate::seed(&problem), |_, _, _, _, _, _| {});
+            (distance, incremental.search().expansions())
+        };
+
+        let (learned_distance, learned) = expansions(Box::new(Heuristic::seed(&problem)));
+        let (distance, combined) = expansions(Box::new(Heuristic::seed(&problem).max(database)));
+
+        assert_eq!(distance, learned_distance);
+        assert!(combined < learned, "{} expansions with the database, {} without", combined, learned);
+    }
+}
+
+mod save {
+    use super::*;
+
+    #[test]
+    fn it_writes_a_file_that_can_be_loaded_back() {
+        let path = path("save");
+
+        DATABASE.save(&path).unwrap();
+        let loaded = Subject::load(&FOUR, &path).unwrap();
+
+        assert_eq!(&loaded, &*DATABASE);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_refuses_a_database_for_a_different_number_of_symbols() {
+        let path = path("symbols");
+
+        Subject::build(&THREE).save(&path).unwrap();
+        let error = Subject::load(&FOUR, &path).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn it_refuses_a_file_that_is_not_a_database() {
+        let path = path("magic");
+
+        fs::write(&path, b"not a database").unwrap();
+
+        assert!(Subject::load(&FOUR, &path).is_err());
+        fs::remove_file(path).unwrap();
+    }
+}
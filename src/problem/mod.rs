@@ -0,0 +1,107 @@
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use crate::symmetry::Symmetry;
+use crate::utility::Utility;
+
+/// The number of symbols and everything that is precomputed from it.
+pub struct Problem {
+    symbols: usize,
+    expansions: usize,
+    factorial: usize,
+    capacity: u32,
+    symmetry: Symmetry,
+    representation: Representation,
+}
+
+/// The most 64-bit words that candidates can be stored in, enough for six symbols.
+pub const MAXIMUM_WORDS: usize = 12;
+
+/// Fewer than three symbols have no symmetry to exploit. The symmetry's
+/// mappings for eight take about 140 MB and a few seconds to precompute, but
+/// those for nine would take gigabytes.
+pub const SUPPORTED_SYMBOLS: RangeInclusive<usize> = 3..=8;
+
+/// How candidates store their bits. Fixed width words are faster to expand but
+/// only fit up to six symbols.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Representation {
+    Roaring,
+    Words,
+}
+
+impl FromStr for Representation {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "roaring" => Ok(Representation::Roaring),
+            "words" => Ok(Representation::Words),
+            _ => Err(format!("unknown representation '{}'", name)),
+        }
+    }
+}
+
+impl Problem {
+    /// Uses fixed width words for the candidates if they fit.
+    pub fn new(symbols: usize) -> Self {
+        let representation = match Self::words_for(symbols) <= MAXIMUM_WORDS {
+            true => Representation::Words,
+            false => Representation::Roaring,
+        };
+
+        Self::with_representation(symbols, representation)
+    }
+
+    pub fn with_representation(symbols: usize, representation: Representation) -> Self {
+        let expansions = symbols - 1;
+        let factorial = Utility::factorial(symbols);
+        let capacity = Self::capacity_for(symbols);
+        let symmetry = Symmetry::precompute(symbols);
+
+        if representation == Representation::Words {
+            assert!(Self::words_for(symbols) <= MAXIMUM_WORDS, "too many symbols to store in words");
+        }
+
+        Self { symbols, expansions, factorial, capacity, symmetry, representation }
+    }
+
+    fn capacity_for(symbols: usize) -> u32 {
+        (Utility::factorial(symbols) + symbols - 2) as u32
+    }
+
+    fn words_for(symbols: usize) -> usize {
+        (Self::capacity_for(symbols) as usize).div_ceil(64)
+    }
+
+    pub fn symbols(&self) -> usize {
+        self.symbols
+    }
+
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+
+    pub fn factorial(&self) -> usize {
+        self.factorial
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.capacity
+    }
+
+    pub fn symmetry(&self) -> &Symmetry {
+        &self.symmetry
+    }
+
+    pub fn representation(&self) -> Representation {
+        self.representation
+    }
+
+    /// The number of 64-bit words the candidates need if they're stored in words.
+    pub fn words(&self) -> usize {
+        Self::words_for(self.symbols)
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,85 @@
+use super::*;
+
+type Subject = Problem;
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn it_sets_the_number_of_symbols() {
+        assert_eq!(Subject::new(4).symbols(), 4);
+        assert_eq!(Subject::new(5).symbols(), 5);
+    }
+
+    #[test]
+    fn it_sets_the_number_of_expansions_to_one_less_than_the_number_of_symbols() {
+        assert_eq!(Subject::new(4).expansions(), 3);
+        assert_eq!(Subject::new(5).expansions(), 4);
+    }
+
+    #[test]
+    fn it_sets_the_factorial_of_the_number_of_symbols() {
+        assert_eq!(Subject::new(4).factorial(), 24);
+        assert_eq!(Subject::new(5).factorial(), 120);
+    }
+
+    #[test]
+    fn it_sets_the_capacity_to_fit_the_permutations_and_the_counter_bits() {
+        assert_eq!(Subject::new(4).capacity(), 24 + 2);
+        assert_eq!(Subject::new(5).capacity(), 120 + 3);
+    }
+
+    #[test]
+    fn it_can_build_problems_with_different_numbers_of_symbols_side_by_side() {
+        let problems = (3..=7).map(Subject::new).collect::<Vec<_>>();
+        let factorials = problems.iter().map(|p| p.factorial()).collect::<Vec<_>>();
+
+        assert_eq!(factorials, &[6, 24, 120, 720, 5040]);
+    }
+}
+
+mod representation {
+    use super::*;
+
+    #[test]
+    fn it_stores_candidates_in_words_if_they_fit() {
+        assert_eq!(Subject::new(4).representation(), Representation::Words);
+        assert_eq!(Subject::new(6).representation(), Representation::Words);
+        assert_eq!(Subject::new(7).representation(), Representation::Roaring);
+    }
+
+    #[test]
+    fn it_can_be_chosen() {
+        let subject = Subject::with_representation(5, Representation::Roaring);
+        assert_eq!(subject.representation(), Representation::Roaring);
+    }
+
+    #[test]
+    #[should_panic]
+    fn it_panics_if_the_candidates_do_not_fit_in_words() {
+        Subject::with_representation(7, Representation::Words);
+    }
+}
+
+mod words {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_number_of_words_that_fit_the_capacity() {
+        assert_eq!(Subject::new(3).words(), 1);
+        assert_eq!(Subject::new(4).words(), 1);
+        assert_eq!(Subject::new(5).words(), 2);
+        assert_eq!(Subject::new(6).words(), 12);
+    }
+}
+
+mod from_str {
+    use super::*;
+
+    #[test]
+    fn it_parses_the_name_of_a_representation() {
+        assert_eq!("roaring".parse(), Ok(Representation::Roaring));
+        assert_eq!("words".parse(), Ok(Representation::Words));
+        assert!("bits".parse::<Representation>().is_err());
+    }
+}
@@ -0,0 +1,51 @@
+/// Results from the literature for small numbers of symbols.
+pub struct Published {
+
+}
+
+// The maximum number of permutations that fit into a string with the given
+// number of wasted characters (the index). A character is wasted if it does not
+// complete a new permutation. These tables are from Nathaniel Johnston's blog:
+// http://www.njohnston.ca/2014/08/all-minimal-superpermutations-on-five-symbols-have-been-found/
+
+const THREE_SYMBOLS: &[usize] = &[3, 6];
+
+const FOUR_SYMBOLS: &[usize] = &[4, 8, 12, 14, 18, 20, 24];
+
+const FIVE_SYMBOLS: &[usize] = &[
+    5, 10, 15, 20, 23, 28, 33, 36, 41, 46, 49, 53, 58, 62, 66,
+    70, 74, 79, 83, 87, 92, 96, 99, 103, 107, 111, 114, 116, 118, 120,
+];
+
+impl Published {
+    pub fn max_perms_per_waste(symbols: usize) -> Option<&'static [usize]> {
+        match symbols {
+            3 => Some(THREE_SYMBOLS),
+            4 => Some(FOUR_SYMBOLS),
+            5 => Some(FIVE_SYMBOLS),
+            _ => None,
+        }
+    }
+
+    /// Converts a table of max perms per waste into the shortest distance to
+    /// reach each number of perms. The distance doesn't count the symbols in
+    /// the starting permutation, so every perm after the first costs one symbol
+    /// plus however many symbols need to be wasted to fit it in.
+    pub fn distances(max_perms_per_waste: &[usize]) -> Vec<usize> {
+        let mut distances = vec![];
+        let mut wasted = 0;
+
+        for perms in 1..=*max_perms_per_waste.last().unwrap_or(&0) {
+            while max_perms_per_waste[wasted] < perms {
+                wasted += 1;
+            }
+
+            distances.push(perms - 1 + wasted);
+        }
+
+        distances
+    }
+}
+
+#[cfg(test)]
+mod test;
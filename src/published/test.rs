@@ -0,0 +1,61 @@
+use super::*;
+
+type Subject = Published;
+
+mod max_perms_per_waste {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_published_table_for_the_number_of_symbols() {
+        assert_eq!(Subject::max_perms_per_waste(3), Some(&[3, 6][..]));
+        assert_eq!(Subject::max_perms_per_waste(4), Some(&[4, 8, 12, 14, 18, 20, 24][..]));
+        assert_eq!(Subject::max_perms_per_waste(5).unwrap().len(), 30);
+    }
+
+    #[test]
+    fn it_returns_none_if_there_is_no_published_table() {
+        assert_eq!(Subject::max_perms_per_waste(6), None);
+        assert_eq!(Subject::max_perms_per_waste(7), None);
+    }
+
+    #[test]
+    fn it_ends_with_every_permutation_for_the_known_minimal_superpermutations() {
+        // The minimal superpermutations have lengths 9, 33 and 153.
+        for (symbols, length) in &[(3, 9), (4, 33), (5, 153)] {
+            let table = Subject::max_perms_per_waste(*symbols).unwrap();
+            let factorial = (1..=*symbols).product::<usize>();
+
+            let wasted = table.len() - 1;
+            let expected = symbols + (factorial - 1) + wasted;
+
+            assert_eq!(*table.last().unwrap(), factorial);
+            assert_eq!(expected, *length);
+        }
+    }
+}
+
+mod distances {
+    use super::*;
+
+    #[test]
+    fn it_converts_max_perms_per_waste_to_the_shortest_distance_for_each_number_of_perms() {
+        let table = Subject::max_perms_per_waste(4).unwrap();
+        let distances = Subject::distances(table);
+
+        //                          perms:  1  2  3  4  5  6  7  8  9  10  11  12  13  14
+        assert_eq!(&distances[0..14], &[0, 1, 2, 3, 5, 6, 7, 8, 10, 11, 12, 13, 15, 16]);
+
+        assert_eq!(distances.len(), 24);
+        assert_eq!(distances.last(), Some(&29));
+    }
+
+    #[test]
+    fn it_returns_a_distance_of_zero_for_the_starting_permutation() {
+        assert_eq!(Subject::distances(&[3, 6]), &[0, 1, 2, 4, 5, 6]);
+    }
+
+    #[test]
+    fn it_returns_nothing_for_an_empty_table() {
+        assert_eq!(Subject::distances(&[]), Vec::<usize>::new());
+    }
+}
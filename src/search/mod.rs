@@ -1,89 +1,757 @@
-use super::EXPANSIONS;
+use crate::backend::Backend;
 use crate::candidate::Candidate;
 use crate::closed_set::ClosedSet;
+use crate::codec::{Encoder, Decoder};
+use crate::events::Events;
 use crate::open_set::OpenSet;
 use crate::heuristic::Heuristic;
+use crate::heuristic_fn::HeuristicFn;
+use crate::limits::Limits;
+use crate::parents::Parents;
+use crate::path::Path;
+use crate::problem::Problem;
+use crate::shard::{Command, Report, Shard, Worker};
+use crate::stats::{Phase, SearchStats};
+use crate::symmetry::Symmetry;
+use std::cmp::Reverse;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering::Relaxed};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Instant;
 
-pub struct Search {
+/// An A* search that can be continued towards successive goals.
+///
+/// With a weight above one it's ARA*. Candidates whose g-cost improves after
+/// they were expanded aren't reopened but kept aside as inconsistent, and are
+/// only merged back into the open set when the weight changes. The closed set
+/// keeps the g-costs so nothing else is searched again.
+pub struct Search<'a> {
+    problem: &'a Problem,
     open_set: OpenSet,
+    inconsistent: OpenSet,
     closed_set: ClosedSet,
+    parents: Parents,
+    shards: Vec<Shard>,
+    labels: Option<Vec<u8>>,
     heuristic: Heuristic,
+    estimate: Box<dyn HeuristicFn>,
+    goal: Option<Candidate>,
+    lower_bound: Option<usize>,
+    error: Option<io::Error>,
+    interrupt: Arc<AtomicBool>,
+    limits: Limits,
+    stats: SearchStats,
+    events: Option<Events>,
+    threads: usize,
+    weight: f64,
+    pause: Option<Instant>,
+    paused: bool,
 }
 
-impl Search {
-    pub fn new(open_set: OpenSet, closed_set: ClosedSet) -> Self {
-        Self { open_set, closed_set, heuristic: Heuristic::seed() }
+impl<'a> Search<'a> {
+    pub fn new(problem: &'a Problem, open_set: OpenSet, closed_set: ClosedSet) -> Self {
+        Self::with_parents(problem, open_set, closed_set, Parents::new())
+    }
+
+    /// Links candidates to their parents with these rather than in memory,
+    /// e.g. so that they can be spilled to disk with the open and closed sets.
+    pub fn with_parents(problem: &'a Problem, open_set: OpenSet, closed_set: ClosedSet, parents: Parents) -> Self {
+        let heuristic = Heuristic::seed(problem);
+
+        Self::from_parts(problem, open_set, closed_set, parents, heuristic, None)
+    }
+
+    fn from_parts(problem: &'a Problem, open_set: OpenSet, closed_set: ClosedSet, parents: Parents, heuristic: Heuristic, goal: Option<Candidate>) -> Self {
+        let interrupt = Arc::new(AtomicBool::new(false));
+        let limits = Limits::none();
+        let estimate = Box::new(heuristic.clone());
+
+        Self { problem, open_set, inconsistent: OpenSet::new(), closed_set, parents, shards: vec![], labels: None, heuristic, estimate, goal, lower_bound: None, error: None, interrupt, limits, stats: SearchStats::new(), events: None, threads: 1, weight: 1.0, pause: None, paused: false }
     }
 
     pub fn seed(&mut self, candidate: Candidate) {
-        self.open_set.add(candidate, 1, 0);
+        let seeded = self.unshard().and_then(|_| self.seed_path(candidate, &[]));
+        self.stop_on_error(seeded);
+    }
+
+    /// Seeds the search with the candidate at the end of the string and records
+    /// the path to it so that the strings of later paths start with it.
+    pub fn seed_from_string(&mut self, string: &str) -> Result<(), String> {
+        let problem = self.problem;
+        let symbols = Candidate::canonical_symbols(problem, string)?;
+        let labels = Candidate::first_permutation(problem, string)?;
+
+        let seeded = self.unshard().and_then(|_| self.seed_path(Candidate::seed(problem), &symbols));
+        self.stop_on_error(seeded);
+        self.labels = Some(labels);
+
+        Ok(())
+    }
+
+    fn seed_path(&mut self, mut candidate: Candidate, symbols: &[usize]) -> io::Result<()> {
+        self.parents.seed(candidate.clone())?;
+
+        for (search_depth, symbol) in symbols.iter().enumerate() {
+            let neighbor = candidate.expand(self.problem, *symbol);
+
+            self.parents.add(&neighbor, search_depth + 1, candidate.fingerprint(), *symbol)?;
+            candidate = neighbor;
+        }
+
+        self.open_set.add(candidate, symbols.len() + 1, symbols.len())
     }
 
+    /// Returns the shortest distance to a candidate with the goal number of
+    /// perms, or None if it was interrupted.
     pub fn shortest_path(&mut self, goal: usize) -> Option<usize> {
+        self.paused = false;
+
+        if self.error.is_some() {
+            return None;
+        }
+
+        let distance = match self.threads {
+            1 => self.shortest_path_serially(goal),
+            _ => self.shortest_path_in_parallel(goal),
+        };
+
+        if let Some(distance) = distance {
+            self.lower_bound = match self.weight == 1.0 {
+                true => Some(distance),
+                false => self.minimum_unweighted_f_cost().map(|f| f.min(distance)),
+            };
+        }
+
+        self.stats.record_memory(self.size_in_bytes());
+
+        distance
+    }
+
+    // The memory used by the sets and parents, including the shards'. Each
+    // keeps count as it changes so this doesn't walk through the candidates.
+    fn size_in_bytes(&self) -> usize {
+        let size = |open_sets: [&OpenSet; 2], closed_set: &ClosedSet, parents: &Parents| {
+            open_sets.iter().map(|o| o.size_in_bytes()).sum::<usize>() + closed_set.size_in_bytes() + parents.size_in_bytes()
+        };
+
+        let shards = self.shards.iter().map(|s| size([&s.open_set, &s.inconsistent], &s.closed_set, &s.parents));
+
+        size([&self.open_set, &self.inconsistent], &self.closed_set, &self.parents) + shards.sum::<usize>()
+    }
+
+    fn shortest_path_serially(&mut self, goal: usize) -> Option<usize> {
+        let unsharded = self.unshard();
+        self.stop_on_error(unsharded)?;
+
+        let start = Instant::now();
+        let distance = self.expand_serially(goal);
+
+        self.stats.record_time(Phase::Expanding, start.elapsed());
+        self.stop_on_error(distance).flatten()
+    }
+
+    fn expand_serially(&mut self, goal: usize) -> io::Result<Option<usize>> {
+        let searching_again = self.searching_again(goal);
+        let problem = self.problem;
         let open_set = &mut self.open_set;
+        let inconsistent = &mut self.inconsistent;
         let closed_set = &mut self.closed_set;
-        let mut reached_goal = false;
+        let parents = &mut self.parents;
+        let interrupt = &self.interrupt;
+        let limits = &self.limits;
+        let stats = &mut self.stats;
+        let events = &self.events;
+        let mut reached_goal = None;
+        let mut previous_f_cost = None;
+        let mut checked = None;
+
+        // Only count the mappings chosen by the expansions below.
+        Symmetry::take_disambiguations();
+
+        while let Some(f_cost) = open_set.minimum_f_cost() {
+            if previous_f_cost != Some(f_cost) {
+                Self::emit_f_cost(events, goal, f_cost, stats, open_set.len(), closed_set.len());
+                previous_f_cost = Some(f_cost);
+            }
 
-        while let Some((candidate, search_depth)) = open_set.next() {
-            if closed_set.contains(&candidate, search_depth) {
+            // Candidates in the closed set's runs on disk are removed from the
+            // next bucket all at once, and again if more runs are written,
+            // rather than looked up as each is expanded.
+            let checking = open_set.next_costs()?.map(|costs| (costs, closed_set.runs_written()));
+
+            if checking != checked {
+                let checked_bucket = open_set.retain_next(|candidates, g_cost| closed_set.remove_closed(candidates, g_cost))?;
+                let (hits, reopenings) = checked_bucket.unwrap_or_default();
+
+                stats.record_batch(hits, reopenings);
+                checked = checking;
                 continue;
             }
 
-            for symbol in 0..*EXPANSIONS {
-                let neighbor = candidate.expand(symbol);
+            let (candidate, search_depth) = match open_set.pop()? {
+                Some(item) => item,
+                None => return Ok(None),
+            };
+
+            match closed_set.g_cost(&candidate) {
+                Some(previous) if search_depth >= previous => {
+                    stats.record_closed_set_hit();
+                    continue;
+                },
+                Some(_) if self.weight > 1.0 => {
+                    inconsistent.add(candidate, f_cost, search_depth)?;
+                    continue;
+                },
+                Some(_) => stats.record_reopening(),
+                None => {},
+            }
+
+            // Leave a candidate that reached the goal with a higher weight in
+            // the open set to be expanded once the next goal is searched for.
+            if searching_again && candidate.number_of_permutations(problem) == goal {
+                open_set.add(candidate.clone(), f_cost, search_depth)?;
+                self.goal = Some(candidate);
+                return Ok(Some(search_depth));
+            }
+
+            let fingerprint = candidate.fingerprint();
+
+            for symbol in 0..problem.expansions() {
+                let neighbor = candidate.expand(problem, symbol);
+
+                stats.record_generated(1);
+                stats.record_disambiguations(Symmetry::take_disambiguations());
 
                 if closed_set.contains(&neighbor, search_depth + 1) {
+                    stats.record_closed_set_hit();
                     continue;
                 }
 
-                let perms = neighbor.number_of_permutations();
+                let perms = neighbor.number_of_permutations(problem);
 
-                if perms == goal {
-                    reached_goal = true;
+                if perms == goal && reached_goal.is_none() {
+                    reached_goal = Some(neighbor.clone());
                 }
 
                 let g_cost = search_depth + 1;
-                let h_cost = self.heuristic.cost(perms, g_cost);
+                let h_cost = self.estimate.weighted_cost(problem, &neighbor, g_cost, self.weight);
                 let f_cost = g_cost + h_cost;
 
-                open_set.add(neighbor, f_cost, g_cost);
+                parents.add(&neighbor, g_cost, fingerprint, symbol)?;
+                open_set.add(neighbor, f_cost, g_cost)?;
+            }
+
+            closed_set.add(candidate, search_depth)?;
+            stats.record_expansion();
+
+            if reached_goal.is_some() {
+                self.goal = reached_goal;
+                return Ok(Some(search_depth + 1));
+            }
+
+            // Stop between expansions so the sets are left in a state that
+            // can be checkpointed and resumed. Reaching a limit is treated
+            // the same as being interrupted.
+            if interrupt.load(Relaxed) || limits.exceeded(stats.expansions()) {
+                interrupt.store(true, Relaxed);
+                return Ok(None);
+            }
+
+            if self.pause.is_some_and(|p| Instant::now() >= p) {
+                self.paused = true;
+                return Ok(None);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Splits the sets into a shard per thread by hashing candidates. The
+    /// shards are kept for later goals. Each round, every thread expands its
+    /// candidates in the bucket a serial search would expand next and then adds
+    /// the neighbors it owns, so the goal is reached at the same distance.
+    fn shortest_path_in_parallel(&mut self, goal: usize) -> Option<usize> {
+        if self.shards.len() != self.threads {
+            let unsharded = self.unshard();
+            self.stop_on_error(unsharded)?;
+
+            // The inconsistent candidates are searched again rather than split.
+            let merged = self.merge_inconsistent();
+            self.stop_on_error(merged)?;
+
+            let start = Instant::now();
+            let open_set = mem::take(&mut self.open_set);
+            let closed_set = mem::take(&mut self.closed_set);
+            let parents = mem::take(&mut self.parents);
+
+            let shards = Shard::split(open_set, closed_set, parents, self.threads);
+            self.stats.record_time(Phase::Sharding, start.elapsed());
+            self.shards = self.stop_on_error(shards)?;
+        }
+
+        let problem = self.problem;
+        let estimate = &*self.estimate;
+        let weight = self.weight;
+        let popped_goal = Some(goal).filter(|_| self.searching_again(goal));
+        let shards = &mut self.shards;
+        let threads = shards.len();
+        let interrupt = &self.interrupt;
+        let limits = &self.limits;
+        let events = &self.events;
+        let pause = self.pause;
+        let stats = &mut self.stats;
+        let mut paused = false;
+
+        let (outboxes, inboxes): (Vec<_>, Vec<_>) = (0..threads).map(|_| channel()).unzip();
+
+        let reached_goal = thread::scope(|scope| {
+            let mut workers = vec![];
+
+            for (index, (shard, inbox)) in shards.iter_mut().zip(inboxes).enumerate() {
+                let (commands, command_receiver) = channel();
+                let (report_sender, reports) = channel();
+                let worker = Worker { index, commands: command_receiver, reports: report_sender, inbox, outboxes: outboxes.clone() };
+
+                workers.push((commands, reports));
+                scope.spawn(move || shard.work(problem, estimate, weight, goal, popped_goal, worker));
             }
 
-            closed_set.add(candidate, search_depth);
+            let broadcast = |command: Command| for (commands, _) in &workers {
+                commands.send(command).expect("a shard's thread panicked");
+            };
+
+            // Each thread has its own channel so that a panic isn't waited on.
+            let reports = || workers.iter().map(|(_, reports)| reports.recv().expect("a shard's thread panicked"));
+
+            let receive_statuses = || reports().map(|report| match report {
+                Report::Received(status) => status,
+                Report::Expanded(..) => unreachable!(),
+            }).collect::<io::Result<Vec<_>>>();
+
+            let mut statuses = receive_statuses()?;
+            let mut previous_f_cost = None;
+            let mut expanded = 0;
+
+            loop {
+                // The lowest f-cost, then the highest g-cost, like OpenSet::next.
+                let (f_cost, g_cost) = match statuses.iter().filter_map(|s| s.next_costs).min_by_key(|&(f, g)| (f, Reverse(g))) {
+                    Some(costs) => costs,
+                    None => break Ok(None),
+                };
 
-            if reached_goal {
-                return Some(search_depth + 1);
+                if previous_f_cost != Some(f_cost) {
+                    if events.is_some() {
+                        let open_set_len = statuses.iter().map(|s| s.open_set_len).sum();
+                        let closed_set_len = statuses.iter().map(|s| s.closed_set_len).sum();
+                        let mut stats = stats.clone();
+
+                        for status in &statuses {
+                            stats.combine(&status.stats);
+                        }
+
+                        Self::emit_f_cost(events, goal, f_cost, &stats, open_set_len, closed_set_len);
+                    }
+
+                    previous_f_cost = Some(f_cost);
+                }
+
+                let start = Instant::now();
+                let mut reached_goals = vec![];
+
+                broadcast(Command::Expand(f_cost, g_cost));
+
+                for report in reports() {
+                    match report {
+                        Report::Expanded(expanded_by_shard) => {
+                            let (expansions, reached_goal) = expanded_by_shard?;
+
+                            expanded += expansions;
+                            reached_goals.extend(reached_goal);
+                        },
+                        Report::Received(_) => unreachable!(),
+                    }
+                }
+
+                stats.record_time(Phase::Expanding, start.elapsed());
+                let start = Instant::now();
+
+                broadcast(Command::Receive);
+                statuses = receive_statuses()?;
+
+                stats.record_time(Phase::Exchanging, start.elapsed());
+
+                reached_goals.extend(statuses.iter_mut().filter_map(|s| s.reached_goal.take()));
+
+                if let Some(reached_goal) = reached_goals.into_iter().min() {
+                    break Ok(Some(reached_goal));
+                }
+
+                // Stop between rounds, like the serial search does between
+                // expansions. Reaching a limit is the same as being interrupted.
+                if interrupt.load(Relaxed) || limits.exceeded(stats.expansions() + expanded) {
+                    interrupt.store(true, Relaxed);
+                    break Ok(None);
+                }
+
+                if pause.is_some_and(|p| Instant::now() >= p) {
+                    paused = true;
+                    break Ok(None);
+                }
             }
+        });
+
+        for shard in shards.iter_mut() {
+            stats.combine(&mem::take(&mut shard.stats));
         }
 
-        None
+        self.paused = paused;
+
+        let (distance, candidate) = self.stop_on_error(reached_goal).flatten()?;
+        self.goal = Some(candidate);
+
+        Some(distance)
+    }
+
+    // Only a goal that was already reached, e.g. with a higher weight, can have
+    // candidates in the open set that reached it. Otherwise goals are only
+    // looked for as they're generated.
+    fn searching_again(&self, goal: usize) -> bool {
+        self.goal.as_ref().is_some_and(|c| c.number_of_permutations(self.problem) == goal)
+    }
+
+    // A lower bound on the distance to the goal if the heuristic is admissible
+    // because some candidate on a shortest path is always in the open set, or
+    // kept aside as inconsistent, with its shortest distance. Spilled
+    // candidates are bounded by their bucket's weighted f-cost rather than read
+    // back.
+    fn minimum_unweighted_f_cost(&self) -> Option<usize> {
+        let problem = self.problem;
+        let estimate = &*self.estimate;
+        let weight = self.weight;
+        let open_sets = self.open_sets();
+
+        open_sets.flat_map(|open_set| {
+            let in_memory = open_set.iter().map(move |(candidate, _, g_cost)| g_cost + estimate.cost(problem, candidate, g_cost));
+            let spilled = open_set.minimum_spilled_f_cost().map(|f_cost| (f_cost.saturating_sub(1) as f64 / weight) as usize);
+
+            in_memory.chain(spilled)
+        }).min()
+    }
+
+    // The open sets and inconsistent candidates of the search and its shards.
+    fn open_sets(&self) -> impl Iterator<Item = &OpenSet> {
+        let shards = self.shards.iter().flat_map(|s| vec![&s.open_set, &s.inconsistent]);
+
+        vec![&self.open_set, &self.inconsistent].into_iter().chain(shards)
+    }
+
+    // Puts the candidates that were kept aside back in the open set, including
+    // the shards', so that they're searched with the new weight.
+    fn merge_inconsistent(&mut self) -> io::Result<()> {
+        let sets = Some((&mut self.open_set, &mut self.inconsistent)).into_iter();
+        let shards = self.shards.iter_mut().map(|s| (&mut s.open_set, &mut s.inconsistent));
+
+        for (open_set, inconsistent) in sets.chain(shards) {
+            open_set.extend(mem::take(inconsistent))?;
+        }
+
+        Ok(())
+    }
+
+    // Merges the shards of a parallel search back into one set of each.
+    fn unshard(&mut self) -> io::Result<()> {
+        if self.shards.is_empty() {
+            return Ok(());
+        }
+
+        let start = Instant::now();
+
+        for shard in &mut self.shards {
+            self.inconsistent.extend(mem::take(&mut shard.inconsistent))?;
+        }
+
+        let (open_set, closed_set, parents) = Shard::merge(mem::take(&mut self.shards))?;
+
+        self.open_set = open_set;
+        self.closed_set = closed_set;
+        self.parents = parents;
+
+        self.stats.record_time(Phase::Sharding, start.elapsed());
+
+        Ok(())
+    }
+
+    // An error reading or writing a set on disk stops the search like an
+    // interrupt. The sets may have lost candidates so it can't be continued.
+    fn stop_on_error<T>(&mut self, result: io::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(error) => {
+                self.error = Some(error);
+                self.interrupt.store(true, Relaxed);
+
+                None
+            },
+        }
+    }
+
+    // Emitted when the minimum f-cost of the open set changes, which is when
+    // the search has ruled out every shorter path to the goal.
+    fn emit_f_cost(events: &Option<Events>, goal: usize, f_cost: usize, stats: &SearchStats, open_set_len: usize, closed_set_len: usize) {
+        if let Some(events) = events {
+            events.emit("f_cost", &[
+                ("goal", goal.into()),
+                ("f_cost", f_cost.into()),
+                ("expansions", stats.expansions().into()),
+                ("open_set", open_set_len.into()),
+                ("closed_set", closed_set_len.into()),
+            ]);
+        }
+    }
+
+    /// The path to the goal that was reached most recently.
+    pub fn path(&self) -> Option<Path<'a>> {
+        let goal = self.goal.as_ref()?;
+
+        let unwound = match self.shards.len() {
+            0 => self.parents.unwind(goal),
+            _ => Parents::unwind_partitioned(&self.shards.iter().map(|s| &s.parents).collect::<Vec<_>>(), goal),
+        };
+
+        // Links that can't be read back from disk are as good as missing.
+        let (start, symbols) = unwound.ok().flatten()?;
+
+        Some(match &self.labels {
+            Some(labels) => Path::with_labels(self.problem, start, symbols, labels.clone()),
+            None => Path::new(self.problem, start, symbols),
+        })
+    }
+
+    /// A lower bound on the shortest distance to the goal that was reached most
+    /// recently. It's the distance itself unless the search was weighted.
+    pub fn lower_bound(&self) -> Option<usize> {
+        self.lower_bound
+    }
+
+    pub fn problem(&self) -> &'a Problem {
+        self.problem
+    }
+
+    /// Stops the search once the limits are exceeded, as if it was interrupted.
+    pub fn limit_to(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Expands candidates on this many threads. One thread searches serially.
+    pub fn use_threads(&mut self, threads: usize) {
+        self.threads = threads.max(1);
+    }
+
+    /// Multiplies the heuristic's costs by the weight and merges the
+    /// inconsistent candidates back into the open set. Call update_heuristic
+    /// afterwards to recalculate the costs of the open set.
+    pub fn weight_by(&mut self, weight: f64) {
+        self.weight = weight;
+
+        let merged = self.merge_inconsistent();
+        self.stop_on_error(merged);
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// The number of candidates expanded since the search was created or read.
+    pub fn expansions(&self) -> usize {
+        self.stats.expansions()
+    }
+
+    pub fn stats(&self) -> &SearchStats {
+        &self.stats
+    }
+
+    /// Estimates costs with this rather than the learned heuristic, which it
+    /// learns from as the search goes, and recalculates the costs of the open
+    /// set. It isn't saved in checkpoints.
+    pub fn use_heuristic_fn(&mut self, estimate: Box<dyn HeuristicFn>) {
+        let heuristic = self.heuristic.clone();
+
+        self.estimate = estimate;
+        self.update_heuristic(&heuristic);
+    }
+
+    /// Writes an event each time the minimum f-cost of the open set changes.
+    pub fn emit_events_to(&mut self, events: Events) {
+        self.events = Some(events);
+    }
+
+    pub fn interrupt_flag(&self) -> Arc<AtomicBool> {
+        self.interrupt.clone()
+    }
+
+    pub fn interrupted(&self) -> bool {
+        self.interrupt.load(Relaxed)
+    }
+
+    /// The error that stopped the search, e.g. if a set on disk couldn't be
+    /// written or read back.
+    pub fn error(&self) -> Option<&io::Error> {
+        self.error.as_ref()
+    }
+
+    /// Pauses between expansions once it's past the deadline.
+    pub fn pause_at(&mut self, deadline: Option<Instant>) {
+        self.pause = deadline;
+    }
+
+    pub fn paused(&self) -> bool {
+        self.paused
     }
 
     pub fn open_set_len(&self) -> usize {
-        self.open_set.len()
+        self.open_set.len() + self.shards.iter().map(|s| s.open_set.len()).sum::<usize>()
     }
 
     pub fn closed_set_len(&self) -> usize {
-        self.closed_set.len()
+        self.closed_set.len() + self.shards.iter().map(|s| s.closed_set.len()).sum::<usize>()
     }
 
+    /// The closed set of a serial search. It's empty while a parallel search
+    /// has split it into shards.
+    pub fn closed_set(&self) -> &ClosedSet {
+        &self.closed_set
+    }
+
+    /// The memory used by the closed set and the parents, including those of
+    /// the shards, for each candidate in the closed set. The parents also link
+    /// the candidates in the open set so that paths can be unwound.
+    pub fn bytes_per_candidate(&self) -> f64 {
+        let shards = self.shards.iter().map(|s| s.closed_set.size_in_bytes() + s.parents.size_in_bytes());
+        let bytes = self.closed_set.size_in_bytes() + self.parents.size_in_bytes() + shards.sum::<usize>();
+
+        bytes as f64 / self.closed_set_len().max(1) as f64
+    }
+
+    /// Replaces the heuristic and recalculates the costs of the open set.
     pub fn update_heuristic(&mut self, heuristic: &Heuristic) {
-        let mut stack = vec![];
+        let problem = self.problem;
+        let weight = self.weight;
+        let start = Instant::now();
 
-        while let Some(item) = self.open_set.next() {
-            stack.push(item);
+        self.heuristic = heuristic.clone();
+        self.estimate.learn(heuristic);
+
+        let estimate = &self.estimate;
+        let sets = vec![&mut self.open_set, &mut self.inconsistent].into_iter();
+        let mut open_sets = sets.chain(self.shards.iter_mut().flat_map(|s| vec![&mut s.open_set, &mut s.inconsistent]));
+
+        let updated = open_sets.try_for_each(|open_set| open_set.update_costs(|candidate, g_cost| {
+            g_cost + estimate.weighted_cost(problem, candidate, g_cost, weight)
+        }));
+
+        self.stats.record_time(Phase::UpdatingHeuristic, start.elapsed());
+        self.stop_on_error(updated);
+    }
+
+    /// Fails once the search has stopped on an error, so that the checkpoint
+    /// from before it is kept rather than sets that may have lost candidates.
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        if let Some(error) = &self.error {
+            return Err(io::Error::new(error.kind(), format!("the search stopped on an error: {}", error)));
         }
 
-        while let Some((candidate, g_cost)) = stack.pop() {
-            let perms = candidate.number_of_permutations();
+        self.heuristic.write_to(encoder)?;
+        self.open_set.write_to(encoder)?;
+        self.inconsistent.write_to(encoder)?;
+        self.closed_set.write_to(encoder)?;
+        self.parents.write_to(encoder)?;
 
-            let h_cost = heuristic.cost(perms, g_cost);
-            let f_cost = g_cost + h_cost;
+        // A parallel search's shards are kept as they are.
+        encoder.write_usize(self.shards.len())?;
 
-            self.open_set.add(candidate, f_cost, g_cost);
+        for shard in &self.shards {
+            shard.write_to(encoder)?;
         }
 
-        self.heuristic = heuristic.clone();
+        match &self.labels {
+            Some(labels) => {
+                encoder.write_usize(1)?;
+                encoder.write_bytes(labels)?;
+            },
+            None => encoder.write_usize(0)?,
+        }
+
+        match &self.goal {
+            Some(goal) => {
+                encoder.write_usize(1)?;
+                goal.write_to(encoder)
+            },
+            None => encoder.write_usize(0),
+        }
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>, problem: &'a Problem) -> io::Result<Self> {
+        let heuristic = Heuristic::read_from(decoder)?;
+        let open_set = OpenSet::read_from(decoder)?;
+        let inconsistent = OpenSet::read_from(decoder)?;
+        let closed_set = ClosedSet::read_from(decoder)?;
+        let parents = Parents::read_from(decoder)?;
+
+        let shards = (0..decoder.read_usize()?).map(|_| Shard::read_from(decoder)).collect::<io::Result<_>>()?;
+
+        let labels = match decoder.read_usize()? {
+            0 => None,
+            _ => Some(Path::read_labels(decoder, problem)?),
+        };
+
+        let goal = match decoder.read_usize()? {
+            0 => None,
+            _ => Some(Candidate::read_from(decoder)?),
+        };
+
+        let mut search = Self::from_parts(problem, open_set, closed_set, parents, heuristic, goal);
+        search.inconsistent = inconsistent;
+        search.shards = shards;
+        search.labels = labels;
+
+        Ok(search)
+    }
+}
+
+impl<'a> Backend<'a> for Search<'a> {
+    const NAME: &'static str = "astar";
+
+    fn problem(&self) -> &'a Problem { self.problem() }
+    fn seed(&mut self, candidate: Candidate) { self.seed(candidate) }
+    fn seed_from_string(&mut self, string: &str) -> Result<(), String> { self.seed_from_string(string) }
+    fn shortest_path(&mut self, goal: usize) -> Option<usize> { self.shortest_path(goal) }
+    fn path(&self) -> Option<Path<'a>> { self.path() }
+    fn lower_bound(&self) -> Option<usize> { self.lower_bound() }
+    fn update_heuristic(&mut self, heuristic: &Heuristic) { self.update_heuristic(heuristic) }
+    fn limit_to(&mut self, limits: Limits) { self.limit_to(limits) }
+    fn weight_by(&mut self, weight: f64) { self.weight_by(weight) }
+    fn weight(&self) -> f64 { self.weight() }
+    fn expansions(&self) -> usize { self.expansions() }
+    fn stats(&self) -> &SearchStats { self.stats() }
+    fn open_set_len(&self) -> usize { self.open_set_len() }
+    fn closed_set_len(&self) -> usize { self.closed_set_len() }
+    fn emit_events_to(&mut self, events: Events) { self.emit_events_to(events) }
+    fn use_heuristic_fn(&mut self, estimate: Box<dyn HeuristicFn>) { self.use_heuristic_fn(estimate) }
+    fn interrupt_flag(&self) -> Arc<AtomicBool> { self.interrupt_flag() }
+    fn interrupted(&self) -> bool { self.interrupted() }
+    fn error(&self) -> Option<&io::Error> { self.error() }
+    fn pause_at(&mut self, deadline: Option<Instant>) { self.pause_at(deadline) }
+    fn paused(&self) -> bool { self.paused() }
+
+    fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        self.write_to(encoder)
+    }
+
+    fn read_from<R: Read>(decoder: &mut Decoder<R>, problem: &'a Problem) -> io::Result<Self> {
+        Self::read_from(decoder, problem)
     }
 }
 
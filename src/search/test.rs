@@ -1,16 +1,19 @@
-#![cfg(not(feature = "four_symbols"))]
-
 use super::*;
 use crate::candidate::Candidate;
+use std::sync::atomic::Ordering::Relaxed;
+
+type Subject<'a> = Search<'a>;
 
-type Subject = Search;
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
 
-fn subject(candidate: &Candidate) -> Subject {
+fn subject(candidate: &Candidate) -> Subject<'static> {
     let open_set = OpenSet::new();
     let closed_set = ClosedSet::new();
-    let mut subject = Subject::new(open_set, closed_set);
+    let mut subject = Subject::new(&PROBLEM, open_set, closed_set);
 
-    subject.heuristic = simplified_heuristic();
+    subject.update_heuristic(&simplified_heuristic());
     subject.seed(candidate.clone());
     subject
 }
@@ -31,23 +34,70 @@ mod seed {
         let open_set = OpenSet::new();
         let closed_set = ClosedSet::new();
 
-        let mut subject = Subject::new(open_set, closed_set);
-        let candidate = Candidate::seed();
+        let mut subject = Subject::new(&PROBLEM, open_set, closed_set);
+        let candidate = Candidate::seed(&PROBLEM);
 
         subject.seed(candidate);
         assert_eq!(subject.open_set.len(), 1);
     }
 }
 
+mod seed_from_string {
+    use super::*;
+
+    #[test]
+    fn it_adds_the_candidate_at_the_end_of_the_string_to_the_open_set() {
+        let mut subject = Subject::new(&PROBLEM, OpenSet::new(), ClosedSet::new());
+        subject.seed_from_string("0123401").unwrap();
+
+        let expected = Candidate::from_string(&PROBLEM, "0123401").unwrap();
+
+        assert_eq!(subject.open_set.len(), 1);
+        assert_eq!(subject.open_set.pop().unwrap(), Some((expected, 2)));
+    }
+
+    #[test]
+    fn it_finds_paths_that_start_with_the_string() {
+        let mut subject = Subject::new(&PROBLEM, OpenSet::new(), ClosedSet::new());
+        subject.update_heuristic(&simplified_heuristic());
+        subject.seed_from_string("01234021").unwrap();
+
+        let distance = subject.shortest_path(4).unwrap();
+        let string = subject.path().unwrap().to_string();
+
+        assert_eq!(distance, 4);
+        assert_eq!(string, "012340213");
+    }
+
+    #[test]
+    fn it_keeps_the_symbols_of_a_string_that_does_not_start_with_0123() {
+        let mut subject = Subject::new(&PROBLEM, OpenSet::new(), ClosedSet::new());
+        subject.update_heuristic(&simplified_heuristic());
+        subject.seed_from_string("43210423").unwrap();
+
+        subject.shortest_path(4).unwrap();
+
+        assert_eq!(subject.path().unwrap().to_string(), "432104231");
+    }
+
+    #[test]
+    fn it_returns_an_error_for_an_invalid_string() {
+        let mut subject = Subject::new(&PROBLEM, OpenSet::new(), ClosedSet::new());
+
+        assert!(subject.seed_from_string("0120").is_err());
+        assert_eq!(subject.open_set.len(), 0);
+    }
+}
+
 mod shortest_path {
     use super::*;
 
     #[test]
     fn it_finds_the_length_of_the_shortest_path_to_the_goal_number_of_permutations() {
-        let start = Candidate::seed();
+        let start = Candidate::seed(&PROBLEM);
         let subject = &mut subject(&start);
 
-        let mut goal = start.number_of_permutations() + 1; // shortest path:
+        let mut goal = start.number_of_permutations(&PROBLEM) + 1; // shortest path:
         assert_eq!(subject.shortest_path(goal), Some(1));  // 012340
 
         goal += 1;
@@ -65,10 +115,10 @@ mod shortest_path {
 
     #[test]
     fn it_reuses_the_open_and_closed_sets_in_between_searches() {
-        let start = Candidate::seed();
+        let start = Candidate::seed(&PROBLEM);
         let subject = &mut subject(&start);
 
-        let mut goal = start.number_of_permutations() + 1;
+        let mut goal = start.number_of_permutations(&PROBLEM) + 1;
         subject.shortest_path(goal);
 
         assert_eq!(subject.open_set.len(), 4);
@@ -83,10 +133,10 @@ mod shortest_path {
 
     #[test]
     fn it_calculates_the_correct_costs_for_the_expanded_candidates() {
-        let start = Candidate::seed();
+        let start = Candidate::seed(&PROBLEM);
         let subject = &mut subject(&start);
 
-        let goal = start.number_of_permutations() + 1;
+        let goal = start.number_of_permutations(&PROBLEM) + 1;
         subject.shortest_path(goal);
                                                      // p  f  g  h
         assert_eq!(next_perms_and_costs(subject), Some((2, 1, 1, 0)));
@@ -98,10 +148,51 @@ mod shortest_path {
 
     fn next_perms_and_costs(subject: &mut Subject) -> Option<(usize, usize, usize, usize)> {
         let f_cost = subject.open_set.minimum_f_cost()?;
-        let (candidate, g_cost) = subject.open_set.next()?;
+        let (candidate, g_cost) = subject.open_set.pop().unwrap()?;
         let h_cost = f_cost - g_cost;
 
-        Some((candidate.number_of_permutations(), f_cost, g_cost, h_cost))
+        Some((candidate.number_of_permutations(&PROBLEM), f_cost, g_cost, h_cost))
+    }
+}
+
+mod path {
+    use super::*;
+
+    #[test]
+    fn it_returns_none_if_no_goal_has_been_reached() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = subject(&start);
+
+        assert_eq!(subject.path(), None);
+    }
+
+    #[test]
+    fn it_returns_the_path_to_the_most_recently_reached_goal() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        let mut goal = start.number_of_permutations(&PROBLEM) + 1;
+        subject.shortest_path(goal);
+        assert_eq!(subject.path(), Some(Path::new(&PROBLEM, start.clone(), vec![0])));
+
+        goal += 1;
+        subject.shortest_path(goal);
+        assert_eq!(subject.path(), Some(Path::new(&PROBLEM, start.clone(), vec![0, 0])));
+    }
+
+    #[test]
+    fn it_returns_a_path_with_the_length_of_the_shortest_path() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        for subgoal in (goal - 4)..=goal {
+            let distance = subject.shortest_path(subgoal).unwrap();
+            assert_eq!(subject.path().unwrap().len(), distance);
+        }
+
+        assert_eq!(subject.path().unwrap().to_string(), "01234102341");
     }
 }
 
@@ -110,8 +201,8 @@ mod open_set_len {
 
     #[test]
     fn it_returns_the_number_of_candidates_in_the_open_set() {
-        let start = Candidate::seed();
-        let goal = start.number_of_permutations() + 1;
+        let start = Candidate::seed(&PROBLEM);
+        let goal = start.number_of_permutations(&PROBLEM) + 1;
 
         let subject = &mut subject(&start);
         assert_eq!(subject.open_set_len(), 1);
@@ -126,8 +217,8 @@ mod closed_set_len {
 
     #[test]
     fn it_returns_the_number_of_candidates_in_the_closed_set() {
-        let start = Candidate::seed();
-        let goal = start.number_of_permutations() + 1;
+        let start = Candidate::seed(&PROBLEM);
+        let goal = start.number_of_permutations(&PROBLEM) + 1;
 
         let subject = &mut subject(&start);
         assert_eq!(subject.closed_set_len(), 0);
@@ -142,7 +233,7 @@ mod update_heuristic {
 
     #[test]
     fn it_sets_the_new_heuristic_for_the_search() {
-        let start = Candidate::seed();
+        let start = Candidate::seed(&PROBLEM);
         let mut subject = subject(&start);
 
         subject.update_heuristic(&updated_heuristic());
@@ -152,7 +243,7 @@ mod update_heuristic {
 
     #[test]
     fn it_recalculates_the_open_set_costs() {
-        let start = Candidate::seed();
+        let start = Candidate::seed(&PROBLEM);
         let mut subject = subject(&start);
 
         subject.update_heuristic(&updated_heuristic());
@@ -164,14 +255,14 @@ mod update_heuristic {
 
     #[test]
     fn it_does_not_change_the_g_costs_for_nested_buckets() {
-        let start = Candidate::seed();
+        let start = Candidate::seed(&PROBLEM);
 
         let mut before = subject(&start);
-        let (_, g_cost_before) = before.open_set.next().unwrap();
+        let (_, g_cost_before) = before.open_set.pop().unwrap().unwrap();
 
         let mut after = subject(&start);
         after.update_heuristic(&updated_heuristic());
-        let (_, g_cost_after) = after.open_set.next().unwrap();
+        let (_, g_cost_after) = after.open_set.pop().unwrap().unwrap();
 
         assert_eq!(g_cost_before, g_cost_after);
 
@@ -180,8 +271,8 @@ mod update_heuristic {
 
     #[test]
     fn it_does_not_reorder_candidates_in_buckets_with_the_same_g_cost() {
-        let start = Candidate::seed();
-        let goal = start.number_of_permutations() + 5;
+        let start = Candidate::seed(&PROBLEM);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
 
         let mut subject = subject(&start);
         subject.shortest_path(goal);
@@ -194,3 +285,417 @@ mod update_heuristic {
         // TODO: move to open set tests?
     }
 }
+
+mod limit_to {
+    use super::*;
+    use crate::limits::Limits;
+
+    #[test]
+    fn it_stops_the_search_once_the_limits_are_exceeded() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        subject.limit_to(Limits::new(Some(3), None));
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        assert_eq!(subject.shortest_path(goal), None);
+        assert!(subject.interrupted());
+        assert_eq!(subject.expansions(), 3);
+    }
+}
+
+mod use_threads {
+    use super::*;
+
+    #[test]
+    fn it_finds_the_same_shortest_paths_as_the_serial_search() {
+        let start = Candidate::seed(&PROBLEM);
+        let serial = &mut subject(&start);
+        let parallel = &mut subject(&start);
+
+        parallel.use_threads(3);
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        for subgoal in (goal - 4)..=goal {
+            let distance = parallel.shortest_path(subgoal);
+
+            assert_eq!(distance, serial.shortest_path(subgoal));
+            assert_eq!(parallel.path().unwrap().len(), distance.unwrap());
+        }
+    }
+
+    #[test]
+    fn it_keeps_the_shards_for_later_goals_and_merges_them_to_continue_serially() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 1;
+
+        subject.use_threads(4);
+        subject.shortest_path(goal);
+
+        assert_eq!(subject.shards.len(), 4);
+        assert_eq!(subject.open_set_len(), 4);
+        assert_eq!(subject.closed_set_len(), 1);
+
+        assert_eq!(subject.shortest_path(goal + 1), Some(2));
+        assert_eq!(subject.shards.len(), 4);
+
+        subject.use_threads(1);
+        assert_eq!(subject.shortest_path(goal + 4), Some(6));
+        assert_eq!(subject.shards.len(), 0);
+        assert_eq!(subject.closed_set().len(), subject.closed_set_len());
+    }
+
+    #[test]
+    fn it_stops_the_search_once_the_limits_are_exceeded() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        subject.use_threads(2);
+        subject.limit_to(crate::limits::Limits::new(Some(3), None));
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        assert_eq!(subject.shortest_path(goal), None);
+        assert!(subject.interrupted());
+        assert_eq!(subject.expansions(), subject.closed_set_len());
+    }
+}
+
+mod weight_by {
+    use super::*;
+
+    #[test]
+    fn it_keeps_candidates_that_would_be_reopened_aside_until_the_weight_changes() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        // As if the start had been expanded further from the seed before.
+        subject.closed_set.add(start.clone(), 2).unwrap();
+        subject.weight_by(3.0);
+
+        assert_eq!(subject.shortest_path(goal), None);
+        assert_eq!(subject.open_set.len(), 0);
+        assert_eq!(subject.inconsistent.len(), 1);
+
+        subject.weight_by(1.0);
+        subject.update_heuristic(&simplified_heuristic());
+
+        assert_eq!(subject.inconsistent.len(), 0);
+        assert_eq!(subject.shortest_path(goal), Some(6));
+        assert_eq!(subject.stats().reopenings(), 1);
+    }
+
+    #[test]
+    fn it_multiplies_the_heuristic_costs_of_the_neighbors_by_the_weight() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        subject.weight_by(3.0);
+        subject.shortest_path(start.number_of_permutations(&PROBLEM) + 1);
+
+        let f_costs = subject.open_set.iter().map(|(_, f, _)| f).collect::<Vec<_>>();
+
+        assert_eq!(f_costs, &[1, 4, 4, 4]);
+    }
+
+    #[test]
+    fn it_finds_a_path_to_a_goal_that_was_searched_for_at_a_higher_weight() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.weight_by(3.0);
+        let weighted = subject.shortest_path(goal).unwrap();
+
+        subject.weight_by(1.0);
+        subject.update_heuristic(&simplified_heuristic());
+
+        let distance = subject.shortest_path(goal).unwrap();
+
+        assert!(distance <= weighted);
+        assert_eq!(distance, 6);
+        assert_eq!(subject.path().unwrap().len(), distance);
+    }
+
+    #[test]
+    fn it_finds_a_lower_bound_on_the_shortest_distance() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.weight_by(3.0);
+        subject.update_heuristic(&simplified_heuristic());
+
+        let weighted = subject.shortest_path(goal).unwrap();
+        let lower_bound = subject.lower_bound().unwrap();
+
+        assert!(lower_bound <= 6 && lower_bound <= weighted);
+
+        subject.weight_by(1.0);
+        subject.update_heuristic(&simplified_heuristic());
+        subject.shortest_path(goal);
+
+        assert_eq!(subject.lower_bound(), Some(6));
+    }
+}
+
+mod use_heuristic_fn {
+    use super::*;
+    use crate::heuristic_fn::Zero;
+
+    #[test]
+    fn it_finds_the_same_shortest_paths_with_another_admissible_heuristic() {
+        let start = Candidate::seed(&PROBLEM);
+        let learned = &mut subject(&start);
+        let zero = &mut subject(&start);
+
+        zero.use_heuristic_fn(Box::new(Zero));
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        for subgoal in (goal - 4)..=goal {
+            assert_eq!(zero.shortest_path(subgoal), learned.shortest_path(subgoal));
+        }
+
+        assert!(zero.expansions() > learned.expansions());
+    }
+
+    #[test]
+    fn it_uses_the_heuristic_on_several_threads() {
+        let start = Candidate::seed(&PROBLEM);
+        let serial = &mut subject(&start);
+        let parallel = &mut subject(&start);
+
+        serial.use_heuristic_fn(Box::new(Zero));
+        parallel.use_heuristic_fn(Box::new(Zero));
+        parallel.use_threads(3);
+
+        let goal = start.number_of_permutations(&PROBLEM) + 4;
+
+        for subgoal in (goal - 3)..=goal {
+            assert_eq!(parallel.shortest_path(subgoal), serial.shortest_path(subgoal));
+        }
+    }
+
+    #[test]
+    fn it_recalculates_the_open_set_costs() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        assert_eq!(subject.open_set.minimum_f_cost(), Some(1));
+
+        subject.use_heuristic_fn(Box::new(Zero));
+
+        assert_eq!(subject.open_set.minimum_f_cost(), Some(0));
+    }
+
+    #[test]
+    fn it_keeps_learning_from_the_updated_heuristic() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        subject.use_heuristic_fn(Box::new(Zero.max(simplified_heuristic())));
+        subject.update_heuristic(&updated_heuristic());
+
+        assert_eq!(subject.heuristic, updated_heuristic());
+        assert_eq!(subject.open_set.minimum_f_cost(), Some(999));
+    }
+}
+
+mod expansions {
+    use super::*;
+
+    #[test]
+    fn it_counts_the_candidates_that_have_been_expanded() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        subject.shortest_path(start.number_of_permutations(&PROBLEM) + 2);
+
+        assert_eq!(subject.expansions(), subject.closed_set_len());
+    }
+}
+
+mod stats {
+    use super::*;
+
+    #[test]
+    fn it_counts_what_the_search_did() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+
+        subject.shortest_path(start.number_of_permutations(&PROBLEM) + 5);
+        let stats = subject.stats();
+
+        assert_eq!(stats.expansions(), subject.expansions());
+        assert_eq!(stats.generated(), stats.expansions() * PROBLEM.expansions());
+        assert!(stats.closed_set_hits() > 0);
+        assert_eq!(stats.reopenings(), 0);
+
+        // Only the symbols after the first two have more than one mapping and
+        // the bits don't always rule any of them out.
+        assert!(stats.disambiguations() > 0);
+        assert!(stats.disambiguations() <= stats.expansions() * 2);
+
+        assert!(stats.time(Phase::Expanding) > std::time::Duration::from_secs(0));
+        assert!(stats.peak_memory() >= subject.closed_set.size_in_bytes() + subject.parents.size_in_bytes());
+    }
+
+    #[test]
+    fn it_counts_the_same_on_several_threads() {
+        let start = Candidate::seed(&PROBLEM);
+        let serial = &mut subject(&start);
+        let parallel = &mut subject(&start);
+
+        parallel.use_threads(3);
+
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        serial.shortest_path(goal);
+        parallel.shortest_path(goal);
+
+        assert_eq!(parallel.stats().expansions(), parallel.expansions());
+        assert_eq!(parallel.stats().generated(), parallel.expansions() * PROBLEM.expansions());
+        assert!(parallel.stats().disambiguations() > 0);
+        assert!(parallel.stats().time(Phase::Sharding) > std::time::Duration::from_secs(0));
+    }
+}
+
+mod interrupt_flag {
+    use super::*;
+
+    #[test]
+    fn it_stops_the_search_after_the_current_expansion_when_set() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.interrupt_flag().store(true, Relaxed);
+
+        assert_eq!(subject.shortest_path(goal), None);
+        assert!(subject.interrupted());
+
+        assert_eq!(subject.closed_set_len(), 1);
+        assert_eq!(subject.open_set_len(), 4);
+    }
+
+    #[test]
+    fn it_can_continue_the_search_once_cleared() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.interrupt_flag().store(true, Relaxed);
+        subject.shortest_path(goal);
+
+        subject.interrupt_flag().store(false, Relaxed);
+        assert_eq!(subject.shortest_path(goal), Some(6));
+    }
+}
+
+mod error {
+    use super::*;
+    use std::{env, fs, process};
+
+    #[test]
+    fn it_stops_the_search_when_a_set_on_disk_fails_and_is_not_written() {
+        let directory = env::temp_dir().join(format!("supermutation-error-test-{}", process::id()));
+        fs::create_dir_all(&directory).unwrap();
+
+        let open_set = OpenSet::on_disk(&directory, 0).unwrap();
+        let mut subject = Subject::new(&PROBLEM, open_set, ClosedSet::new());
+
+        subject.seed(Candidate::seed(&PROBLEM));
+        fs::remove_dir_all(&directory).unwrap();
+
+        assert_eq!(subject.shortest_path(3), None);
+        assert!(subject.interrupted());
+        assert!(subject.error().is_some());
+
+        assert_eq!(subject.shortest_path(3), None);
+        assert!(subject.write_to(&mut Encoder::new(vec![])).is_err());
+    }
+}
+
+mod pause_at {
+    use super::*;
+
+    #[test]
+    fn it_pauses_the_search_after_the_current_expansion_once_past_the_deadline() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 5;
+
+        subject.pause_at(Some(Instant::now()));
+
+        assert_eq!(subject.shortest_path(goal), None);
+        assert!(subject.paused());
+        assert!(!subject.interrupted());
+        assert_eq!(subject.closed_set_len(), 1);
+
+        subject.pause_at(None);
+
+        assert_eq!(subject.shortest_path(goal), Some(6));
+        assert!(!subject.paused());
+    }
+}
+
+mod write_to {
+    use super::*;
+    use crate::codec::{Encoder, Decoder};
+
+    #[test]
+    fn it_can_be_read_back_and_continue_the_search() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 2;
+
+        subject.shortest_path(goal);
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let mut result = Subject::read_from(&mut Decoder::new(&bytes[..]), &PROBLEM).unwrap();
+
+        assert_eq!(result.heuristic, subject.heuristic);
+        assert_eq!(result.open_set_len(), subject.open_set_len());
+        assert_eq!(result.closed_set_len(), subject.closed_set_len());
+        assert_eq!(result.path(), subject.path());
+
+        let goal = goal + 3;
+
+        assert_eq!(result.shortest_path(goal), subject.shortest_path(goal));
+        assert_eq!(result.path(), subject.path());
+    }
+
+    #[test]
+    fn it_keeps_the_shards_of_a_parallel_search() {
+        let start = Candidate::seed(&PROBLEM);
+        let subject = &mut subject(&start);
+        let goal = start.number_of_permutations(&PROBLEM) + 2;
+
+        subject.use_threads(3);
+        subject.shortest_path(goal);
+
+        let mut bytes = vec![];
+        subject.write_to(&mut Encoder::new(&mut bytes)).unwrap();
+
+        let mut result = Subject::read_from(&mut Decoder::new(&bytes[..]), &PROBLEM).unwrap();
+
+        assert_eq!(result.shards.len(), 3);
+        assert_eq!(result.open_set_len(), subject.open_set_len());
+        assert_eq!(result.closed_set_len(), subject.closed_set_len());
+        assert_eq!(result.path(), subject.path());
+
+        result.use_threads(3);
+
+        let goal = goal + 3;
+
+        assert_eq!(result.shortest_path(goal), subject.shortest_path(goal));
+        assert_eq!(result.path(), subject.path());
+    }
+}
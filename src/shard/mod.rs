@@ -0,0 +1,274 @@
+use crate::candidate::Candidate;
+use crate::closed_set::ClosedSet;
+use crate::codec::{Encoder, Decoder};
+use crate::heuristic_fn::HeuristicFn;
+use crate::open_set::OpenSet;
+use crate::parents::Parents;
+use crate::problem::Problem;
+use crate::stats::SearchStats;
+use crate::symmetry::Symmetry;
+use std::io::{self, Read, Write};
+use std::sync::mpsc::{Receiver, Sender};
+
+/// A neighbor, its g-cost, its parent's fingerprint and the symbol that was
+/// expanded.
+pub type Message = (Candidate, usize, u128, usize);
+
+/// What the search tells a shard's thread to do next. The thread stops once
+/// the sender is dropped.
+#[derive(Clone, Copy)]
+pub enum Command {
+    Expand(usize, usize),
+    Receive,
+}
+
+/// What a shard's thread reports back after each command, or the error that
+/// stopped it, e.g. reading back a set on disk.
+pub enum Report {
+    Expanded(io::Result<(usize, Option<(usize, Candidate)>)>),
+    Received(io::Result<Status>),
+}
+
+/// The state of a shard after it received its neighbors.
+pub struct Status {
+    pub next_costs: Option<(usize, usize)>,
+    pub reached_goal: Option<(usize, Candidate)>,
+    pub open_set_len: usize,
+    pub closed_set_len: usize,
+    pub stats: SearchStats,
+}
+
+/// The channels a shard's thread uses to take part in a parallel search. Each
+/// outbox sends to a peer's inbox, including its own, tagged with the index.
+pub struct Worker {
+    pub index: usize,
+    pub commands: Receiver<Command>,
+    pub reports: Sender<Report>,
+    pub inbox: Receiver<(usize, Vec<Message>)>,
+    pub outboxes: Vec<Sender<(usize, Vec<Message>)>>,
+}
+
+/// The part of a parallel search that one thread owns. Each candidate belongs
+/// to the shard its hash picks so that duplicates always meet in one place.
+pub struct Shard {
+    pub open_set: OpenSet,
+    pub inconsistent: OpenSet,
+    pub closed_set: ClosedSet,
+    pub parents: Parents,
+    pub stats: SearchStats,
+}
+
+impl Shard {
+    pub fn new() -> Self {
+        Self { open_set: OpenSet::new(), inconsistent: OpenSet::new(), closed_set: ClosedSet::new(), parents: Parents::new(), stats: SearchStats::new() }
+    }
+
+    // The same as the part of the closed set the candidate is partitioned into.
+    pub fn owner(candidate: &Candidate, shards: usize) -> usize {
+        (candidate.fingerprint() % shards as u128) as usize
+    }
+
+    pub fn split(open_set: OpenSet, closed_set: ClosedSet, parents: Parents, shards: usize) -> io::Result<Vec<Self>> {
+        let mut result = (0..shards).map(|_| Self::new()).collect::<Vec<_>>();
+        let open_sets = open_set.partition(shards)?;
+        let closed_sets = closed_set.partition(shards)?;
+        let parents = parents.partition(shards)?;
+
+        for (((shard, open_set), closed_set), parents) in result.iter_mut().zip(open_sets).zip(closed_sets).zip(parents) {
+            shard.open_set = open_set;
+            shard.closed_set = closed_set;
+            shard.parents = parents;
+        }
+
+        Ok(result)
+    }
+
+    pub fn merge(shards: Vec<Self>) -> io::Result<(OpenSet, ClosedSet, Parents)> {
+        let mut open_set = shards[0].open_set.empty();
+        let mut closed_set = shards[0].closed_set.empty();
+        let mut parents = shards[0].parents.empty();
+
+        for shard in shards {
+            open_set.extend(shard.open_set)?;
+            closed_set.extend(shard.closed_set)?;
+
+            parents.extend(shard.parents)?;
+        }
+
+        Ok((open_set, closed_set, parents))
+    }
+
+    /// Runs the commands sent to this shard's thread until the search drops the
+    /// sender. It reports its status once before the first command.
+    pub fn work(&mut self, problem: &Problem, estimate: &dyn HeuristicFn, weight: f64, goal: usize, popped_goal: Option<usize>, worker: Worker) {
+        let report = |report| worker.reports.send(report).expect("the search stopped listening to its shards");
+
+        report(Report::Received(self.status(None)));
+
+        for command in worker.commands.iter() {
+            match command {
+                Command::Expand(f_cost, g_cost) => {
+                    let mut outboxes = worker.outboxes.iter().map(|_| vec![]).collect::<Vec<_>>();
+                    let expanded = self.expand(problem, weight, f_cost, g_cost, popped_goal, &mut outboxes);
+
+                    for (peer, messages) in worker.outboxes.iter().zip(outboxes) {
+                        peer.send((worker.index, messages)).expect("a shard stopped listening to its peers");
+                    }
+
+                    report(Report::Expanded(expanded));
+                },
+                Command::Receive => {
+                    // Sorted by sender so that the search is deterministic.
+                    let mut messages = worker.inbox.try_iter().collect::<Vec<_>>();
+                    messages.sort_by_key(|(sender, _)| *sender);
+
+                    let inbox = messages.into_iter().flat_map(|(_, m)| m).collect();
+                    let status = self.receive(problem, estimate, weight, goal, inbox).and_then(|r| self.status(r));
+
+                    report(Report::Received(status));
+                },
+            }
+        }
+    }
+
+    fn status(&mut self, reached_goal: Option<(usize, Candidate)>) -> io::Result<Status> {
+        Ok(Status {
+            next_costs: self.open_set.next_costs()?,
+            reached_goal,
+            open_set_len: self.open_set.len(),
+            closed_set_len: self.closed_set.len(),
+            stats: self.stats.clone(),
+        })
+    }
+
+    /// Expands every candidate with this f-cost and g-cost, which is the bucket
+    /// a serial search would expand next, and addresses each neighbor to the
+    /// shard that owns it. Returns the number of expansions and the closest
+    /// candidate that had already reached the popped goal, which isn't
+    /// expanded. There's only a popped goal when a goal is searched for again.
+    /// Like the serial search, the bucket is checked against the closed set's
+    /// runs on disk all at once, and again if more runs are written. With a
+    /// weight above one, candidates that would be reopened are kept aside as
+    /// inconsistent instead.
+    pub fn expand(&mut self, problem: &Problem, weight: f64, f_cost: usize, g_cost: usize, popped_goal: Option<usize>, outboxes: &mut [Vec<Message>]) -> io::Result<(usize, Option<(usize, Candidate)>)> {
+        let mut expansions = 0;
+        let mut reached_goals = vec![];
+        let mut checked = None;
+
+        Symmetry::take_disambiguations();
+
+        while self.open_set.next_costs()? == Some((f_cost, g_cost)) {
+            let checking = Some(self.closed_set.runs_written());
+
+            if checking != checked {
+                let closed_set = &self.closed_set;
+                let checked_bucket = self.open_set.retain_next(|candidates, g_cost| closed_set.remove_closed(candidates, g_cost))?;
+                let (hits, reopenings) = checked_bucket.unwrap_or_default();
+
+                self.stats.record_batch(hits, reopenings);
+                checked = checking;
+                continue;
+            }
+
+            let (candidate, search_depth) = match self.open_set.pop()? {
+                Some(item) => item,
+                None => break,
+            };
+
+            match self.closed_set.g_cost(&candidate) {
+                Some(previous) if search_depth >= previous => {
+                    self.stats.record_closed_set_hit();
+                    continue;
+                },
+                Some(_) if weight > 1.0 => {
+                    self.inconsistent.add(candidate, f_cost, search_depth)?;
+                    continue;
+                },
+                Some(_) => self.stats.record_reopening(),
+                None => {},
+            }
+
+            if Some(candidate.number_of_permutations(problem)) == popped_goal {
+                reached_goals.push((search_depth, candidate));
+                continue;
+            }
+
+            let fingerprint = candidate.fingerprint();
+
+            for symbol in 0..problem.expansions() {
+                let neighbor = candidate.expand(problem, symbol);
+                let owner = Self::owner(&neighbor, outboxes.len());
+
+                self.stats.record_disambiguations(Symmetry::take_disambiguations());
+
+                outboxes[owner].push((neighbor, search_depth + 1, fingerprint, symbol));
+            }
+
+            self.closed_set.add(candidate, search_depth)?;
+            self.stats.record_generated(problem.expansions());
+            self.stats.record_expansion();
+            expansions += 1;
+        }
+
+        let reached_goal = reached_goals.iter().min().cloned();
+
+        for (search_depth, candidate) in reached_goals {
+            self.open_set.add(candidate, f_cost, search_depth)?;
+        }
+
+        Ok((expansions, reached_goal))
+    }
+
+    /// Adds the neighbors addressed to this shard and returns the closest one
+    /// that reached the goal number of perms. Like the serial search, a goal is
+    /// reached when it's generated and not already closed.
+    pub fn receive(&mut self, problem: &Problem, estimate: &dyn HeuristicFn, weight: f64, goal: usize, inbox: Vec<Message>) -> io::Result<Option<(usize, Candidate)>> {
+        let mut reached_goal: Option<(usize, Candidate)> = None;
+
+        for (neighbor, g_cost, parent, symbol) in inbox {
+            if self.closed_set.contains(&neighbor, g_cost) {
+                self.stats.record_closed_set_hit();
+                continue;
+            }
+
+            let perms = neighbor.number_of_permutations(problem);
+
+            if perms == goal && reached_goal.as_ref().is_none_or(|(g, c)| (g_cost, &neighbor) < (*g, c)) {
+                reached_goal = Some((g_cost, neighbor.clone()));
+            }
+
+            let h_cost = estimate.weighted_cost(problem, &neighbor, g_cost, weight);
+            let f_cost = g_cost + h_cost;
+
+            self.parents.add(&neighbor, g_cost, parent, symbol)?;
+            self.open_set.add(neighbor, f_cost, g_cost)?;
+        }
+
+        Ok(reached_goal)
+    }
+
+    pub fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        self.open_set.write_to(encoder)?;
+        self.inconsistent.write_to(encoder)?;
+        self.closed_set.write_to(encoder)?;
+        self.parents.write_to(encoder)
+    }
+
+    pub fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self> {
+        let open_set = OpenSet::read_from(decoder)?;
+        let inconsistent = OpenSet::read_from(decoder)?;
+        let closed_set = ClosedSet::read_from(decoder)?;
+        let parents = Parents::read_from(decoder)?;
+
+        Ok(Self { open_set, inconsistent, closed_set, parents, stats: SearchStats::new() })
+    }
+}
+
+impl Default for Shard {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test;
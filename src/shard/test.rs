@@ -0,0 +1,199 @@
+use super::*;
+use crate::heuristic::Heuristic;
+
+type Subject = Shard;
+
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
+fn heuristic() -> Heuristic {
+    Heuristic::new(4, vec![0], vec![2, 1, 0, 0, 0, 0, 0], vec![0, 0, 0, 0, 0, 0, 0])
+}
+
+mod owner {
+    use super::*;
+
+    #[test]
+    fn it_picks_the_same_shard_for_equal_candidates() {
+        let seed = Candidate::seed(&PROBLEM);
+
+        let a = seed.expand(&PROBLEM, 1);
+        let b = seed.expand(&PROBLEM, 1);
+
+        assert_eq!(Subject::owner(&a, 7), Subject::owner(&b, 7));
+        assert!(Subject::owner(&a, 7) < 7);
+    }
+}
+
+mod split {
+    use super::*;
+
+    #[test]
+    fn it_gives_each_candidate_to_the_shard_that_owns_it() {
+        let seed = Candidate::seed(&PROBLEM);
+        let mut open_set = OpenSet::new();
+        let mut closed_set = ClosedSet::new();
+
+        for symbol in 0..PROBLEM.expansions() {
+            open_set.add(seed.expand(&PROBLEM, symbol), 2, 1).unwrap();
+        }
+
+        closed_set.add(seed.clone(), 0).unwrap();
+
+        let shards = Subject::split(open_set, closed_set, Parents::new(), 3).unwrap();
+        let total = shards.iter().map(|s| s.open_set.len()).sum::<usize>();
+
+        assert_eq!(shards.len(), 3);
+        assert_eq!(total, PROBLEM.expansions());
+        assert_eq!(shards[Subject::owner(&seed, 3)].closed_set.len(), 1);
+
+        for (i, shard) in shards.iter().enumerate() {
+            assert!(shard.open_set.iter().all(|(c, _, _)| Subject::owner(c, 3) == i));
+        }
+    }
+}
+
+mod merge {
+    use super::*;
+
+    #[test]
+    fn it_combines_the_shards_back_into_one_set_of_each() {
+        let seed = Candidate::seed(&PROBLEM);
+        let mut parents = Parents::new();
+        let mut open_set = OpenSet::new();
+
+        parents.seed(seed.clone()).unwrap();
+
+        for symbol in 0..PROBLEM.expansions() {
+            let neighbor = seed.expand(&PROBLEM, symbol);
+
+            parents.add(&neighbor, 1, seed.fingerprint(), symbol).unwrap();
+            open_set.add(neighbor, 2, 1).unwrap();
+        }
+
+        let shards = Subject::split(open_set, ClosedSet::new(), parents, 3).unwrap();
+        let (open_set, closed_set, parents) = Subject::merge(shards).unwrap();
+
+        assert_eq!(open_set.len(), PROBLEM.expansions());
+        assert_eq!(closed_set.len(), 0);
+        assert_eq!(parents.len(), PROBLEM.expansions() + 1);
+    }
+}
+
+mod expand {
+    use super::*;
+
+    #[test]
+    fn it_expands_the_candidates_with_the_f_cost_and_g_cost_and_addresses_their_neighbors() {
+        let seed = Candidate::seed(&PROBLEM);
+        let neighbor = seed.expand(&PROBLEM, 1);
+        let mut subject = Subject::new();
+        let mut outboxes = vec![vec![], vec![]];
+
+        subject.open_set.add(seed.clone(), 2, 0).unwrap();
+        subject.open_set.add(neighbor.clone(), 2, 1).unwrap();
+        subject.open_set.add(seed.expand(&PROBLEM, 2), 3, 1).unwrap();
+
+        let (expansions, reached_goal) = subject.expand(&PROBLEM, 1.0, 2, 1, None, &mut outboxes).unwrap();
+        let messages = outboxes.iter().flatten().collect::<Vec<_>>();
+
+        assert_eq!(expansions, 1);
+        assert_eq!(reached_goal, None);
+        assert_eq!(messages.len(), PROBLEM.expansions());
+        assert_eq!(subject.open_set.len(), 2);
+        assert!(subject.closed_set.contains(&neighbor, 1));
+
+        for (i, outbox) in outboxes.iter().enumerate() {
+            assert!(outbox.iter().all(|(c, g, p, _)| Subject::owner(c, 2) == i && *g == 2 && *p == neighbor.fingerprint()));
+        }
+    }
+
+    #[test]
+    fn it_keeps_candidates_that_already_reached_the_goal_without_expanding_them() {
+        let seed = Candidate::seed(&PROBLEM);
+        let neighbor = seed.expand(&PROBLEM, 0);
+        let goal = neighbor.number_of_permutations(&PROBLEM);
+
+        let mut subject = Subject::new();
+        let mut outboxes = vec![vec![]];
+
+        subject.open_set.add(neighbor.clone(), 1, 1).unwrap();
+
+        let (expansions, reached_goal) = subject.expand(&PROBLEM, 1.0, 1, 1, Some(goal), &mut outboxes).unwrap();
+
+        assert_eq!(expansions, 0);
+        assert_eq!(reached_goal, Some((1, neighbor)));
+        assert_eq!(subject.open_set.len(), 1);
+        assert_eq!(outboxes[0].len(), 0);
+    }
+
+    #[test]
+    fn it_expands_candidates_that_reached_the_goal_unless_it_is_searched_for_again() {
+        let seed = Candidate::seed(&PROBLEM);
+        let neighbor = seed.expand(&PROBLEM, 0);
+
+        let mut subject = Subject::new();
+        let mut outboxes = vec![vec![]];
+
+        subject.open_set.add(neighbor, 1, 1).unwrap();
+
+        let (expansions, reached_goal) = subject.expand(&PROBLEM, 1.0, 1, 1, None, &mut outboxes).unwrap();
+
+        assert_eq!(expansions, 1);
+        assert_eq!(reached_goal, None);
+        assert_eq!(outboxes[0].len(), PROBLEM.expansions());
+    }
+
+    #[test]
+    fn it_keeps_candidates_that_would_be_reopened_aside_when_weighted() {
+        let seed = Candidate::seed(&PROBLEM);
+        let mut subject = Subject::new();
+        let mut outboxes = vec![vec![]];
+
+        subject.closed_set.add(seed.clone(), 2).unwrap();
+        subject.open_set.add(seed.clone(), 1, 1).unwrap();
+
+        let (expansions, _) = subject.expand(&PROBLEM, 3.0, 1, 1, None, &mut outboxes).unwrap();
+
+        assert_eq!(expansions, 0);
+        assert_eq!(subject.open_set.len(), 0);
+        assert_eq!(subject.inconsistent.iter().collect::<Vec<_>>(), &[(&seed, 1, 1)]);
+    }
+}
+
+mod receive {
+    use super::*;
+
+    #[test]
+    fn it_adds_the_neighbors_and_returns_the_closest_that_reached_the_goal() {
+        let seed = Candidate::seed(&PROBLEM);
+        let goal = seed.number_of_permutations(&PROBLEM) + 1;
+        let mut subject = Subject::new();
+
+        let inbox = (0..PROBLEM.expansions()).map(|symbol| {
+            (seed.expand(&PROBLEM, symbol), 1, seed.fingerprint(), symbol)
+        }).collect();
+
+        let reached_goal = subject.receive(&PROBLEM, &heuristic(), 1.0, goal, inbox).unwrap();
+
+        assert_eq!(reached_goal, Some((1, seed.expand(&PROBLEM, 0))));
+        assert_eq!(subject.open_set.len(), PROBLEM.expansions());
+        assert_eq!(subject.parents.len(), PROBLEM.expansions());
+    }
+
+    #[test]
+    fn it_skips_neighbors_that_are_closed_at_a_lower_g_cost() {
+        let seed = Candidate::seed(&PROBLEM);
+        let neighbor = seed.expand(&PROBLEM, 1);
+        let mut subject = Subject::new();
+
+        subject.closed_set.add(neighbor.clone(), 1).unwrap();
+
+        let inbox = vec![(neighbor, 2, seed.fingerprint(), 1)];
+        let reached_goal = subject.receive(&PROBLEM, &heuristic(), 1.0, 99, inbox).unwrap();
+
+        assert_eq!(reached_goal, None);
+        assert_eq!(subject.open_set.len(), 0);
+    }
+}
@@ -0,0 +1,390 @@
+use crate::candidate::Candidate;
+use crate::codec::{Encoder, Decoder};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+/// A candidate and its g-cost.
+pub type Record = (Candidate, usize);
+
+const RECORDS_PER_BLOCK: usize = 64;
+const FILTER_BITS_PER_RECORD: usize = 12;
+const FILTER_HASHES: usize = 4;
+
+/// Something that can be written to a spill file and sorted into runs by its
+/// key. When runs are merged, the entry with the lowest g-cost is kept.
+pub trait Entry: Sized {
+    type Key: Ord + Clone;
+
+    fn key(&self) -> &Self::Key;
+    fn fingerprint(key: &Self::Key) -> u128;
+
+    /// The memory used by a key in a run's index.
+    fn key_size_in_bytes(key: &Self::Key) -> usize;
+    fn g_cost(&self) -> usize;
+
+    fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()>;
+    fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self>;
+}
+
+impl Entry for Record {
+    type Key = Candidate;
+
+    fn key(&self) -> &Candidate {
+        &self.0
+    }
+
+    fn fingerprint(candidate: &Candidate) -> u128 {
+        candidate.fingerprint()
+    }
+
+    fn key_size_in_bytes(candidate: &Candidate) -> usize {
+        candidate.size_in_bytes()
+    }
+
+    fn g_cost(&self) -> usize {
+        self.1
+    }
+
+    fn write_to<W: Write>(&self, encoder: &mut Encoder<W>) -> io::Result<()> {
+        encoder.write_usize(self.1)?;
+        self.0.write_to(encoder)
+    }
+
+    fn read_from<R: Read>(decoder: &mut Decoder<R>) -> io::Result<Self> {
+        let g_cost = decoder.read_usize()?;
+        let candidate = Candidate::read_from(decoder)?;
+
+        Ok((candidate, g_cost))
+    }
+}
+
+static DIRECTORIES: AtomicUsize = AtomicUsize::new(0);
+
+/// A directory of files that open and closed sets write candidates to once they
+/// use more memory than their budget. It's removed when it is dropped, which
+/// is after the last set that shares it.
+pub struct Spill {
+    directory: PathBuf,
+    budget: usize,
+    files: AtomicUsize,
+}
+
+impl Spill {
+    pub fn new<P: AsRef<Path>>(parent: P, budget: usize) -> io::Result<Arc<Self>> {
+        let name = format!("supermutation-{}-{}", process::id(), DIRECTORIES.fetch_add(1, Relaxed));
+        let directory = parent.as_ref().join(name);
+
+        fs::create_dir_all(&directory)?;
+
+        Ok(Arc::new(Self { directory, budget, files: AtomicUsize::new(0) }))
+    }
+
+    /// The number of bytes of candidates a set keeps in memory before spilling.
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Picks a name for a new file, which is created when it's first appended to.
+    pub fn new_file(&self) -> usize {
+        self.files.fetch_add(1, Relaxed)
+    }
+
+    /// Appends blocks of entries to the file and returns the offset of each.
+    pub fn append<'e, E: Entry + 'e, I: IntoIterator<Item = &'e [E]>>(&self, file: usize, blocks: I) -> io::Result<Vec<u64>> {
+        let mut file = OpenOptions::new().create(true).append(true).open(self.path(file))?;
+        let mut offset = file.seek(SeekFrom::End(0))?;
+        let mut offsets = vec![];
+
+        for block in blocks {
+            let bytes = Self::encode(block)?;
+            file.write_all(&bytes)?;
+
+            offsets.push(offset);
+            offset += bytes.len() as u64;
+        }
+
+        file.flush()?;
+
+        Ok(offsets)
+    }
+
+    /// Reads this many entries from the offset in the file.
+    pub fn read<E: Entry>(&self, file: usize, offset: u64, entries: usize) -> io::Result<Vec<E>> {
+        self.entries(file, offset, entries)?.collect()
+    }
+
+    /// Reads this many entries from the offset in the file one at a time, so
+    /// that a whole file doesn't have to fit in memory.
+    pub fn entries<E: Entry>(&self, file: usize, offset: u64, entries: usize) -> io::Result<Entries<E>> {
+        let mut file = File::open(self.path(file))?;
+        file.seek(SeekFrom::Start(offset))?;
+
+        Ok(Entries { decoder: Decoder::new(BufReader::new(file)), remaining: entries, entry: PhantomData })
+    }
+
+    pub fn remove(&self, file: usize) -> io::Result<()> {
+        match fs::remove_file(self.path(file)) {
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(()),
+            result => result,
+        }
+    }
+
+    fn encode<E: Entry>(block: &[E]) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![];
+        let mut encoder = Encoder::new(&mut bytes);
+
+        for entry in block {
+            entry.write_to(&mut encoder)?;
+        }
+
+        Ok(bytes)
+    }
+
+    fn path(&self, file: usize) -> PathBuf {
+        self.directory.join(file.to_string())
+    }
+}
+
+impl Drop for Spill {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.directory);
+    }
+}
+
+/// Entries read from a file in order.
+pub struct Entries<E: Entry> {
+    decoder: Decoder<BufReader<File>>,
+    remaining: usize,
+    entry: PhantomData<E>,
+}
+
+impl<E: Entry> Iterator for Entries<E> {
+    type Item = io::Result<E>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+
+        Some(E::read_from(&mut self.decoder))
+    }
+}
+
+/// Entries sorted by key and written to a file. Every block's first key is
+/// kept in memory so that looking one up reads a single block, and a bloom
+/// filter skips most lookups for keys that aren't there. Keys that are looked
+/// up together in order read each block at most once.
+pub struct Run<E: Entry> {
+    spill: Arc<Spill>,
+    file: usize,
+    len: usize,
+    index: Vec<(E::Key, u64)>,
+    filter: Vec<u64>,
+    bytes: usize,
+}
+
+impl<E: Entry> Run<E> {
+    /// Writes entries that are sorted by key.
+    pub fn write<I>(spill: &Arc<Spill>, entries: I) -> io::Result<Self>
+        where I: IntoIterator<Item = E>, I::IntoIter: ExactSizeIterator
+    {
+        let entries = entries.into_iter();
+        let mut writer = RunWriter::new(spill, entries.len())?;
+
+        for entry in entries {
+            writer.push(entry)?;
+        }
+
+        writer.finish()
+    }
+
+    /// Merges runs into one, keeping the entry with the lowest g-cost for each
+    /// key. Each run is read once, in order.
+    pub fn merge(spill: &Arc<Spill>, runs: Vec<Self>) -> io::Result<Self> {
+        let mut writer = RunWriter::new(spill, runs.iter().map(|run| run.len).sum())?;
+        let mut entries = runs.iter().map(|run| run.entries()).collect::<io::Result<Vec<_>>>()?;
+        let mut heads = entries.iter_mut().map(|e| e.next().transpose()).collect::<io::Result<Vec<_>>>()?;
+
+        while let Some(key) = heads.iter().flatten().map(|entry| entry.key()).min().cloned() {
+            let mut lowest: Option<E> = None;
+
+            for (head, entries) in heads.iter_mut().zip(&mut entries) {
+                if head.as_ref().is_none_or(|entry| *entry.key() != key) {
+                    continue;
+                }
+
+                let entry = mem::replace(head, entries.next().transpose()?).unwrap();
+
+                if lowest.as_ref().is_none_or(|l| entry.g_cost() < l.g_cost()) {
+                    lowest = Some(entry);
+                }
+            }
+
+            writer.push(lowest.unwrap())?;
+        }
+
+        writer.finish()
+    }
+
+    /// Merges the newest runs while they're at least as long as the ones before
+    /// them, so there are logarithmically many runs to look keys up in and each
+    /// entry is rewritten logarithmically many times.
+    pub fn merge_newest(spill: &Arc<Spill>, runs: &mut Vec<Self>) -> io::Result<()> {
+        while let [.., previous, newest] = &runs[..] {
+            if newest.len() < previous.len() {
+                break;
+            }
+
+            let newest = runs.split_off(runs.len() - 2);
+            runs.push(Self::merge(spill, newest)?);
+        }
+
+        Ok(())
+    }
+
+    /// Splits into runs by the part each key is in, reading this one once.
+    pub fn partition<F: Fn(&E::Key) -> usize>(&self, parts: usize, part: F) -> io::Result<Vec<Self>> {
+        let mut writers = (0..parts).map(|_| RunWriter::new(&self.spill, self.len)).collect::<io::Result<Vec<_>>>()?;
+
+        for entry in self.entries()? {
+            let entry = entry?;
+            writers[part(entry.key())].push(entry)?;
+        }
+
+        writers.into_iter().map(|writer| writer.finish()).collect()
+    }
+
+    /// The entry with the key, if it was written.
+    pub fn get(&self, key: &E::Key) -> io::Result<Option<E>> {
+        Ok(self.get_sorted(Some(key))?.pop().flatten())
+    }
+
+    /// Looks up keys in sorted order, reading each block at most once, and
+    /// returns what was written for each.
+    pub fn get_sorted<'k, I: IntoIterator<Item = &'k E::Key>>(&self, keys: I) -> io::Result<Vec<Option<E>>> where E::Key: 'k {
+        let mut block: Option<(usize, Vec<E>)> = None;
+        let mut result = vec![];
+
+        for key in keys {
+            if !self.filter_bits(key).all(|bit| self.filter[bit / 64] & (1 << (bit % 64)) != 0) {
+                result.push(None);
+                continue;
+            }
+
+            let number = match self.index.partition_point(|(first, _)| first <= key) {
+                0 => { result.push(None); continue; },
+                n => n - 1,
+            };
+
+            if block.as_ref().is_none_or(|(n, _)| *n != number) {
+                let entries = RECORDS_PER_BLOCK.min(self.len - number * RECORDS_PER_BLOCK);
+                block = Some((number, self.spill.read(self.file, self.index[number].1, entries)?));
+            }
+
+            let entries = &mut block.as_mut().unwrap().1;
+            let position = entries.iter().position(|entry| entry.key() == key);
+
+            result.push(position.map(|p| entries.swap_remove(p)));
+        }
+
+        Ok(result)
+    }
+
+    /// Reads the entries back in order, one at a time.
+    pub fn entries(&self) -> io::Result<Entries<E>> {
+        self.spill.entries(self.file, 0, self.len)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// The memory used by the index and filter rather than the file.
+    pub fn size_in_bytes(&self) -> usize {
+        self.bytes
+    }
+
+    fn filter_bits(&self, key: &E::Key) -> impl Iterator<Item = usize> {
+        let fingerprint = E::fingerprint(key);
+        let (low, high) = (fingerprint as u64, (fingerprint >> 64) as u64);
+        let bits = self.filter.len() as u64 * 64;
+
+        (0..FILTER_HASHES as u64).map(move |i| (low.wrapping_add(i.wrapping_mul(high)) % bits) as usize)
+    }
+}
+
+impl<E: Entry> Drop for Run<E> {
+    fn drop(&mut self) {
+        let _ = self.spill.remove(self.file);
+    }
+}
+
+// Writes a run a block at a time as sorted entries are pushed. The filter is
+// sized for the most entries the run could have.
+struct RunWriter<E: Entry> {
+    run: Run<E>,
+    writer: BufWriter<File>,
+    offset: u64,
+    block: Vec<E>,
+}
+
+impl<E: Entry> RunWriter<E> {
+    fn new(spill: &Arc<Spill>, capacity: usize) -> io::Result<Self> {
+        let file = spill.new_file();
+        let writer = BufWriter::new(File::create(spill.path(file))?);
+
+        let words = (capacity * FILTER_BITS_PER_RECORD).div_ceil(64);
+        let filter = vec![0; words.max(1)];
+        let run = Run { spill: spill.clone(), file, len: 0, index: vec![], bytes: filter.len() * 8, filter };
+
+        Ok(Self { run, writer, offset: 0, block: vec![] })
+    }
+
+    fn push(&mut self, entry: E) -> io::Result<()> {
+        for bit in self.run.filter_bits(entry.key()) {
+            self.run.filter[bit / 64] |= 1 << (bit % 64);
+        }
+
+        if self.block.is_empty() {
+            self.run.bytes += E::key_size_in_bytes(entry.key()) + 8;
+            self.run.index.push((entry.key().clone(), self.offset));
+        }
+
+        self.block.push(entry);
+        self.run.len += 1;
+
+        if self.block.len() == RECORDS_PER_BLOCK {
+            self.write_block()?;
+        }
+
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<Run<E>> {
+        self.write_block()?;
+        self.writer.flush()?;
+
+        Ok(self.run)
+    }
+
+    fn write_block(&mut self) -> io::Result<()> {
+        let bytes = Spill::encode(&self.block)?;
+
+        self.writer.write_all(&bytes)?;
+        self.offset += bytes.len() as u64;
+        self.block.clear();
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
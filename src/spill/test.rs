@@ -0,0 +1,177 @@
+use super::*;
+use crate::problem::Problem;
+use std::env;
+
+type Subject = Spill;
+
+lazy_static! {
+    static ref PROBLEM: Problem = Problem::new(5);
+}
+
+// The first n distinct candidates of a breadth first search and their depths.
+fn records(n: usize) -> Vec<Record> {
+    let mut records = vec![(Candidate::seed(&PROBLEM), 0)];
+    let mut index = 0;
+
+    while records.len() < n {
+        let (candidate, g_cost) = records[index].clone();
+
+        for symbol in 0..PROBLEM.expansions() {
+            let neighbor = candidate.expand(&PROBLEM, symbol);
+
+            if records.len() < n && records.iter().all(|(c, _)| *c != neighbor) {
+                records.push((neighbor, g_cost + 1));
+            }
+        }
+
+        index += 1;
+    }
+
+    records
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn it_creates_a_directory_that_is_removed_when_dropped() {
+        let subject = Subject::new(env::temp_dir(), 100).unwrap();
+        let directory = subject.directory.clone();
+
+        assert!(directory.is_dir());
+        assert_eq!(subject.budget(), 100);
+
+        drop(subject);
+
+        assert!(!directory.exists());
+    }
+}
+
+mod append {
+    use super::*;
+
+    #[test]
+    fn it_returns_offsets_that_blocks_can_be_read_back_from() {
+        let subject = Subject::new(env::temp_dir(), 100).unwrap();
+        let records = records(10);
+        let file = subject.new_file();
+
+        let offsets = subject.append(file, records.chunks(4)).unwrap();
+
+        assert_eq!(offsets.len(), 3);
+        assert_eq!(offsets[0], 0);
+
+        assert_eq!(subject.read::<Record>(file, offsets[1], 4).unwrap(), &records[4..8]);
+        assert_eq!(subject.read::<Record>(file, 0, 10).unwrap(), records);
+
+        let more = subject.append(file, Some(&records[..1])).unwrap();
+
+        assert_eq!(subject.read::<Record>(file, more[0], 1).unwrap(), &records[..1]);
+    }
+}
+
+mod run {
+    use super::*;
+
+    fn entries(run: &Run<Record>) -> Vec<Record> {
+        run.entries().unwrap().collect::<io::Result<_>>().unwrap()
+    }
+
+    #[test]
+    fn it_gets_the_g_cost_of_candidates_in_the_run() {
+        let spill = Subject::new(env::temp_dir(), 100).unwrap();
+        let mut records = records(200);
+
+        records.sort();
+
+        let run = Run::write(&spill, records.clone()).unwrap();
+        let present = records[150].clone();
+        let absent = Candidate::seed(&Problem::new(4));
+
+        assert_eq!(run.len(), 200);
+        assert_eq!(run.get(&present.0).unwrap(), Some(present));
+        assert_eq!(run.get(&absent).unwrap(), None);
+        assert_eq!(entries(&run), records);
+    }
+
+    #[test]
+    fn it_gets_sorted_candidates_in_one_pass() {
+        let spill = Subject::new(env::temp_dir(), 100).unwrap();
+        let mut records = records(200);
+
+        records.sort();
+
+        let run = Run::write(&spill, records[..150].to_vec()).unwrap();
+        let keys = records.iter().step_by(7).map(|(c, _)| c);
+        let expected = records.iter().step_by(7).map(|r| Some(r.clone()).filter(|_| r < &records[150]));
+
+        assert_eq!(run.get_sorted(keys).unwrap(), expected.collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn it_merges_runs_keeping_the_lowest_g_cost_of_each_candidate() {
+        let spill = Subject::new(env::temp_dir(), 100).unwrap();
+        let mut records = records(100);
+
+        records.sort();
+
+        let higher = records.iter().map(|(c, g)| (c.clone(), g + 1)).step_by(2).collect::<Vec<_>>();
+        let runs = vec![Run::write(&spill, higher).unwrap(), Run::write(&spill, records.clone()).unwrap()];
+
+        let merged = Run::merge(&spill, runs).unwrap();
+
+        assert_eq!(merged.len(), 100);
+        assert_eq!(entries(&merged), records);
+    }
+
+    #[test]
+    fn it_merges_the_newest_runs_while_they_are_as_long_as_the_ones_before() {
+        let spill = Subject::new(env::temp_dir(), 100).unwrap();
+        let mut records = records(64);
+        let mut runs = vec![];
+
+        records.sort();
+
+        for chunk in records.chunks(8) {
+            runs.push(Run::write(&spill, chunk.to_vec()).unwrap());
+            Run::merge_newest(&spill, &mut runs).unwrap();
+        }
+
+        assert_eq!(runs.len(), 1);
+        assert_eq!(entries(&runs[0]), records);
+
+        runs.push(Run::write(&spill, records[..8].to_vec()).unwrap());
+        Run::merge_newest(&spill, &mut runs).unwrap();
+
+        assert_eq!(runs.iter().map(|r| r.len()).collect::<Vec<_>>(), vec![64, 8]);
+    }
+
+    #[test]
+    fn it_partitions_the_run_by_key() {
+        let spill = Subject::new(env::temp_dir(), 100).unwrap();
+        let mut records = records(100);
+
+        records.sort();
+
+        let run = Run::write(&spill, records.clone()).unwrap();
+        let parts = run.partition(3, |c| (c.fingerprint() % 3) as usize).unwrap();
+
+        for (part, run) in parts.iter().enumerate() {
+            let expected = records.iter().filter(|(c, _)| c.fingerprint() % 3 == part as u128);
+            assert_eq!(entries(run), expected.cloned().collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn it_removes_its_file_when_dropped() {
+        let spill = Subject::new(env::temp_dir(), 100).unwrap();
+        let run = Run::write(&spill, records(3)).unwrap();
+        let path = spill.path(run.file);
+
+        assert!(path.exists());
+
+        drop(run);
+
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,164 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Counts what a search has done since it was created or read, and the time
+/// it has spent in each phase.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SearchStats {
+    expansions: usize,
+    generated: usize,
+    closed_set_hits: usize,
+    reopenings: usize,
+    disambiguations: usize,
+    phase_times: [Duration; Phase::COUNT],
+    peak_memory: usize,
+}
+
+/// What a search spends its time doing. Shards are only exchanged and split
+/// by a parallel search.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    Expanding,
+    Exchanging,
+    Sharding,
+    UpdatingHeuristic,
+}
+
+impl Phase {
+    pub const COUNT: usize = 4;
+    pub const ALL: [Phase; Phase::COUNT] = [Phase::Expanding, Phase::Exchanging, Phase::Sharding, Phase::UpdatingHeuristic];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phase::Expanding => "expanding",
+            Phase::Exchanging => "exchanging",
+            Phase::Sharding => "sharding",
+            Phase::UpdatingHeuristic => "updating_heuristic",
+        }
+    }
+}
+
+impl SearchStats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn expansions(&self) -> usize {
+        self.expansions
+    }
+
+    /// The neighbors made by expanding candidates.
+    pub fn generated(&self) -> usize {
+        self.generated
+    }
+
+    /// Candidates and neighbors that were skipped because they had already
+    /// been expanded at the same or a lower g-cost.
+    pub fn closed_set_hits(&self) -> usize {
+        self.closed_set_hits
+    }
+
+    /// Candidates that were expanded again because a shorter path to them was
+    /// found, which only happens if the heuristic is inconsistent or weighted.
+    pub fn reopenings(&self) -> usize {
+        self.reopenings
+    }
+
+    /// Expansions whose symmetry mapping had to be chosen from several by the
+    /// bits that are set, i.e. the bits ruled some of them out.
+    pub fn disambiguations(&self) -> usize {
+        self.disambiguations
+    }
+
+    pub fn time(&self, phase: Phase) -> Duration {
+        self.phase_times[phase as usize]
+    }
+
+    pub fn total_time(&self) -> Duration {
+        self.phase_times.iter().sum()
+    }
+
+    /// An estimate in bytes of the most memory the search's sets used. It's
+    /// sampled after each subgoal rather than after every expansion.
+    pub fn peak_memory(&self) -> usize {
+        self.peak_memory
+    }
+
+    pub fn expansions_per_second(&self) -> f64 {
+        self.expansions as f64 / self.total_time().as_secs_f64().max(1e-9)
+    }
+
+    pub fn record_expansion(&mut self) {
+        self.expansions += 1;
+    }
+
+    pub fn record_generated(&mut self, neighbors: usize) {
+        self.generated += neighbors;
+    }
+
+    pub fn record_closed_set_hit(&mut self) {
+        self.closed_set_hits += 1;
+    }
+
+    pub fn record_reopening(&mut self) {
+        self.reopenings += 1;
+    }
+
+    /// Records what checking a batch of candidates against the closed set at
+    /// once found, e.g. a bucket against the runs of a closed set on disk.
+    pub fn record_batch(&mut self, closed_set_hits: usize, reopenings: usize) {
+        self.closed_set_hits += closed_set_hits;
+        self.reopenings += reopenings;
+    }
+
+    pub fn record_disambiguations(&mut self, count: usize) {
+        self.disambiguations += count;
+    }
+
+    pub fn record_time(&mut self, phase: Phase, duration: Duration) {
+        self.phase_times[phase as usize] += duration;
+    }
+
+    pub fn record_memory(&mut self, bytes: usize) {
+        self.peak_memory = self.peak_memory.max(bytes);
+    }
+
+    /// Adds the counts and times of another, e.g. a shard of a parallel search.
+    pub fn combine(&mut self, other: &Self) {
+        self.expansions += other.expansions;
+        self.generated += other.generated;
+        self.closed_set_hits += other.closed_set_hits;
+        self.reopenings += other.reopenings;
+        self.disambiguations += other.disambiguations;
+
+        for (time, other) in self.phase_times.iter_mut().zip(&other.phase_times) {
+            *time += *other;
+        }
+
+        self.peak_memory = self.peak_memory.max(other.peak_memory);
+    }
+}
+
+impl fmt::Display for SearchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "expansions: {} ({:.0} per second)", self.expansions, self.expansions_per_second())?;
+        writeln!(f, "generated: {}", self.generated)?;
+        writeln!(f, "closed set hits: {}", self.closed_set_hits)?;
+        writeln!(f, "reopenings: {}", self.reopenings)?;
+        writeln!(f, "disambiguations: {}", self.disambiguations)?;
+
+        for phase in &Phase::ALL {
+            writeln!(f, "{}: {:.3}s", phase.name().replace('_', " "), self.time(*phase).as_secs_f64())?;
+        }
+
+        let kilobytes = self.peak_memory as f64 / 1024.0;
+
+        match kilobytes < 1024.0 {
+            true => write!(f, "peak memory: {:.1} KB", kilobytes),
+            false => write!(f, "peak memory: {:.1} MB", kilobytes / 1024.0),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test;
@@ -0,0 +1,97 @@
+use super::*;
+
+type Subject = SearchStats;
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn it_starts_with_everything_at_zero() {
+        let subject = Subject::new();
+
+        assert_eq!(subject.expansions(), 0);
+        assert_eq!(subject.generated(), 0);
+        assert_eq!(subject.peak_memory(), 0);
+        assert_eq!(subject.total_time(), Duration::from_secs(0));
+    }
+}
+
+mod record_time {
+    use super::*;
+
+    #[test]
+    fn it_adds_up_the_time_spent_in_each_phase() {
+        let mut subject = Subject::new();
+
+        subject.record_time(Phase::Expanding, Duration::from_millis(3));
+        subject.record_time(Phase::Expanding, Duration::from_millis(4));
+        subject.record_time(Phase::UpdatingHeuristic, Duration::from_millis(2));
+
+        assert_eq!(subject.time(Phase::Expanding), Duration::from_millis(7));
+        assert_eq!(subject.time(Phase::UpdatingHeuristic), Duration::from_millis(2));
+        assert_eq!(subject.time(Phase::Sharding), Duration::from_millis(0));
+        assert_eq!(subject.total_time(), Duration::from_millis(9));
+    }
+}
+
+mod record_memory {
+    use super::*;
+
+    #[test]
+    fn it_keeps_the_most_memory_that_was_recorded() {
+        let mut subject = Subject::new();
+
+        subject.record_memory(100);
+        subject.record_memory(300);
+        subject.record_memory(200);
+
+        assert_eq!(subject.peak_memory(), 300);
+    }
+}
+
+mod combine {
+    use super::*;
+
+    #[test]
+    fn it_adds_the_counts_and_times_and_keeps_the_highest_peak() {
+        let mut subject = Subject::new();
+        let mut other = Subject::new();
+
+        subject.record_expansion();
+        subject.record_generated(4);
+        subject.record_memory(50);
+
+        other.record_expansion();
+        other.record_closed_set_hit();
+        other.record_reopening();
+        other.record_disambiguations(1);
+        other.record_time(Phase::Exchanging, Duration::from_millis(5));
+        other.record_memory(20);
+
+        subject.combine(&other);
+
+        assert_eq!(subject.expansions(), 2);
+        assert_eq!(subject.generated(), 4);
+        assert_eq!(subject.closed_set_hits(), 1);
+        assert_eq!(subject.reopenings(), 1);
+        assert_eq!(subject.disambiguations(), 1);
+        assert_eq!(subject.time(Phase::Exchanging), Duration::from_millis(5));
+        assert_eq!(subject.peak_memory(), 50);
+    }
+}
+
+mod fmt {
+    use super::*;
+
+    #[test]
+    fn it_prints_a_line_for_each_statistic() {
+        let mut subject = Subject::new();
+        subject.record_generated(3);
+
+        let string = subject.to_string();
+
+        assert!(string.contains("generated: 3"));
+        assert!(string.contains("updating heuristic: 0.000s"));
+        assert_eq!(string.lines().count(), 10);
+    }
+}
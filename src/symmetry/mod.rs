@@ -1,26 +1,64 @@
-use croaring::Bitmap;
+use crate::bitset::Bits;
 use lehmer::Lehmer;
-use std::iter::{once, repeat};
+use std::cell::Cell;
+use std::iter::{once, repeat_n};
 use super::utility::Utility;
 
+thread_local! {
+    // Mappings chosen on this thread since the count was last taken. It's per
+    // thread so that shards and searches that share a problem count their own.
+    static DISAMBIGUATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Precomputed mappings that relabel a candidate's bits after an expansion.
 pub struct Symmetry {
     mappings: Vec<Vec<Vec<u32>>>,
+    transpositions: Vec<Vec<Vec<u8>>>,
 }
 
 impl Symmetry {
     pub fn precompute(n: usize) -> Symmetry {
-        Symmetry { mappings: Self::combined_mappings(n) }
+        let mappings = Self::combined_mappings(n);
+        let transpositions = Self::transpositions(n, |_, t| t);
+
+        Symmetry { mappings, transpositions }
+    }
+
+    /// The bit mapping for the expansion, disambiguated by the leftmost bit rule.
+    pub fn mapping<B: Bits>(&self, symbol: usize, bitmap: &B) -> &Vec<u32> {
+        &self.mappings[symbol][self.choice(symbol, bitmap)]
+    }
+
+    /// The relabelling of the symbols that corresponds to the chosen mapping.
+    pub fn transposition<B: Bits>(&self, symbol: usize, bitmap: &B) -> &Vec<u8> {
+        &self.transpositions[symbol][self.choice(symbol, bitmap)]
     }
 
-    pub fn mapping(&self, symbol: usize, bitmap: &Bitmap) -> &Vec<u32> {
+    /// The number of mappings the one for the expansion is chosen from.
+    pub fn choices(&self, symbol: usize) -> usize {
+        self.mappings[symbol].len()
+    }
+
+    /// The number of times a mapping had to be chosen by the bits that are set
+    /// on this thread since this was last called, which resets it.
+    pub fn take_disambiguations() -> usize {
+        DISAMBIGUATIONS.with(|count| count.replace(0))
+    }
+
+    // Only counts a disambiguation if the bits ruled out some of the mappings,
+    // otherwise they all agree on the bits that are set.
+    fn choice<B: Bits>(&self, symbol: usize, bitmap: &B) -> usize {
         let mappings = &self.mappings[symbol];
 
         if mappings.len() == 1 {
-            return &mappings[0]
+            return 0;
         }
 
         let mut choices: Vec<usize> = (0..mappings.len()).collect();
 
+        // The position indexes into each of the remaining choices' mappings,
+        // not into the mappings themselves.
+        #[allow(clippy::needless_range_loop)]
         for i in 0..mappings[0].len() {
             let bit_is_set = |c: &usize| {
                 bitmap.contains(mappings[*c][i])
@@ -30,21 +68,25 @@ impl Symmetry {
                 continue;
             }
 
-            choices.drain_filter(|c| !bit_is_set(c));
+            choices.retain(|c| bit_is_set(c));
 
             if choices.len() == 1 {
                 break;
             }
         }
 
-        &mappings[choices[0]]
+        if choices.len() < mappings.len() {
+            DISAMBIGUATIONS.with(|count| count.set(count.get() + 1));
+        }
+
+        choices[0]
     }
 
     fn combined_mappings(n: usize) -> Vec<Vec<Vec<u32>>> {
         let counters = Self::counter_mappings(n);
 
         Self::permutation_mappings(n, |symbol, mapping| {
-            let head = mapping.iter().map(|h| *h as u32);
+            let head = mapping.into_iter();
             let tail = counters[symbol].clone();
 
             head.chain(tail).collect()
@@ -61,15 +103,15 @@ impl Symmetry {
 
 
         (0..(n - 1)).rev().enumerate().map(|(j, i)| {
-            let head = template[0..i].iter().map(|t| *t);
-            let tail = repeat(ground_truth).take(j);
+            let head = template[0..i].iter().copied();
+            let tail = repeat_n(ground_truth, j);
 
             head.chain(tail).collect()
         }).collect()
     }
 
     fn permutation_mappings<F, T>(n: usize, f: F) -> Vec<Vec<Vec<T>>>
-        where F: Fn(usize, Vec<u8>) -> Vec<T>
+        where F: Fn(usize, Vec<u32>) -> Vec<T>
     {
         let factorial = Utility::factorial(n);
 
@@ -85,7 +127,7 @@ impl Symmetry {
                 let iterator = permutation.iter().map(|p| inverse[*p as usize]);
                 let mapped = iterator.collect::<Vec<u8>>();
 
-                Lehmer::from_permutation(&mapped).to_decimal() as u8
+                Lehmer::from_permutation(&mapped).to_decimal() as u32
             }).collect();
 
             f(symbol, mappings)
@@ -102,7 +144,7 @@ impl Symmetry {
                 let lehmer = Lehmer::from_decimal(i, symbol);
                 let permutation = lehmer.to_permutation();
 
-                let head = permutation.iter().map(|h| *h);
+                let head = permutation.iter().copied();
                 let middle = (symbol..n).skip(1).map(|t| t as u8);
                 let tail = once(symbol as u8);
 
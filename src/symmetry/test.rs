@@ -1,6 +1,5 @@
-#![cfg(not(feature = "four_symbols"))]
-
 use super::*;
+use croaring::Bitmap;
 
 type Subject = Symmetry;
 
@@ -76,6 +75,22 @@ mod permutation_mappings {
                     //
         ]]]);
     }
+
+    #[test]
+    fn it_returns_mappings_that_are_bijections_on_the_permutation_indexes() {
+        for n in 3..=7 {
+            let factorial = Utility::factorial(n);
+            let result = Subject::permutation_mappings(n, |_, m| m);
+
+            for mapping in result.iter().flatten() {
+                let mut sorted = mapping.clone();
+                sorted.sort();
+
+                let expected = (0..factorial as u32).collect::<Vec<_>>();
+                assert_eq!(sorted, expected, "n = {}", n);
+            }
+        }
+    }
 }
 
 mod counter_mappings {
@@ -153,6 +168,32 @@ mod counter_mappings {
     }
 }
 
+mod combined_mappings {
+    use super::*;
+
+    #[test]
+    fn it_maps_the_permutation_bits_onto_themselves_for_every_symbol() {
+        for n in 3..=7 {
+            let factorial = Utility::factorial(n);
+            let result = Subject::combined_mappings(n);
+
+            assert_eq!(result.len(), n - 1);
+
+            for (symbol, mappings) in result.iter().enumerate() {
+                assert_eq!(mappings.len(), Utility::factorial(symbol));
+
+                for mapping in mappings {
+                    let mut head = mapping[0..factorial].to_vec();
+                    head.sort();
+
+                    let expected = (0..factorial as u32).collect::<Vec<_>>();
+                    assert_eq!(head, expected, "n = {}, symbol = {}", n, symbol);
+                }
+            }
+        }
+    }
+}
+
 mod mapping {
     use super::*;
 
@@ -192,3 +233,64 @@ mod mapping {
         assert_eq!(mapping(&[]), &first());
     }
 }
+
+mod choices {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_number_of_mappings_the_expansion_chooses_from() {
+        let subject = Symmetry::precompute(5);
+        let choices = (0..4).map(|symbol| subject.choices(symbol)).collect::<Vec<_>>();
+
+        assert_eq!(choices, &[1, 1, 2, 6]);
+    }
+}
+
+mod take_disambiguations {
+    use super::*;
+
+    lazy_static! {
+        static ref SUBJECT: Subject = Subject::precompute(4);
+    }
+
+    #[test]
+    fn it_counts_the_mappings_chosen_by_the_bits_that_are_set_since_it_was_last_taken() {
+        Subject::take_disambiguations();
+
+        SUBJECT.mapping(0, &Bitmap::of(&[6]));
+        SUBJECT.mapping(2, &Bitmap::of(&[]));
+        assert_eq!(Subject::take_disambiguations(), 0);
+
+        SUBJECT.mapping(2, &Bitmap::of(&[0]));
+        SUBJECT.transposition(2, &Bitmap::of(&[6]));
+        assert_eq!(Subject::take_disambiguations(), 2);
+        assert_eq!(Subject::take_disambiguations(), 0);
+    }
+}
+
+mod transposition {
+    use super::*;
+
+    lazy_static! {
+        static ref SUBJECT: Subject = Subject::precompute(4);
+    }
+
+    fn transposition(slice: &[u32]) -> &Vec<u8> {
+        SUBJECT.transposition(2, &Bitmap::of(slice))
+    }
+
+    #[test]
+    fn it_returns_the_transposition_for_the_chosen_mapping() {
+        // These agree with the choices made in the #mapping tests above.
+        assert_eq!(transposition(&[0]), &[0, 1, 3, 2]);
+        assert_eq!(transposition(&[6]), &[1, 0, 3, 2]);
+        assert_eq!(transposition(&[0, 7]), &[1, 0, 3, 2]);
+        assert_eq!(transposition(&[4, 10, 16, 17]), &[0, 1, 3, 2]);
+    }
+
+    #[test]
+    fn it_returns_the_only_transposition_when_there_is_no_choice() {
+        assert_eq!(SUBJECT.transposition(0, &Bitmap::of(&[6])), &[1, 2, 3, 0]);
+        assert_eq!(SUBJECT.transposition(1, &Bitmap::of(&[6])), &[0, 2, 3, 1]);
+    }
+}
@@ -9,6 +9,40 @@ impl Utility {
             _ => n * Self::factorial(n - 1),
         }
     }
+
+    /// Symbols are digits numbered from zero. Strings that number them from one,
+    /// i.e. that use the last symbol's digit but not zero, are shifted down and
+    /// whitespace is ignored.
+    pub fn parse_symbols(string: &str, n: usize) -> Result<Vec<u8>, String> {
+        let mut symbols = vec![];
+
+        for c in string.chars().filter(|c| !c.is_whitespace()) {
+            match c.to_digit(10) {
+                Some(digit) => symbols.push(digit as u8),
+                None => return Err(format!("'{}' is not a digit", c)),
+            }
+        }
+
+        if symbols.is_empty() {
+            return Err("the string is empty".to_string());
+        }
+
+        if !symbols.contains(&0) && symbols.contains(&(n as u8)) {
+            symbols.iter_mut().for_each(|s| *s -= 1);
+        }
+
+        if let Some(s) = symbols.iter().find(|s| **s as usize >= n) {
+            return Err(format!("'{}' is not one of the {} symbols", s, n));
+        }
+
+        Ok(symbols)
+    }
+
+    pub fn is_permutation(symbols: &[u8]) -> bool {
+        let mut seen = [false; 10];
+
+        symbols.iter().all(|s| !std::mem::replace(&mut seen[*s as usize], true))
+    }
 }
 
 #[cfg(test)]
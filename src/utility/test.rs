@@ -1,5 +1,3 @@
-#![cfg(not(feature = "four_symbols"))]
-
 use super::*;
 
 type Subject = Utility;
@@ -17,3 +15,44 @@ mod factorial {
         assert_eq!(Subject::factorial(5), 120);
     }
 }
+
+mod parse_symbols {
+    use super::*;
+
+    #[test]
+    fn it_parses_digits_and_ignores_whitespace() {
+        assert_eq!(Subject::parse_symbols("01 2\n0", 3), Ok(vec![0, 1, 2, 0]));
+    }
+
+    #[test]
+    fn it_shifts_symbols_that_are_numbered_from_one() {
+        assert_eq!(Subject::parse_symbols("1231", 3), Ok(vec![0, 1, 2, 0]));
+    }
+
+    #[test]
+    fn it_only_shifts_strings_that_use_the_last_symbol() {
+        assert_eq!(Subject::parse_symbols("11111", 5), Ok(vec![1, 1, 1, 1, 1]));
+    }
+
+    #[test]
+    fn it_returns_an_error_for_digits_that_are_not_one_of_the_symbols() {
+        assert!(Subject::parse_symbols("0123", 3).is_err());
+        assert!(Subject::parse_symbols("1234", 3).is_err());
+    }
+
+    #[test]
+    fn it_returns_an_error_for_non_digits_or_an_empty_string() {
+        assert!(Subject::parse_symbols("01a2", 3).is_err());
+        assert!(Subject::parse_symbols(" ", 3).is_err());
+    }
+}
+
+mod is_permutation {
+    use super::*;
+
+    #[test]
+    fn it_returns_true_if_no_symbol_is_repeated() {
+        assert!(Subject::is_permutation(&[2, 0, 1]));
+        assert!(!Subject::is_permutation(&[2, 0, 2]));
+    }
+}
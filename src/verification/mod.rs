@@ -0,0 +1,115 @@
+use crate::problem::Problem;
+use crate::utility::Utility;
+use lehmer::Lehmer;
+use std::fmt;
+
+/// Checks a string with the same conventions as the search so the results can
+/// be compared with its output. Symbols are numbered from zero (strings that
+/// number them from one are shifted down) and distances are measured from the
+/// end of the first permutation, as they are from the seed.
+#[derive(Debug, PartialEq)]
+pub struct Verification {
+    symbols: usize,
+    length: usize,
+    first_appearances: Vec<usize>,
+    missing: Vec<Vec<u8>>,
+}
+
+impl Verification {
+    pub fn new(problem: &Problem, string: &str) -> Result<Self, String> {
+        let symbols = problem.symbols();
+        let string = Utility::parse_symbols(string, symbols)?;
+        let factorial = Utility::factorial(symbols);
+
+        let mut seen = vec![false; factorial];
+        let mut first_appearances = vec![];
+
+        for (position, window) in string.windows(symbols).enumerate() {
+            if !Utility::is_permutation(window) {
+                continue;
+            }
+
+            let index = Lehmer::from_permutation(window).to_decimal();
+
+            if !seen[index] {
+                seen[index] = true;
+                first_appearances.push(position);
+            }
+        }
+
+        let missing = (0..factorial).filter(|i| !seen[*i]).map(|i| {
+            Lehmer::from_decimal(i, symbols).to_permutation()
+        }).collect();
+
+        Ok(Self { symbols, length: string.len(), first_appearances, missing })
+    }
+
+    pub fn symbols(&self) -> usize {
+        self.symbols
+    }
+
+    pub fn len(&self) -> usize {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn number_of_permutations(&self) -> usize {
+        self.first_appearances.len()
+    }
+
+    pub fn is_superpermutation(&self) -> bool {
+        self.missing.is_empty()
+    }
+
+    pub fn missing(&self) -> &[Vec<u8>] {
+        &self.missing
+    }
+
+    /// A character is wasted if it doesn't complete a new permutation, not
+    /// counting the symbols before the first permutation can be completed.
+    pub fn wasted(&self) -> usize {
+        let completable = self.length.saturating_sub(self.symbols - 1);
+        completable - self.number_of_permutations()
+    }
+
+    /// The position of the first symbol of each new permutation.
+    pub fn first_appearances(&self) -> &[usize] {
+        &self.first_appearances
+    }
+
+    /// The distance at which each number of perms is reached, i.e. how far the
+    /// new permutation starts after the first one does.
+    pub fn profile(&self) -> Vec<(usize, usize)> {
+        let first = self.first_appearances.first().copied().unwrap_or(0);
+
+        self.first_appearances.iter().enumerate().map(|(i, position)| {
+            (i + 1, position - first)
+        }).collect()
+    }
+}
+
+impl fmt::Display for Verification {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let factorial = Utility::factorial(self.symbols);
+
+        writeln!(f, "Symbols: {}", self.symbols)?;
+        writeln!(f, "Length: {}", self.length)?;
+        writeln!(f, "Permutations: {} of {}", self.number_of_permutations(), factorial)?;
+        writeln!(f, "Wasted characters: {}", self.wasted())?;
+        writeln!(f, "Superpermutation: {}", if self.is_superpermutation() { "yes" } else { "no" })?;
+        writeln!(f)?;
+        write!(f, "perms distance")?;
+
+        for (perms, distance) in self.profile() {
+            write!(f, "\n{} {}", perms, distance)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test;
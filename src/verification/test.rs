@@ -0,0 +1,138 @@
+use super::*;
+
+type Subject = Verification;
+
+lazy_static! {
+    static ref THREE: Problem = Problem::new(3);
+    static ref FOUR: Problem = Problem::new(4);
+    static ref FIVE: Problem = Problem::new(5);
+}
+
+mod new {
+    use super::*;
+
+    #[test]
+    fn it_verifies_the_minimal_superpermutation_for_three_symbols() {
+        let subject = Subject::new(&THREE, "012010210").unwrap();
+
+        assert_eq!(subject.symbols(), 3);
+        assert_eq!(subject.len(), 9);
+        assert_eq!(subject.number_of_permutations(), 6);
+        assert!(subject.is_superpermutation());
+        assert_eq!(subject.wasted(), 1);
+    }
+
+    #[test]
+    fn it_shifts_strings_that_number_their_symbols_from_one() {
+        let subject = Subject::new(&THREE, "123121321").unwrap();
+
+        assert_eq!(subject, Subject::new(&THREE, "012010210").unwrap());
+    }
+
+    #[test]
+    fn it_ignores_whitespace() {
+        let subject = Subject::new(&THREE, " 0120\n10210\n").unwrap();
+
+        assert_eq!(subject, Subject::new(&THREE, "012010210").unwrap());
+    }
+
+    #[test]
+    fn it_returns_an_error_for_non_digits_or_an_empty_string() {
+        assert!(Subject::new(&THREE, "01a2").is_err());
+        assert!(Subject::new(&THREE, "").is_err());
+    }
+
+    #[test]
+    fn it_returns_an_error_for_digits_that_are_not_one_of_the_symbols() {
+        assert!(Subject::new(&THREE, "0123").is_err());
+    }
+
+    #[test]
+    fn it_uses_the_number_of_symbols_in_the_problem() {
+        let subject = Subject::new(&FIVE, "1234").unwrap();
+
+        assert_eq!(subject.symbols(), 5);
+        assert_eq!(subject.number_of_permutations(), 0);
+        assert!(!subject.is_superpermutation());
+    }
+
+    #[test]
+    fn it_does_not_shift_strings_that_do_not_use_the_last_symbol() {
+        let subject = Subject::new(&FIVE, "11111").unwrap();
+
+        assert_eq!(subject.symbols(), 5);
+        assert_eq!(subject.number_of_permutations(), 0);
+    }
+
+    #[test]
+    fn it_reports_the_permutations_that_are_missing() {
+        let subject = Subject::new(&THREE, "0120102").unwrap();
+
+        assert!(!subject.is_superpermutation());
+        assert_eq!(subject.missing(), &[vec![0, 2, 1], vec![2, 1, 0]]);
+    }
+
+    #[test]
+    fn it_verifies_a_minimal_superpermutation_for_four_symbols() {
+        let subject = Subject::new(&FOUR, "123412314231243121342132413214321").unwrap();
+
+        assert_eq!(subject.number_of_permutations(), 24);
+        assert!(subject.is_superpermutation());
+        assert_eq!(subject.wasted(), 6);
+    }
+}
+
+mod first_appearances {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_position_where_each_new_permutation_starts() {
+        // The window at position 3 (010) isn't a permutation.
+        let subject = Subject::new(&THREE, "0120102").unwrap();
+
+        assert_eq!(subject.first_appearances(), &[0, 1, 2, 4]);
+    }
+}
+
+mod profile {
+    use super::*;
+
+    #[test]
+    fn it_returns_the_distance_at_which_each_number_of_perms_is_reached() {
+        let subject = Subject::new(&THREE, "012010210").unwrap();
+
+        assert_eq!(subject.profile(), &[(1, 0), (2, 1), (3, 2), (4, 4), (5, 5), (6, 6)]);
+    }
+
+    #[test]
+    fn it_measures_distances_from_the_end_of_the_first_permutation() {
+        let subject = Subject::new(&THREE, "00120").unwrap();
+
+        assert_eq!(subject.first_appearances(), &[1, 2]);
+        assert_eq!(subject.profile(), &[(1, 0), (2, 1)]);
+    }
+}
+
+mod fmt {
+    use super::*;
+
+    #[test]
+    fn it_prints_a_summary_followed_by_the_profile() {
+        let subject = Subject::new(&THREE, "012010210").unwrap();
+
+        assert_eq!(subject.to_string(), "\
+Symbols: 3
+Length: 9
+Permutations: 6 of 6
+Wasted characters: 1
+Superpermutation: yes
+
+perms distance
+1 0
+2 1
+3 2
+4 4
+5 5
+6 6");
+    }
+}